@@ -0,0 +1,188 @@
+//! Labels and notes attached to ranges of the source.
+//!
+//! Annotations point at spans of the highlighted code, like "this borrow ends here"
+//! in a tutorial. They are rendered on top of the token stream of
+//! [`highlight_iter()`](crate::highlight::highlight_iter) by the formatters that support them:
+//!
+//! - [`HtmlInline`](crate::formatter::HtmlInline), [`HtmlLinked`](crate::formatter::HtmlLinked)
+//!   and [`HtmlMultiThemes`](crate::formatter::HtmlMultiThemes) wrap the annotated text in
+//!   `<mark class="annotation">` elements with the label and note as a tooltip, followed by a
+//!   numbered `<sup class="annotation-marker">` footnote marker.
+//! - [`Terminal`](crate::formatter::Terminal) draws rustc-style caret underlines with the
+//!   label below each annotated line.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::annotations::{Annotation, AnnotationRange};
+//! use lumis::{TerminalBuilder, ansi::ColorDepth, languages::Language, formatter::Formatter};
+//!
+//! let code = "let x = 1;\nlet r = &x;\n";
+//!
+//! let formatter = TerminalBuilder::new()
+//!     .lang(Language::Rust)
+//!     .annotations(vec![
+//!         Annotation::new(AnnotationRange::LineColumn { start: (2, 9), end: (2, 11) }, "borrow here"),
+//!     ])
+//!     .color_depth(ColorDepth::NoColor)
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut output = Vec::new();
+//! formatter.format(code, &mut output).unwrap();
+//!
+//! assert_eq!(
+//!     String::from_utf8(output).unwrap(),
+//!     "let x = 1;\nlet r = &x;\n        ^^ borrow here\n"
+//! );
+//! ```
+
+use crate::highlight::floor_char_boundary;
+use std::ops::Range;
+
+/// A label, and optionally a longer note, attached to a range of the source.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::annotations::Annotation;
+///
+/// let annotation = Annotation::new(8..10, "borrow here").with_note("ends at the end of the block");
+///
+/// assert_eq!(annotation.byte_range("let r = &x;"), 8..10);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    /// Range of the source the annotation points at.
+    pub range: AnnotationRange,
+    /// Short message displayed next to the annotated text.
+    pub label: String,
+    /// Optional longer explanation.
+    pub note: Option<String>,
+}
+
+impl Annotation {
+    pub fn new(range: impl Into<AnnotationRange>, label: impl Into<String>) -> Self {
+        Self {
+            range: range.into(),
+            label: label.into(),
+            note: None,
+        }
+    }
+
+    /// Attach a note to the annotation.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Resolve the range of the annotation into a byte range of `source`.
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        self.range.byte_range(source)
+    }
+
+    /// Label and note joined by a newline, used as the tooltip of HTML annotations.
+    pub(crate) fn title(&self) -> String {
+        match &self.note {
+            Some(note) => format!("{}\n{}", self.label, note),
+            None => self.label.clone(),
+        }
+    }
+}
+
+/// Range of the source an [`Annotation`] points at.
+///
+/// An empty range points at a position between two characters.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::annotations::AnnotationRange;
+///
+/// let source = "fn main() {\n    let x = 1;\n}\n";
+/// let range = AnnotationRange::LineColumn { start: (2, 9), end: (2, 10) };
+///
+/// assert_eq!(range.byte_range(source), 20..21);
+/// assert_eq!(&source[range.byte_range(source)], "x");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AnnotationRange {
+    /// Byte range into the source.
+    Bytes(Range<usize>),
+    /// `(line, column)` positions, both 1-based with columns counted in characters.
+    ///
+    /// The end position is exclusive. Columns past the end of a line are clamped to it.
+    LineColumn {
+        start: (usize, usize),
+        end: (usize, usize),
+    },
+}
+
+impl AnnotationRange {
+    /// Resolve the range into a byte range of `source`.
+    ///
+    /// Ranges are clamped to the source length and byte offsets are moved back to the
+    /// nearest char boundary.
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        let (start, end) = match self {
+            AnnotationRange::Bytes(bytes) => (
+                floor_char_boundary(source, bytes.start),
+                floor_char_boundary(source, bytes.end),
+            ),
+            AnnotationRange::LineColumn { start, end } => {
+                (byte_offset(source, *start), byte_offset(source, *end))
+            }
+        };
+
+        start.min(end)..end
+    }
+}
+
+impl From<Range<usize>> for AnnotationRange {
+    fn from(range: Range<usize>) -> Self {
+        AnnotationRange::Bytes(range)
+    }
+}
+
+fn byte_offset(source: &str, (line, column): (usize, usize)) -> usize {
+    let Some(line_start) = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+        .nth(line.max(1) - 1)
+    else {
+        return source.len();
+    };
+
+    let line = source[line_start..].split('\n').next().unwrap_or_default();
+    let column = line
+        .char_indices()
+        .nth(column.max(1) - 1)
+        .map_or(line.len(), |(offset, _)| offset);
+
+    line_start + column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let source = "ab\nçd\n";
+        let range = |start, end| AnnotationRange::LineColumn { start, end }.byte_range(source);
+
+        assert_eq!(range((1, 1), (1, 3)), 0..2);
+        assert_eq!(range((2, 2), (2, 3)), 5..6);
+        // Columns past the end of the line stop at the newline
+        assert_eq!(range((1, 2), (1, 99)), 1..2);
+        // Lines past the end of the source
+        assert_eq!(range((9, 1), (9, 2)), 7..7);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let source = "çd";
+
+        assert_eq!(AnnotationRange::Bytes(1..9).byte_range(source), 0..3);
+        assert_eq!(AnnotationRange::Bytes(1..2).byte_range(source), 0..2);
+    }
+}
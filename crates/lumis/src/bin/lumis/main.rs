@@ -1,8 +1,9 @@
 mod gen_theme;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use lumis::formatter::Formatter as FormatterTrait;
+use lumis::highlight::SourceRange;
 use lumis::languages::Language;
 use std::fmt::Display;
 use std::fs;
@@ -15,6 +16,12 @@ use strum::IntoEnumIterator;
 /// This binary provides command-line access to Lumis's syntax highlighting capabilities.
 #[derive(Parser)]
 #[command(version)]
+#[cfg_attr(
+    not(feature = "image"),
+    command(
+        after_help = "The `image` command to render PNG screenshots requires the `image` feature:\n  cargo install lumis --features image"
+    )
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -44,7 +51,7 @@ enum Commands {
         /// Path to the file to highlight
         path: String,
 
-        /// Output format (terminal, html-inline, html-linked, html-multi-themes)
+        /// Output format (terminal, html-inline, html-linked, html-multi-themes, svg, latex, rtf, json)
         #[arg(short = 'f', long)]
         formatter: Option<Formatter>,
 
@@ -52,7 +59,7 @@ enum Commands {
         #[arg(short = 't', long)]
         theme: Option<String>,
 
-        /// Multiple themes with format "name:theme_id" (can be repeated)
+        /// Multiple themes with format "name:theme_id" (can be repeated, rendered in order)
         #[arg(long)]
         themes: Vec<String>,
 
@@ -67,6 +74,16 @@ enum Commands {
         /// Highlight lines
         #[arg(short = 'l', long)]
         highlight_lines: Option<String>,
+
+        /// Only render a range of lines, e.g. "40:80", "40:" or ":80"
+        #[arg(long)]
+        lines: Option<String>,
+
+        #[command(flatten)]
+        line_numbers: LineNumberArgs,
+
+        #[command(flatten)]
+        terminal: TerminalArgs,
     },
 
     /// Highlight a string of source code
@@ -78,7 +95,7 @@ enum Commands {
         #[arg(short = 'l', long)]
         language: Option<String>,
 
-        /// Output format (terminal, html-inline, html-linked, html-multi-themes)
+        /// Output format (terminal, html-inline, html-linked, html-multi-themes, svg, latex, rtf, json)
         #[arg(short = 'f', long)]
         formatter: Option<Formatter>,
 
@@ -86,7 +103,7 @@ enum Commands {
         #[arg(short = 't', long)]
         theme: Option<String>,
 
-        /// Multiple themes with format "name:theme_id" (can be repeated)
+        /// Multiple themes with format "name:theme_id" (can be repeated, rendered in order)
         #[arg(long)]
         themes: Vec<String>,
 
@@ -101,6 +118,114 @@ enum Commands {
         /// Highlight lines
         #[arg(long)]
         highlight_lines: Option<String>,
+
+        /// Only render a range of lines, e.g. "40:80", "40:" or ":80"
+        #[arg(long)]
+        lines: Option<String>,
+
+        #[command(flatten)]
+        line_numbers: LineNumberArgs,
+
+        #[command(flatten)]
+        terminal: TerminalArgs,
+    },
+
+    /// Highlight a unified diff, or compare two files side by side
+    Diff {
+        /// Path to the diff file, or "-" to read from stdin. The old file when comparing two files
+        path: String,
+
+        /// Path to the new file, to compare it side by side with the old file
+        new_path: Option<String>,
+
+        /// Output format (terminal or html-inline)
+        #[arg(short = 'f', long)]
+        formatter: Option<Formatter>,
+
+        /// Theme name (e.g., "dracula", "github_dark")
+        #[arg(short = 't', long)]
+        theme: Option<String>,
+
+        /// Show the old and new line numbers (always shown when comparing two files)
+        #[arg(long)]
+        line_numbers: bool,
+
+        /// Color depth for terminal output
+        #[arg(long, value_enum, default_value_t = ColorDepth::Auto)]
+        color_depth: ColorDepth,
+    },
+
+    /// Render a file as a PNG image
+    #[cfg(feature = "image")]
+    Image {
+        /// Path to the file to render
+        path: String,
+
+        /// Output file path (writes to stdout if not specified)
+        #[arg(short = 'o', long)]
+        output: Option<String>,
+
+        /// Theme name (e.g., "dracula", "github_dark")
+        #[arg(short = 't', long)]
+        theme: Option<String>,
+
+        /// Font size in logical pixels
+        #[arg(long, default_value_t = 14.0)]
+        font_size: f32,
+
+        /// Space in logical pixels between the code and the edges of the window
+        #[arg(long, default_value_t = 24.0)]
+        padding: f32,
+
+        /// Pixel density multiplier
+        #[arg(long, default_value_t = 2.0)]
+        scale: f32,
+
+        /// Render line numbers
+        #[arg(long)]
+        line_numbers: bool,
+
+        /// Do not draw the window title bar and rounded corners
+        #[arg(long)]
+        no_window_chrome: bool,
+
+        /// Do not draw the drop shadow
+        #[arg(long)]
+        no_shadow: bool,
+
+        /// Only render a range of lines, e.g. "40:80", "40:" or ":80"
+        #[arg(long)]
+        lines: Option<String>,
+    },
+
+    /// Generate a stylesheet for html-linked output from a theme
+    Css {
+        /// Theme name or path to a theme JSON file, the light theme when used with --dark
+        theme: String,
+
+        /// Dark theme name or path to a theme JSON file, combined with THEME
+        #[arg(long)]
+        dark: Option<String>,
+
+        /// How the dark theme is selected when used with --dark
+        #[arg(long, value_enum, default_value_t = ColorScheme::MediaQuery)]
+        color_scheme: ColorScheme,
+
+        /// Selector of the <pre> elements, also scoping the rules of their content
+        #[arg(long, default_value = "pre.lumis")]
+        root_selector: String,
+
+        /// Prefix of the classes of the scopes, e.g. "hl-" for ".hl-keyword"
+        #[arg(long, default_value = "")]
+        class_prefix: String,
+
+        /// Leave out the italic styles of the theme
+        #[arg(long)]
+        no_italic: bool,
+
+        /// Output file path (prints to stdout if not specified)
+        #[arg(short = 'o', long)]
+        output: Option<String>,
     },
 
     /// Generate a theme JSON from a Git repository containing a Neovim theme
@@ -139,6 +264,104 @@ enum Formatter {
     /// ANSI colored output for terminal (default)
     #[default]
     Terminal,
+    /// SVG image
+    Svg,
+    /// Standalone LaTeX document
+    Latex,
+    /// Rich Text Format for pasting into office apps
+    Rtf,
+    /// JSON token stream
+    Json,
+}
+
+/// Options for the line number gutter
+#[derive(Args, Clone)]
+struct LineNumberArgs {
+    /// Show line numbers
+    #[arg(long)]
+    line_numbers: bool,
+
+    /// Number of the first line
+    #[arg(long, default_value_t = 1)]
+    line_number_start: usize,
+}
+
+/// Options for the terminal formatter
+#[derive(Args, Clone)]
+struct TerminalArgs {
+    /// Color depth for terminal output
+    #[arg(long, value_enum, default_value_t = ColorDepth::Auto)]
+    color_depth: ColorDepth,
+
+    /// Separator between the line numbers and the code
+    #[arg(long, default_value = "│")]
+    gutter_separator: String,
+
+    /// Show a header with the file name (or language) in terminal output
+    #[arg(long)]
+    header: bool,
+
+    /// Paint the theme background behind every cell in terminal output
+    #[arg(long)]
+    background: bool,
+
+    /// Width in columns to pad lines to with --background and to wrap them at with --wrap
+    /// (defaults to the terminal width)
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Soft-wrap long lines in terminal output, marking continuation lines with ↪
+    #[arg(long)]
+    wrap: bool,
+}
+
+/// How the dark theme of a stylesheet is selected
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ColorScheme {
+    /// With the prefers-color-scheme media query (default)
+    #[default]
+    MediaQuery,
+    /// With a data-theme="dark" attribute on an ancestor element
+    DataTheme,
+}
+
+impl From<ColorScheme> for lumis::css::ColorScheme {
+    fn from(scheme: ColorScheme) -> Self {
+        match scheme {
+            ColorScheme::MediaQuery => Self::MediaQuery,
+            ColorScheme::DataTheme => Self::DataTheme,
+        }
+    }
+}
+
+/// Color depth options for terminal output
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ColorDepth {
+    /// Detect from COLORTERM, TERM, NO_COLOR and CLICOLOR_FORCE (default)
+    #[default]
+    Auto,
+    /// 24-bit colors
+    Truecolor,
+    /// 256 color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// 16 standard colors
+    #[value(name = "16")]
+    Ansi16,
+    /// No colors
+    None,
+}
+
+impl From<ColorDepth> for lumis::ansi::ColorDepth {
+    fn from(depth: ColorDepth) -> Self {
+        match depth {
+            ColorDepth::Auto => Self::Auto,
+            ColorDepth::Truecolor => Self::TrueColor,
+            ColorDepth::Ansi256 => Self::Ansi256,
+            ColorDepth::Ansi16 => Self::Ansi16,
+            ColorDepth::None => Self::NoColor,
+        }
+    }
 }
 
 /// Entry point for the lumis CLI
@@ -159,6 +382,9 @@ fn main() -> Result<()> {
             default_theme,
             css_variable_prefix,
             highlight_lines,
+            lines,
+            line_numbers,
+            terminal,
         } => highlight(
             &path,
             formatter,
@@ -167,6 +393,9 @@ fn main() -> Result<()> {
             default_theme,
             css_variable_prefix,
             highlight_lines,
+            lines,
+            line_numbers,
+            terminal,
         ),
         Commands::HighlightSource {
             source,
@@ -177,6 +406,9 @@ fn main() -> Result<()> {
             default_theme,
             css_variable_prefix,
             highlight_lines,
+            lines,
+            line_numbers,
+            terminal,
         } => highlight_source(
             &source,
             language.as_deref(),
@@ -186,6 +418,65 @@ fn main() -> Result<()> {
             default_theme,
             css_variable_prefix,
             highlight_lines,
+            lines,
+            line_numbers,
+            terminal,
+        ),
+        Commands::Diff {
+            path,
+            new_path,
+            formatter,
+            theme,
+            line_numbers,
+            color_depth,
+        } => diff(
+            &path,
+            new_path.as_deref(),
+            formatter,
+            theme,
+            line_numbers,
+            color_depth,
+        ),
+        #[cfg(feature = "image")]
+        Commands::Image {
+            path,
+            output,
+            theme,
+            font_size,
+            padding,
+            scale,
+            line_numbers,
+            no_window_chrome,
+            no_shadow,
+            lines,
+        } => image(
+            &path,
+            output.as_deref(),
+            theme,
+            font_size,
+            padding,
+            scale,
+            line_numbers,
+            !no_window_chrome,
+            !no_shadow,
+            lines,
+        ),
+        Commands::Css {
+            theme,
+            dark,
+            color_scheme,
+            root_selector,
+            class_prefix,
+            no_italic,
+            output,
+        } => css(
+            &theme,
+            dark.as_deref(),
+            color_scheme,
+            root_selector,
+            class_prefix,
+            !no_italic,
+            output.as_deref(),
         ),
         Commands::GenTheme {
             url,
@@ -380,9 +671,12 @@ fn print_cursor(src: &str, cursor: &mut tree_sitter::TreeCursor, depth: usize, c
 ///
 /// # Arguments
 /// * `path` - Path to the file to highlight
-/// * `formatter` - Output format (terminal, html-inline, html-linked)
+/// * `formatter` - Output format (terminal, html-inline, html-linked, svg, latex, rtf, json)
 /// * `theme` - Theme name to use for highlighting
 /// * `highlight_lines` - Optional string specifying lines to highlight (e.g., "1,3-5,8")
+/// * `lines` - Optional range of lines to render (e.g., "40:80")
+/// * `terminal` - Options for the terminal formatter
+#[allow(clippy::too_many_arguments)]
 fn highlight(
     path: &str,
     formatter: Option<Formatter>,
@@ -391,6 +685,9 @@ fn highlight(
     default_theme: Option<String>,
     css_variable_prefix: String,
     highlight_lines: Option<String>,
+    lines: Option<String>,
+    line_numbers: LineNumberArgs,
+    terminal: TerminalArgs,
 ) -> Result<()> {
     let theme = theme.unwrap_or("catppuccin_frappe".to_string());
     let theme = lumis::themes::get(&theme).ok();
@@ -400,6 +697,8 @@ fn highlight(
     } else {
         None
     };
+    let range = lines.map(|lines| parse_line_range(&lines)).transpose()?;
+    let formatter = formatter.unwrap_or_default();
 
     let bytes = read_or_die(Path::new(&path));
     let source = std::str::from_utf8(&bytes)
@@ -407,31 +706,25 @@ fn highlight(
 
     let language = lumis::languages::Language::guess(Some(path), source);
 
-    match formatter.unwrap_or_default() {
+    match formatter {
         Formatter::HtmlInline => {
-            let formatter = if let Some(lines) = parsed_highlight_lines {
-                let html_highlight_lines = lumis::formatter::html_inline::HighlightLines {
+            let html_highlight_lines =
+                parsed_highlight_lines.map(|lines| lumis::formatter::html_inline::HighlightLines {
                     lines,
                     style: Some(lumis::formatter::html_inline::HighlightLinesStyle::Theme),
                     class: None,
-                };
-                lumis::HtmlInlineBuilder::new()
-                    .lang(language)
-                    .theme(theme)
-                    .italic(false)
-                    .include_highlights(false)
-                    .highlight_lines(Some(html_highlight_lines))
-                    .build()
-                    .unwrap()
-            } else {
-                lumis::HtmlInlineBuilder::new()
-                    .lang(language)
-                    .theme(theme)
-                    .italic(false)
-                    .include_highlights(false)
-                    .build()
-                    .unwrap()
-            };
+                });
+            let formatter = lumis::HtmlInlineBuilder::new()
+                .lang(language)
+                .theme(theme)
+                .italic(false)
+                .include_highlights(false)
+                .highlight_lines(html_highlight_lines)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
 
             let mut output = Vec::new();
             formatter.format(source, &mut output).unwrap();
@@ -447,7 +740,7 @@ fn highlight(
                 ));
             }
 
-            let mut theme_map = std::collections::HashMap::new();
+            let mut theme_map = Vec::new();
             for theme_spec in themes {
                 let parts: Vec<&str> = theme_spec.split(':').collect();
                 if parts.len() != 2 {
@@ -460,7 +753,7 @@ fn highlight(
                 let theme_id = parts[1];
                 let theme_obj = lumis::themes::get(theme_id)
                     .map_err(|_| anyhow::anyhow!("Theme '{}' not found", theme_id))?;
-                theme_map.insert(theme_name, theme_obj);
+                theme_map.push((theme_name, theme_obj));
             }
 
             let formatter = {
@@ -468,7 +761,10 @@ fn highlight(
                 builder
                     .lang(language)
                     .themes(theme_map)
-                    .css_variable_prefix(css_variable_prefix);
+                    .css_variable_prefix(css_variable_prefix)
+                    .line_numbers(line_numbers.line_numbers)
+                    .line_number_start(line_numbers.line_number_start)
+                    .range(range);
 
                 if let Some(default) = default_theme {
                     builder.default_theme(default);
@@ -493,22 +789,19 @@ fn highlight(
         }
 
         Formatter::HtmlLinked => {
-            let formatter = if let Some(lines) = parsed_highlight_lines {
-                let html_highlight_lines = lumis::formatter::html_linked::HighlightLines {
+            let html_highlight_lines =
+                parsed_highlight_lines.map(|lines| lumis::formatter::html_linked::HighlightLines {
                     lines,
                     class: "highlighted".to_string(),
-                };
-                lumis::HtmlLinkedBuilder::new()
-                    .lang(language)
-                    .highlight_lines(Some(html_highlight_lines))
-                    .build()
-                    .unwrap()
-            } else {
-                lumis::HtmlLinkedBuilder::new()
-                    .lang(language)
-                    .build()
-                    .unwrap()
-            };
+                });
+            let formatter = lumis::HtmlLinkedBuilder::new()
+                .lang(language)
+                .highlight_lines(html_highlight_lines)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
 
             let mut output = Vec::new();
             formatter.format(source, &mut output).unwrap();
@@ -521,6 +814,16 @@ fn highlight(
             let formatter = lumis::TerminalBuilder::new()
                 .lang(language)
                 .theme(theme)
+                .color_depth(terminal.color_depth.into())
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .gutter_separator(terminal.gutter_separator)
+                .highlight_lines(parsed_highlight_lines.unwrap_or_default())
+                .header(terminal.header.then(|| path.to_string()))
+                .background(terminal.background)
+                .width(terminal.width)
+                .soft_wrap(terminal.wrap)
+                .range(range)
                 .build()
                 .unwrap();
 
@@ -530,11 +833,277 @@ fn highlight(
 
             println!("{highlighted}");
         }
+
+        Formatter::Svg => {
+            let formatter = lumis::SvgBuilder::new()
+                .lang(language)
+                .theme(theme)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+
+        Formatter::Latex => {
+            println!("\\documentclass{{article}}");
+            if let Some(theme) = &theme {
+                print!("{}", lumis::formatter::latex::preamble(theme));
+            }
+            println!("\\begin{{document}}");
+
+            let formatter = lumis::LatexBuilder::new()
+                .lang(language)
+                .theme(theme)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+            println!("\\end{{document}}");
+        }
+
+        Formatter::Rtf => {
+            let formatter = lumis::RtfBuilder::new()
+                .lang(language)
+                .theme(theme)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+
+        Formatter::Json => {
+            let formatter = lumis::JsonBuilder::new()
+                .lang(language)
+                .theme(theme)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Highlights a unified diff, or compares two files side by side
+///
+/// # Arguments
+/// * `path` - Path to the diff file, or "-" to read from stdin. The old file when `new_path` is given
+/// * `new_path` - Path to the new file to compare side by side with `path`
+/// * `formatter` - Output format (terminal or html-inline)
+/// * `theme` - Theme name to use for highlighting
+/// * `line_numbers` - Whether to show the old and new line numbers
+/// * `color_depth` - Color depth for terminal output
+fn diff(
+    path: &str,
+    new_path: Option<&str>,
+    formatter: Option<Formatter>,
+    theme: Option<String>,
+    line_numbers: bool,
+    color_depth: ColorDepth,
+) -> Result<()> {
+    use std::io::Read;
+
+    let theme = theme.unwrap_or("catppuccin_frappe".to_string());
+    let theme = lumis::themes::get(&theme).ok();
+
+    let bytes = if path == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        read_or_die(Path::new(&path))
+    };
+    let source = std::str::from_utf8(&bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to decode file '{}' as UTF-8: {}", path, e))?;
+
+    let new_source = match new_path {
+        Some(new_path) => {
+            let bytes = read_or_die(Path::new(new_path));
+            let source = String::from_utf8(bytes).map_err(|e| {
+                anyhow::anyhow!("Failed to decode file '{}' as UTF-8: {}", new_path, e)
+            })?;
+            Some((Language::guess(Some(new_path), &source), source))
+        }
+        None => None,
+    };
+
+    let mut output = Vec::new();
+
+    match formatter.unwrap_or_default() {
+        Formatter::Terminal => {
+            let formatter = lumis::TerminalDiffBuilder::new()
+                .theme(theme)
+                .line_numbers(line_numbers)
+                .color_depth(color_depth.into())
+                .build()
+                .unwrap();
+
+            match &new_source {
+                Some((language, new_source)) => formatter
+                    .format_side_by_side(source, new_source, *language, &mut output)
+                    .unwrap(),
+                None => formatter.format(source, &mut output).unwrap(),
+            }
+        }
+        Formatter::HtmlInline => {
+            let formatter = lumis::HtmlDiffBuilder::new()
+                .theme(theme)
+                .line_numbers(line_numbers)
+                .build()
+                .unwrap();
+
+            match &new_source {
+                Some((language, new_source)) => formatter
+                    .format_side_by_side(source, new_source, *language, &mut output)
+                    .unwrap(),
+                None => formatter.format(source, &mut output).unwrap(),
+            }
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "lumis diff only supports the terminal and html-inline formatters"
+            ))
+        }
+    }
+
+    let highlighted = String::from_utf8(output).unwrap();
+
+    print!("{highlighted}");
+
+    Ok(())
+}
+
+/// Renders a file as a PNG image
+///
+/// # Arguments
+/// * `path` - Path to the file to render
+/// * `output` - Output file path, or `None` to write to stdout
+/// * `theme` - Theme name to use for highlighting
+#[cfg(feature = "image")]
+#[allow(clippy::too_many_arguments)]
+fn image(
+    path: &str,
+    output: Option<&str>,
+    theme: Option<String>,
+    font_size: f32,
+    padding: f32,
+    scale: f32,
+    line_numbers: bool,
+    window_chrome: bool,
+    shadow: bool,
+    lines: Option<String>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let range = lines.map(|lines| parse_line_range(&lines)).transpose()?;
+
+    let theme = theme.unwrap_or("catppuccin_frappe".to_string());
+    let theme = lumis::themes::get(&theme).ok();
+
+    let bytes = read_or_die(Path::new(&path));
+    let source = std::str::from_utf8(&bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to decode file '{}' as UTF-8: {}", path, e))?;
+
+    let language = lumis::languages::Language::guess(Some(path), source);
+
+    let formatter = lumis::PngBuilder::new()
+        .lang(language)
+        .theme(theme)
+        .font_size(font_size)
+        .padding(padding)
+        .scale(scale)
+        .line_numbers(line_numbers)
+        .window_chrome(window_chrome)
+        .shadow(shadow)
+        .range(range)
+        .build()
+        .unwrap();
+
+    let png = formatter.render(source)?;
+
+    match output {
+        Some(output) => fs::write(output, png)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", output, e))?,
+        None => std::io::stdout().write_all(&png)?,
+    }
+
+    Ok(())
+}
+
+/// Generates a stylesheet from a theme, or from a light and a dark theme
+///
+/// # Arguments
+/// * `theme` - Theme name or path to a theme JSON file
+/// * `dark` - Dark theme name or path, combined with `theme` as the light theme
+/// * `color_scheme` - How the dark theme is selected
+/// * `root_selector` - Selector of the `<pre>` elements
+/// * `class_prefix` - Prefix of the classes of the scopes
+/// * `italic` - Whether to include the italic styles
+/// * `output` - Output file path, or `None` to write to stdout
+fn css(
+    theme: &str,
+    dark: Option<&str>,
+    color_scheme: ColorScheme,
+    root_selector: String,
+    class_prefix: String,
+    italic: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    let stylesheet = lumis::css::StylesheetBuilder::new()
+        .root_selector(root_selector)
+        .class_prefix(class_prefix)
+        .italic(italic)
+        .build()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let theme = load_theme(theme)?;
+    let css = match dark {
+        Some(dark) => stylesheet.light_dark_css(&theme, &load_theme(dark)?, color_scheme.into()),
+        None => stylesheet.css(&theme),
+    };
+
+    match output {
+        Some(output) => fs::write(output, css)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", output, e))?,
+        None => print!("{}", css),
     }
 
     Ok(())
 }
 
+/// Loads a theme by name, or from a JSON file when `theme` is an existing path
+fn load_theme(theme: &str) -> Result<lumis::themes::Theme> {
+    if Path::new(theme).is_file() {
+        return lumis::themes::from_file(theme)
+            .map_err(|e| anyhow::anyhow!("Failed to load theme '{}': {}", theme, e));
+    }
+
+    lumis::themes::get(theme).map_err(|_| anyhow::anyhow!("Theme '{}' not found", theme))
+}
+
 const EXIT_BAD_ARGUMENTS: i32 = 2;
 
 /// Reads a file or exits with an error message
@@ -653,6 +1222,53 @@ fn parse_highlight_lines(input: &str) -> Result<Vec<RangeInclusive<usize>>> {
     Ok(ranges)
 }
 
+/// Parses a lines string into a `SourceRange`
+///
+/// Supports formats like:
+/// - "40:80" (lines 40 to 80)
+/// - "40:" (line 40 to the end of the file)
+/// - ":80" (start of the file to line 80)
+/// - "40" (only line 40)
+///
+/// # Arguments
+/// * `input` - The string to parse
+fn parse_line_range(input: &str) -> Result<SourceRange> {
+    let parse = |line: &str, default: usize| -> Result<usize> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(default);
+        }
+        let line: usize = line
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid line number: '{}'", line))?;
+        if line == 0 {
+            return Err(anyhow::anyhow!("Line numbers must be greater than 0"));
+        }
+        Ok(line)
+    };
+
+    let (start, end) = match input.split_once(':') {
+        Some((start, end)) => (parse(start, 1)?, parse(end, usize::MAX)?),
+        None => {
+            let line = parse(input, 0)?;
+            (line, line)
+        }
+    };
+
+    if start == 0 {
+        return Err(anyhow::anyhow!("Invalid line range: '{}'", input));
+    }
+    if start > end {
+        return Err(anyhow::anyhow!(
+            "Start line ({}) must be less than or equal to end line ({})",
+            start,
+            end
+        ));
+    }
+
+    Ok(SourceRange::Lines(start..=end))
+}
+
 /// Converts an absolute path to a path relative to the current directory
 ///
 /// # Arguments
@@ -683,9 +1299,11 @@ fn try_canonicalize(path: &Path) -> PathBuf {
 /// # Arguments
 /// * `source` - The source code to highlight
 /// * `language` - Programming language for the source code
-/// * `formatter` - Output format (terminal, html-inline, html-linked)
+/// * `formatter` - Output format (terminal, html-inline, html-linked, svg, latex, rtf, json)
 /// * `theme` - Theme name to use for highlighting
 /// * `highlight_lines` - Optional string specifying lines to highlight (e.g., "1,3-5,8")
+/// * `lines` - Optional range of lines to render (e.g., "40:80")
+/// * `terminal` - Options for the terminal formatter
 #[allow(clippy::too_many_arguments)]
 fn highlight_source(
     source: &str,
@@ -696,6 +1314,9 @@ fn highlight_source(
     default_theme: Option<String>,
     css_variable_prefix: String,
     highlight_lines: Option<String>,
+    lines: Option<String>,
+    line_numbers: LineNumberArgs,
+    terminal: TerminalArgs,
 ) -> Result<()> {
     let theme = theme.unwrap_or("catppuccin_frappe".to_string());
     let theme = lumis::themes::get(&theme).ok();
@@ -705,34 +1326,30 @@ fn highlight_source(
     } else {
         None
     };
+    let range = lines.map(|lines| parse_line_range(&lines)).transpose()?;
+    let formatter = formatter.unwrap_or_default();
 
     let lang = lumis::languages::Language::guess(language, source);
 
-    match formatter.unwrap_or_default() {
+    match formatter {
         Formatter::HtmlInline => {
-            let formatter = if let Some(lines) = parsed_highlight_lines {
-                let html_highlight_lines = lumis::formatter::html_inline::HighlightLines {
+            let html_highlight_lines =
+                parsed_highlight_lines.map(|lines| lumis::formatter::html_inline::HighlightLines {
                     lines,
                     style: Some(lumis::formatter::html_inline::HighlightLinesStyle::Theme),
                     class: None,
-                };
-                lumis::HtmlInlineBuilder::new()
-                    .lang(lang)
-                    .theme(theme)
-                    .italic(false)
-                    .include_highlights(false)
-                    .highlight_lines(Some(html_highlight_lines))
-                    .build()
-                    .unwrap()
-            } else {
-                lumis::HtmlInlineBuilder::new()
-                    .lang(lang)
-                    .theme(theme)
-                    .italic(false)
-                    .include_highlights(false)
-                    .build()
-                    .unwrap()
-            };
+                });
+            let formatter = lumis::HtmlInlineBuilder::new()
+                .lang(lang)
+                .theme(theme)
+                .italic(false)
+                .include_highlights(false)
+                .highlight_lines(html_highlight_lines)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
 
             let mut output = Vec::new();
             formatter.format(source, &mut output).unwrap();
@@ -742,19 +1359,19 @@ fn highlight_source(
         }
 
         Formatter::HtmlLinked => {
-            let formatter = if let Some(lines) = parsed_highlight_lines {
-                let html_highlight_lines = lumis::formatter::html_linked::HighlightLines {
+            let html_highlight_lines =
+                parsed_highlight_lines.map(|lines| lumis::formatter::html_linked::HighlightLines {
                     lines,
                     class: "highlighted".to_string(),
-                };
-                lumis::HtmlLinkedBuilder::new()
-                    .lang(lang)
-                    .highlight_lines(Some(html_highlight_lines))
-                    .build()
-                    .unwrap()
-            } else {
-                lumis::HtmlLinkedBuilder::new().lang(lang).build().unwrap()
-            };
+                });
+            let formatter = lumis::HtmlLinkedBuilder::new()
+                .lang(lang)
+                .highlight_lines(html_highlight_lines)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
 
             let mut output = Vec::new();
             formatter.format(source, &mut output).unwrap();
@@ -769,7 +1386,7 @@ fn highlight_source(
                 std::process::exit(1);
             }
 
-            let mut theme_map = std::collections::HashMap::new();
+            let mut theme_map = Vec::new();
             for theme_spec in themes {
                 let parts: Vec<&str> = theme_spec.split(':').collect();
                 if parts.len() != 2 {
@@ -788,7 +1405,7 @@ fn highlight_source(
                         std::process::exit(1);
                     }
                 };
-                theme_map.insert(theme_name, theme_obj);
+                theme_map.push((theme_name, theme_obj));
             }
 
             let formatter = {
@@ -796,7 +1413,10 @@ fn highlight_source(
                 builder
                     .lang(lang)
                     .themes(theme_map)
-                    .css_variable_prefix(css_variable_prefix);
+                    .css_variable_prefix(css_variable_prefix)
+                    .line_numbers(line_numbers.line_numbers)
+                    .line_number_start(line_numbers.line_number_start)
+                    .range(range);
 
                 if let Some(default) = default_theme {
                     builder.default_theme(default);
@@ -830,6 +1450,85 @@ fn highlight_source(
             let formatter = lumis::TerminalBuilder::new()
                 .lang(lang)
                 .theme(theme)
+                .color_depth(terminal.color_depth.into())
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .gutter_separator(terminal.gutter_separator)
+                .highlight_lines(parsed_highlight_lines.unwrap_or_default())
+                .header(terminal.header.then(|| lang.name().to_string()))
+                .background(terminal.background)
+                .width(terminal.width)
+                .soft_wrap(terminal.wrap)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+
+        Formatter::Svg => {
+            let formatter = lumis::SvgBuilder::new()
+                .lang(lang)
+                .theme(theme)
+                .line_numbers(line_numbers.line_numbers)
+                .line_number_start(line_numbers.line_number_start)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+
+        Formatter::Latex => {
+            println!("\\documentclass{{article}}");
+            if let Some(theme) = &theme {
+                print!("{}", lumis::formatter::latex::preamble(theme));
+            }
+            println!("\\begin{{document}}");
+
+            let formatter = lumis::LatexBuilder::new()
+                .lang(lang)
+                .theme(theme)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+            println!("\\end{{document}}");
+        }
+
+        Formatter::Rtf => {
+            let formatter = lumis::RtfBuilder::new()
+                .lang(lang)
+                .theme(theme)
+                .range(range)
+                .build()
+                .unwrap();
+
+            let mut output = Vec::new();
+            formatter.format(source, &mut output).unwrap();
+            let highlighted = String::from_utf8(output).unwrap();
+
+            println!("{highlighted}");
+        }
+
+        Formatter::Json => {
+            let formatter = lumis::JsonBuilder::new()
+                .lang(lang)
+                .theme(theme)
+                .range(range)
                 .build()
                 .unwrap();
 
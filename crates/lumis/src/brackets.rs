@@ -0,0 +1,343 @@
+//! Rainbow brackets: matching bracket pairs colored by nesting depth.
+//!
+//! Deeply nested code, like Lisp, Clojure, Elixir or JSON, is easier to read when each pair
+//! of brackets gets its own color. Pairs are matched on the parse tree rather than by counting
+//! characters, so brackets inside strings and comments are left alone, and unbalanced brackets
+//! keep their regular highlight.
+//!
+//! All the formatters accept a `rainbow_brackets` option taking a [`RainbowBrackets`] palette.
+//! The default palette is derived from the theme with [`Theme::bracket_palette()`], and the
+//! theme CSS files include `.rainbow-bracket-N` rules for [`HtmlLinked`](crate::formatter::HtmlLinked).
+//!
+//! Only the brackets of the main language are colored, not the ones of injected languages.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::brackets::RainbowBrackets;
+//! use lumis::{HtmlInlineBuilder, languages::Language, formatter::Formatter};
+//!
+//! let formatter = HtmlInlineBuilder::new()
+//!     .lang(Language::JSON)
+//!     .rainbow_brackets(Some(RainbowBrackets::with_palette(["#ff0000", "#00ff00"])))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut output = Vec::new();
+//! formatter.format(r#"{"a": [1]}"#, &mut output).unwrap();
+//! let html = String::from_utf8(output).unwrap();
+//!
+//! assert!(html.contains(r#"<span style="color: #ff0000;">&lbrace;</span>"#));
+//! assert!(html.contains(r#"<span style="color: #00ff00;">[</span>"#));
+//! ```
+
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::themes::Theme;
+use std::ops::Range;
+use tree_sitter::{Node, Parser, TreeCursor};
+
+/// Number of colors of the palettes derived from themes, and of the `.rainbow-bracket-N`
+/// rules in the theme CSS files.
+pub const PALETTE_SIZE: usize = 6;
+
+/// Palette used when there's no theme to derive one from.
+pub const DEFAULT_PALETTE: [&str; PALETTE_SIZE] = [
+    "#ffd700", "#da70d6", "#179fff", "#ff8c00", "#3ac93a", "#ff6f6f",
+];
+
+/// Colors of rainbow brackets, cycled through by nesting depth.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::brackets::RainbowBrackets;
+/// use lumis::themes;
+///
+/// let theme = themes::get("dracula").unwrap();
+///
+/// // Derived from the theme by default
+/// assert_eq!(RainbowBrackets::default().palette(Some(&theme)), theme.bracket_palette());
+///
+/// let custom = RainbowBrackets::with_palette(["#ff0000", "#00ff00"]);
+/// assert_eq!(custom.palette(Some(&theme)), vec!["#ff0000", "#00ff00"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RainbowBrackets {
+    /// Hex colors by nesting depth, the outermost pair first.
+    ///
+    /// When empty, the palette is derived from the theme.
+    pub palette: Vec<String>,
+}
+
+impl RainbowBrackets {
+    /// Use a custom palette instead of the one derived from the theme.
+    pub fn with_palette<I, S>(palette: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            palette: palette.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Resolve the colors used with `theme`.
+    pub fn palette(&self, theme: Option<&Theme>) -> Vec<String> {
+        if !self.palette.is_empty() {
+            return self.palette.clone();
+        }
+
+        match theme {
+            Some(theme) => theme.bracket_palette(),
+            None => DEFAULT_PALETTE
+                .iter()
+                .map(|color| color.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// A bracket of a matching pair.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bracket {
+    /// Byte range of the bracket in the source.
+    pub range: Range<usize>,
+    /// Nesting depth of the pair, starting at 0 for the outermost pairs.
+    pub depth: usize,
+}
+
+/// Find the matching bracket pairs of `source`, sorted by position.
+///
+/// Brackets are the anonymous nodes of the parse tree opening with `(`, `[` or `{`, like `%{`
+/// in Elixir or `#{` in Clojure, and closing with `)`, `]` or `}`. An opening and a closing
+/// bracket match when they're children of the same node.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::brackets::match_brackets;
+/// use lumis::languages::Language;
+///
+/// let brackets = match_brackets("[[1], \"[\"]", Language::JSON);
+/// let depths: Vec<_> = brackets.iter().map(|bracket| (bracket.range.start, bracket.depth)).collect();
+///
+/// // The bracket inside the string is ignored
+/// assert_eq!(depths, vec![(0, 0), (1, 1), (3, 1), (9, 0)]);
+/// ```
+pub fn match_brackets(source: &str, language: Language) -> Vec<Bracket> {
+    let mut parser = Parser::new();
+    if parser.set_language(&language.config().language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    // Opening brackets waiting for their match, with the id of their parent node.
+    let mut open: Vec<(Range<usize>, Option<usize>)> = Vec::new();
+    let mut brackets = Vec::new();
+
+    visit_leaves(&mut tree.walk(), &mut |node| {
+        if node.is_named() || node.byte_range().is_empty() {
+            return;
+        }
+        let kind = node.kind();
+        let parent = node.parent().map(|parent| parent.id());
+
+        if kind.len() <= 2 && kind.ends_with(['(', '[', '{']) {
+            open.push((node.byte_range(), parent));
+        } else if matches!(kind, ")" | "]" | "}") {
+            // Unmatched opening brackets inside the pair are dropped with it.
+            if let Some(i) = open.iter().rposition(|(_, id)| *id == parent) {
+                let (range, _) = open[i].clone();
+                open.truncate(i);
+                brackets.push(Bracket { range, depth: i });
+                brackets.push(Bracket {
+                    range: node.byte_range(),
+                    depth: i,
+                });
+            }
+        }
+    });
+
+    brackets.sort_by_key(|bracket| bracket.range.start);
+    brackets
+}
+
+/// Visit the leaves of the tree in document order.
+fn visit_leaves<'tree>(cursor: &mut TreeCursor<'tree>, visit: &mut impl FnMut(Node<'tree>)) {
+    loop {
+        if cursor.goto_first_child() {
+            continue;
+        }
+        visit(cursor.node());
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return;
+            }
+        }
+    }
+}
+
+/// Matching brackets of a source with the palette used to color them.
+pub(crate) struct BracketColors {
+    brackets: Vec<Bracket>,
+    palette: Vec<String>,
+}
+
+impl BracketColors {
+    pub(crate) fn new(
+        source: &str,
+        language: Language,
+        rainbow: &RainbowBrackets,
+        theme: Option<&Theme>,
+    ) -> Self {
+        Self {
+            brackets: match_brackets(source, language),
+            palette: rainbow.palette(theme),
+        }
+    }
+
+    /// Split `range` at the brackets it contains, returning each piece with the nesting
+    /// depth of the bracket it covers, if any.
+    pub(crate) fn split(&self, range: Range<usize>) -> Vec<(Range<usize>, Option<usize>)> {
+        let first = self
+            .brackets
+            .partition_point(|bracket| bracket.range.end <= range.start);
+        let mut pieces = Vec::new();
+        let mut start = range.start;
+
+        for bracket in &self.brackets[first..] {
+            if bracket.range.start >= range.end {
+                break;
+            }
+            if bracket.range.start > start {
+                pieces.push((start..bracket.range.start, None));
+            }
+            let end = bracket.range.end.min(range.end);
+            pieces.push((bracket.range.start.max(start)..end, Some(bracket.depth)));
+            start = end;
+        }
+        if start < range.end {
+            pieces.push((start..range.end, None));
+        }
+
+        pieces
+    }
+
+    /// Nesting depth of the bracket covering `offset`.
+    pub(crate) fn depth_at(&self, offset: usize) -> Option<usize> {
+        let i = self
+            .brackets
+            .partition_point(|bracket| bracket.range.end <= offset);
+        self.brackets
+            .get(i)
+            .filter(|bracket| bracket.range.start <= offset)
+            .map(|bracket| bracket.depth)
+    }
+
+    /// First start or end offset of a bracket after `offset`.
+    pub(crate) fn next_boundary(&self, offset: usize) -> Option<usize> {
+        crate::navigation::next_boundary(&self.brackets, |bracket| &bracket.range, offset)
+    }
+
+    /// Colors cycled through by nesting depth.
+    pub(crate) fn palette(&self) -> &[String] {
+        &self.palette
+    }
+
+    /// Color of the brackets at `depth`.
+    pub(crate) fn color(&self, depth: usize) -> Option<&str> {
+        if self.palette.is_empty() {
+            return None;
+        }
+        Some(&self.palette[depth % self.palette.len()])
+    }
+
+    /// `style` with the foreground of the brackets at `depth`.
+    pub(crate) fn style(&self, style: &Style, depth: usize) -> Style {
+        Style {
+            fg: self.color(depth).map(str::to_string).or(style.fg.clone()),
+            ..style.clone()
+        }
+    }
+}
+
+/// Wrap a callback of [`highlight_iter()`](crate::highlight::highlight_iter) so brackets are
+/// passed as their own segments, styled with the color of their depth.
+pub(crate) fn rainbow<'a, F, E>(
+    source: &'a str,
+    colors: Option<&'a BracketColors>,
+    mut callback: F,
+) -> impl FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a
+where
+    F: FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a,
+{
+    move |text, range, scope, language, style| {
+        let Some(colors) = colors else {
+            return callback(text, range, scope, language, style);
+        };
+
+        for (piece, depth) in colors.split(range) {
+            let text = &source[piece.clone()];
+            match depth {
+                Some(depth) => callback(text, piece, scope, language, &colors.style(style, depth))?,
+                None => callback(text, piece, scope, language, style)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depths(source: &str, language: Language) -> Vec<(String, usize)> {
+        match_brackets(source, language)
+            .into_iter()
+            .map(|bracket| (source[bracket.range].to_string(), bracket.depth))
+            .collect()
+    }
+
+    #[test]
+    fn test_ignores_strings_and_comments() {
+        let code = "fn f() {\n    // (\n    g(\"{\");\n}\n";
+
+        assert_eq!(
+            depths(code, Language::Rust),
+            vec![
+                ("(".to_string(), 0),
+                (")".to_string(), 0),
+                ("{".to_string(), 0),
+                ("(".to_string(), 1),
+                (")".to_string(), 1),
+                ("}".to_string(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let colors = BracketColors {
+            brackets: match_brackets("[[]]", Language::JSON),
+            palette: vec!["#ff0000".to_string(), "#00ff00".to_string()],
+        };
+
+        assert_eq!(
+            colors.split(0..4),
+            vec![
+                (0..1, Some(0)),
+                (1..2, Some(1)),
+                (2..3, Some(1)),
+                (3..4, Some(0))
+            ]
+        );
+        assert_eq!(colors.color(2), Some("#ff0000"));
+        assert_eq!(colors.next_boundary(0), Some(1));
+        assert_eq!(colors.next_boundary(3), Some(4));
+        assert_eq!(colors.next_boundary(4), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Stylesheets generated from themes at runtime.
+//!
+//! The CSS files bundled in `css/` style the output of [`HtmlLinked`](crate::formatter::HtmlLinked)
+//! with a fixed `pre.lumis` selector. A [`Stylesheet`] generates the same rules from any
+//! [`Theme`], including custom themes loaded with [`themes::from_file()`](crate::themes::from_file),
+//! with a custom root selector and class prefix, and can combine a light and a dark theme in a
+//! single stylesheet switched by [`ColorScheme`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::css::{ColorScheme, StylesheetBuilder};
+//! use lumis::themes;
+//!
+//! let stylesheet = StylesheetBuilder::new()
+//!     .root_selector(".docs pre")
+//!     .class_prefix("hl-")
+//!     .build()
+//!     .unwrap();
+//!
+//! let light = themes::get("github_light").unwrap();
+//! let dark = themes::get("github_dark").unwrap();
+//! let css = stylesheet.light_dark_css(&light, &dark, ColorScheme::MediaQuery);
+//!
+//! assert!(css.contains(".docs pre .hl-keyword {"));
+//! assert!(css.contains("@media (prefers-color-scheme: dark) {\n  .docs pre {"));
+//! ```
+
+use crate::themes::Theme;
+use derive_builder::Builder;
+use std::collections::BTreeSet;
+
+/// How the dark theme of [`Stylesheet::light_dark_css()`] is selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorScheme {
+    /// With the `prefers-color-scheme: dark` media query of the operating system.
+    #[default]
+    MediaQuery,
+    /// With a `data-theme="dark"` attribute on an ancestor, like `<html data-theme="dark">`.
+    DataTheme,
+}
+
+/// Options of the stylesheets generated from themes.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::css::Stylesheet;
+/// use lumis::themes;
+///
+/// let theme = themes::get("dracula").unwrap();
+/// let css = Stylesheet::default().css(&theme);
+///
+/// assert!(css.contains("pre.lumis .keyword {\n  color: #ff79c6;\n}\n"));
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Stylesheet {
+    /// Selector of the `<pre>` elements, also scoping the rules of their content. Defaults to
+    /// `pre.lumis`.
+    #[builder(setter(into))]
+    root_selector: String,
+    /// Prefix of the classes of the scopes, eg: `hl-` for `.hl-keyword`, matching the
+    /// `class_prefix` option of [`HtmlLinked`](crate::formatter::HtmlLinked).
+    #[builder(setter(into))]
+    class_prefix: String,
+    /// Include the italic styles of the theme. Defaults to `true`.
+    italic: bool,
+}
+
+impl StylesheetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Stylesheet {
+    fn default() -> Self {
+        Self {
+            root_selector: "pre.lumis".to_string(),
+            class_prefix: String::new(),
+            italic: true,
+        }
+    }
+}
+
+impl Stylesheet {
+    /// Generate the stylesheet of `theme`.
+    pub fn css(&self, theme: &Theme) -> String {
+        let mut css = format!(
+            "/* {}\n * revision: {}\n */\n\n",
+            theme.name, theme.revision
+        );
+        css.push_str(&self.rules(theme, None, &self.root_selector, ""));
+        css
+    }
+
+    /// Generate a stylesheet with the rules of `light`, overridden by the rules of `dark` when
+    /// selected by `scheme`.
+    pub fn light_dark_css(&self, light: &Theme, dark: &Theme, scheme: ColorScheme) -> String {
+        let mut css = format!(
+            "/* light: {} (revision: {})\n * dark: {} (revision: {})\n */\n\n",
+            light.name, light.revision, dark.name, dark.revision
+        );
+        css.push_str(&self.rules(light, None, &self.root_selector, ""));
+
+        match scheme {
+            ColorScheme::MediaQuery => {
+                css.push_str("@media (prefers-color-scheme: dark) {\n");
+                css.push_str(&self.rules(dark, Some(light), &self.root_selector, "  "));
+                css.push_str("}\n");
+            }
+            ColorScheme::DataTheme => {
+                let root = format!("[data-theme=\"dark\"] {}", self.root_selector);
+                css.push_str(&self.rules(dark, Some(light), &root, ""));
+            }
+        }
+
+        css
+    }
+
+    /// Rules of `theme` scoped to `root`, each line indented by `indent`, overriding the
+    /// scopes styled by `base`.
+    fn rules(&self, theme: &Theme, base: Option<&Theme>, root: &str, indent: &str) -> String {
+        let pre = Rule {
+            selectors: vec![String::new()],
+            declarations: theme.pre_style("\n  ").unwrap_or_default(),
+        };
+
+        std::iter::once(pre)
+            .chain(theme_rules(theme, base, self.italic, &self.class_prefix))
+            .map(|rule| {
+                let selectors: Vec<String> = rule
+                    .selectors
+                    .iter()
+                    .map(|selector| format!("{} {}", root, selector).trim_end().to_string())
+                    .collect();
+                rule.render(&selectors)
+                    .lines()
+                    .map(|line| format!("{}{}\n", indent, line))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+}
+
+/// A rule of a theme stylesheet.
+pub(crate) struct Rule {
+    /// Selectors relative to the `<pre>` element.
+    pub(crate) selectors: Vec<String>,
+    /// Declarations separated by a new line and an indentation.
+    pub(crate) declarations: String,
+}
+
+impl Rule {
+    /// Render the rule with `selectors` in place of its own.
+    pub(crate) fn render(&self, selectors: &[String]) -> String {
+        if self.declarations.is_empty() {
+            return format!("{} {{}}\n", selectors.join(",\n"));
+        }
+        format!(
+            "{} {{\n  {}\n}}\n",
+            selectors.join(",\n"),
+            self.declarations
+        )
+    }
+}
+
+/// Rules of the content of the `<pre>` element styled by `theme`: the scopes with their
+/// classes prefixed by `class_prefix`, annotations, rainbow brackets, semantic colors,
+/// whitespace and line numbers.
+///
+/// When `theme` overrides a `base` theme, like the dark theme of a light and dark stylesheet,
+/// the properties of the scopes of `base` that `theme` doesn't declare are reset to `inherit`
+/// so they don't leak into `theme`.
+pub(crate) fn theme_rules(
+    theme: &Theme,
+    base: Option<&Theme>,
+    italic: bool,
+    class_prefix: &str,
+) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    let base_scopes = base.map(|base| &base.highlights);
+    let scopes: BTreeSet<&String> = theme
+        .highlights
+        .keys()
+        .chain(
+            base_scopes
+                .into_iter()
+                .flat_map(|highlights| highlights.keys()),
+        )
+        .collect();
+
+    for scope in scopes {
+        let mut declarations: Vec<String> = theme
+            .highlights
+            .get(scope)
+            .map(|style| style.css(italic, "\n"))
+            .filter(|css| !css.is_empty())
+            .map(|css| css.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        let base_css = base_scopes
+            .and_then(|highlights| highlights.get(scope))
+            .map(|style| style.css(italic, "\n"))
+            .unwrap_or_default();
+        for base_declaration in base_css.lines() {
+            let property = base_declaration.split(':').next().unwrap_or_default();
+            let declared = declarations
+                .iter()
+                .any(|declaration| declaration.split(':').next() == Some(property));
+            if !declared {
+                declarations.push(format!("{property}: inherit;"));
+            }
+        }
+
+        if !declarations.is_empty() {
+            rules.push(Rule {
+                selectors: vec![format!(".{}{}", class_prefix, scope.replace('.', "-"))],
+                declarations: declarations.join("\n  "),
+            });
+        }
+    }
+
+    let annotation_style = theme.annotation_style();
+    let (mark_css, marker_css) =
+        crate::formatter::html::annotation_declarations(annotation_style.as_ref());
+    rules.push(Rule {
+        selectors: vec!["mark.annotation".to_string()],
+        declarations: mark_css.join("\n  "),
+    });
+    rules.push(Rule {
+        selectors: vec![".annotation-marker".to_string()],
+        declarations: marker_css.join("\n  "),
+    });
+    rules.push(Rule {
+        selectors: vec!["a.symbol-reference".to_string()],
+        declarations: crate::formatter::html::SYMBOL_REFERENCE_CSS.replace("; ", ";\n  "),
+    });
+
+    for (i, color) in theme.bracket_palette().iter().enumerate() {
+        rules.push(Rule {
+            selectors: vec![format!(".rainbow-bracket-{}", i + 1)],
+            declarations: format!("color: {color};"),
+        });
+    }
+
+    for (i, color) in theme.semantic_palette().iter().enumerate() {
+        rules.push(Rule {
+            selectors: vec![format!(".semantic-{}", i + 1)],
+            declarations: format!("color: {color};"),
+        });
+    }
+
+    let whitespace_css = theme
+        .whitespace_style()
+        .map(|style| style.css(italic, "\n  "))
+        .filter(|css| !css.is_empty());
+    if let Some(declarations) = whitespace_css {
+        rules.push(Rule {
+            selectors: vec![
+                ".visible-whitespace".to_string(),
+                ".indent-guide".to_string(),
+            ],
+            declarations,
+        });
+    }
+
+    let line_number_css = theme
+        .line_number_style()
+        .map(|style| style.css(italic, "\n  "))
+        .filter(|css| !css.is_empty())
+        .map(|css| format!("\n  {css}"))
+        .unwrap_or_default();
+    rules.push(Rule {
+        selectors: vec![".line-number".to_string()],
+        declarations: format!("user-select: none;\n  -webkit-user-select: none;{line_number_css}"),
+    });
+
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    #[test]
+    fn test_custom_selector_and_prefix() {
+        let theme = themes::get("dracula").unwrap();
+        let css = StylesheetBuilder::new()
+            .root_selector(".docs")
+            .class_prefix("hl-")
+            .italic(false)
+            .build()
+            .unwrap()
+            .css(&theme);
+
+        assert!(css.contains(".docs {\n  color: #f8f8f2;\n  background-color: #282a36;\n}\n"));
+        assert!(css.contains(".docs .hl-keyword {\n  color: #ff79c6;\n}\n"));
+        assert!(css.contains(".docs .visible-whitespace,\n.docs .indent-guide {\n"));
+        assert!(!css.contains("font-style: italic;"));
+    }
+
+    #[test]
+    fn test_light_dark_with_data_theme() {
+        let light = themes::get("github_light").unwrap();
+        let dark = themes::get("github_dark").unwrap();
+        let css = Stylesheet::default().light_dark_css(&light, &dark, ColorScheme::DataTheme);
+
+        assert!(css.contains("pre.lumis {\n  color: #1f2328;"));
+        assert!(css.contains("[data-theme=\"dark\"] pre.lumis {\n  color: #e6edf3;"));
+        assert!(!css.contains("@media"));
+    }
+
+    #[test]
+    fn test_dark_rules_reset_light_declarations() {
+        let light = themes::get("gruvbox_light").unwrap();
+        let dark = themes::get("dracula").unwrap();
+        let css = Stylesheet::default().light_dark_css(&light, &dark, ColorScheme::DataTheme);
+        let dark_css = &css[css.find("[data-theme").unwrap()..];
+
+        assert!(dark_css.contains(".comment {\n  color: #6272a4;\n  font-style: inherit;\n}\n"));
+        assert!(dark_css.contains(".namespace {\n  color: inherit;\n}\n"));
+    }
+}
@@ -0,0 +1,491 @@
+//! Compiler-style diagnostics rendered with lumis highlighting.
+//!
+//! A [`Diagnostic`] describes a problem found in a file: a [`Severity`], a message and
+//! labelled spans of the source, which are [`Annotation`]s. It renders as a report in the
+//! style of rustc, miette or ariadne, with the excerpt around the labels highlighted through
+//! the [`Terminal`](crate::formatter::Terminal) formatter, or as HTML through the
+//! [`HtmlInline`](crate::formatter::HtmlInline) formatter for web UIs. Linters get the themes
+//! and the language detection of lumis for free.
+//!
+//! ```text
+//! error[E0502]: cannot borrow `v` as mutable because it is also borrowed as immutable
+//!   --> src/main.rs:2:13
+//!    │
+//!  2 │ let first = &v[0];
+//!    │             ^^^^^ immutable borrow occurs here
+//!  3 │ v.push(4);
+//!    │ ^^^^^^^^^ mutable borrow occurs here
+//!    │
+//!    = help: clone the first element
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::annotations::Annotation;
+//! use lumis::ansi::ColorDepth;
+//! use lumis::diagnostics::{DiagnosticBuilder, Severity};
+//!
+//! let source = "let x = 1;\nlet y = x +;\n";
+//!
+//! let diagnostic = DiagnosticBuilder::new()
+//!     .severity(Severity::Error)
+//!     .message("expected an expression")
+//!     .path(Some("src/lib.rs".to_string()))
+//!     .labels(vec![Annotation::new(22..23, "expected an expression after `+`")])
+//!     .color_depth(ColorDepth::NoColor)
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut output = Vec::new();
+//! diagnostic.format_terminal(source, &mut output).unwrap();
+//!
+//! assert_eq!(
+//!     String::from_utf8(output).unwrap(),
+//!     concat!(
+//!         "error: expected an expression\n",
+//!         "  --> src/lib.rs:2:12\n",
+//!         "   │\n",
+//!         " 1 │ let x = 1;\n",
+//!         " 2 │ let y = x +;\n",
+//!         "   │            ^ expected an expression after `+`\n",
+//!     )
+//! );
+//! ```
+
+use crate::annotations::Annotation;
+use crate::formatter::ansi::{self, ColorDepth};
+use crate::formatter::{html, Formatter, HtmlInlineBuilder, TerminalBuilder};
+use crate::highlight::{SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::Theme;
+use derive_builder::Builder;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+/// Severity of a [`Diagnostic`].
+///
+/// Colored with the theme's `comment.error`, `comment.warning`, `comment.note` and
+/// `comment.hint` highlights.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+impl Severity {
+    fn scope(&self) -> &'static str {
+        match self {
+            Severity::Error => "comment.error",
+            Severity::Warning => "comment.warning",
+            Severity::Info => "comment.note",
+            Severity::Hint => "comment.hint",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+            Severity::Hint => write!(f, "hint"),
+        }
+    }
+}
+
+/// A problem found in a file, with labelled spans of its source.
+///
+/// Use [`DiagnosticBuilder`] to create instances, then render the report for a source
+/// with [`format_terminal()`](Diagnostic::format_terminal) or
+/// [`format_html()`](Diagnostic::format_html).
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::annotations::Annotation;
+/// use lumis::diagnostics::{DiagnosticBuilder, Severity};
+/// use lumis::themes;
+///
+/// let source = "def greet(name):\n    print(nme)\n";
+///
+/// let diagnostic = DiagnosticBuilder::new()
+///     .severity(Severity::Warning)
+///     .code(Some("F821".to_string()))
+///     .message("undefined name `nme`")
+///     .path(Some("greet.py".to_string()))
+///     .labels(vec![Annotation::new(27..30, "not defined")])
+///     .help(Some("did you mean `name`?".to_string()))
+///     .theme(themes::get("dracula").ok())
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// diagnostic.format_html(source, &mut output).unwrap();
+/// let html = String::from_utf8(output).unwrap();
+///
+/// assert!(html.contains(r#"<strong class="diagnostic-severity" style="color: #f1fa8c;">warning[F821]</strong>"#));
+/// assert!(html.contains(r#"<code class="language-python""#));
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Diagnostic {
+    severity: Severity,
+    /// Main message, printed after the severity.
+    #[builder(setter(into))]
+    message: String,
+    /// Optional code of the diagnostic, e.g. `E0502`.
+    code: Option<String>,
+    /// Path of the file, printed in the location and used to guess the language.
+    path: Option<String>,
+    /// Language of the source, guessed from the path and the source when `None`.
+    lang: Option<Language>,
+    /// Labelled spans of the source. The first label is the primary one, its position is
+    /// printed in the location.
+    labels: Vec<Annotation>,
+    /// Notes printed after the excerpt.
+    notes: Vec<String>,
+    /// Suggestion printed after the notes.
+    help: Option<String>,
+    /// Number of lines printed before and after the labelled lines. Defaults to 1.
+    context_lines: usize,
+    theme: Option<Theme>,
+    /// Colors supported by the terminal, defaults to [`ColorDepth::TrueColor`].
+    color_depth: ColorDepth,
+}
+
+impl DiagnosticBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Diagnostic {
+    fn default() -> Self {
+        Self {
+            severity: Severity::default(),
+            message: String::new(),
+            code: None,
+            path: None,
+            lang: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+            context_lines: 1,
+            theme: None,
+            color_depth: ColorDepth::default(),
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Render the report with ANSI colors.
+    ///
+    /// The excerpt is rendered by the [`Terminal`](crate::formatter::Terminal) formatter,
+    /// with caret underlines below the labelled spans.
+    pub fn format_terminal(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let color_depth = self.color_depth.resolve();
+        let paint =
+            |text: &str, style: &Style| ansi::wrap_with_ansi_with_depth(text, style, color_depth);
+        let bold = Style {
+            bold: true,
+            ..Default::default()
+        };
+        let gutter_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .unwrap_or_default();
+
+        writeln!(
+            output,
+            "{}{}",
+            paint(&self.title(), &self.severity_style()),
+            paint(&format!(": {}", self.message), &bold)
+        )?;
+
+        let excerpt = self.excerpt(source);
+        let number_width = excerpt
+            .as_ref()
+            .map_or(1, |lines| lines.end().to_string().len());
+        let indent = " ".repeat(number_width + 2);
+
+        if let Some(location) = self.location(source) {
+            writeln!(
+                output,
+                "{}{} {}",
+                " ".repeat(number_width + 1),
+                paint("-->", &gutter_style),
+                location
+            )?;
+        }
+
+        if let Some(lines) = excerpt {
+            writeln!(output, "{}{}", indent, paint("│", &gutter_style))?;
+
+            let formatter = TerminalBuilder::new()
+                .lang(self.language(source))
+                .theme(self.theme.clone())
+                .color_depth(color_depth)
+                .line_numbers(true)
+                .range(Some(SourceRange::Lines(lines)))
+                .annotations(self.labels.clone())
+                .build()
+                .map_err(io::Error::other)?;
+            let mut buffer = Vec::new();
+            formatter.format(source, &mut buffer)?;
+            output.write_all(&buffer)?;
+            if !buffer.ends_with(b"\n") {
+                writeln!(output)?;
+            }
+        }
+
+        let footer = self.footer();
+        if !footer.is_empty() {
+            writeln!(output, "{}{}", indent, paint("│", &gutter_style))?;
+        }
+        for (kind, text) in footer {
+            writeln!(
+                output,
+                "{}{} {} {}",
+                indent,
+                paint("=", &gutter_style),
+                paint(&format!("{}:", kind), &bold),
+                text
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the report as HTML.
+    ///
+    /// The excerpt is rendered by the [`HtmlInline`](crate::formatter::HtmlInline) formatter
+    /// with line numbers and the labelled spans wrapped in `<mark>` elements, followed by
+    /// the list of labels, numbered like the footnote markers of the marks.
+    pub fn format_html(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let style_attr = |style: Option<String>| {
+            style
+                .map(|style| format!(" style=\"{}\"", style))
+                .unwrap_or_default()
+        };
+        let container_style = self.theme.as_ref().and_then(|theme| theme.pre_style(" "));
+        let severity_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.badge_color(self.severity.scope()))
+            .map(|color| format!("color: {};", color));
+
+        write!(
+            output,
+            "<div class=\"lumis-diagnostic diagnostic-{}\"{}>",
+            self.severity,
+            style_attr(container_style)
+        )?;
+        write!(
+            output,
+            "<div class=\"diagnostic-header\"><strong class=\"diagnostic-severity\"{}>{}</strong>: <span class=\"diagnostic-message\">{}</span></div>",
+            style_attr(severity_style),
+            html::escape(&self.title()),
+            html::escape(&self.message)
+        )?;
+
+        if let Some(location) = self.location(source) {
+            write!(
+                output,
+                "<div class=\"diagnostic-location\">{}</div>",
+                html::escape(&location)
+            )?;
+        }
+
+        if let Some(lines) = self.excerpt(source) {
+            let formatter = HtmlInlineBuilder::new()
+                .lang(self.language(source))
+                .theme(self.theme.clone())
+                .line_numbers(true)
+                .range(Some(SourceRange::Lines(lines)))
+                .annotations(self.labels.clone())
+                .build()
+                .map_err(io::Error::other)?;
+            formatter.format(source, output)?;
+
+            write!(output, "<ol class=\"diagnostic-labels\">")?;
+            for (i, label) in self.labels.iter().enumerate() {
+                write!(
+                    output,
+                    "<li data-annotation=\"{}\">{}",
+                    i + 1,
+                    html::escape(&label.label)
+                )?;
+                if let Some(note) = &label.note {
+                    write!(output, "<br>{}", html::escape(note))?;
+                }
+                write!(output, "</li>")?;
+            }
+            write!(output, "</ol>")?;
+        }
+
+        let footer = self.footer();
+        if !footer.is_empty() {
+            write!(output, "<ul class=\"diagnostic-notes\">")?;
+            for (kind, text) in footer {
+                write!(
+                    output,
+                    "<li class=\"diagnostic-{}\"><strong>{}:</strong> {}</li>",
+                    kind,
+                    kind,
+                    html::escape(text)
+                )?;
+            }
+            write!(output, "</ul>")?;
+        }
+
+        write!(output, "</div>")
+    }
+
+    fn title(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{}]", self.severity, code),
+            None => self.severity.to_string(),
+        }
+    }
+
+    fn severity_style(&self) -> Style {
+        Style {
+            fg: self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.badge_color(self.severity.scope()))
+                .map(str::to_string),
+            bold: true,
+            ..Default::default()
+        }
+    }
+
+    fn language(&self, source: &str) -> Language {
+        self.lang
+            .unwrap_or_else(|| Language::guess(self.path.as_deref(), source))
+    }
+
+    /// Path followed by the line and column of the primary label.
+    fn location(&self, source: &str) -> Option<String> {
+        let position = self.labels.first().map(|label| {
+            let offset = label.byte_range(source).start;
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line = source[..offset].matches('\n').count() + 1;
+            let column = source[line_start..offset].chars().count() + 1;
+            format!("{}:{}", line, column)
+        });
+
+        match (&self.path, position) {
+            (Some(path), Some(position)) => Some(format!("{}:{}", path, position)),
+            (Some(path), None) => Some(path.clone()),
+            (None, position) => position,
+        }
+    }
+
+    /// Lines touched by the labels, extended by `context_lines` on both sides.
+    fn excerpt(&self, source: &str) -> Option<RangeInclusive<usize>> {
+        let line_count = source.lines().count().max(1);
+
+        self.labels
+            .iter()
+            .map(|label| SourceRange::Bytes(label.byte_range(source)).line_range(source))
+            .reduce(|a, b| *a.start().min(b.start())..=*a.end().max(b.end()))
+            .map(|lines| {
+                let end = (lines.end() + self.context_lines).min(line_count);
+                let start = lines
+                    .start()
+                    .saturating_sub(self.context_lines)
+                    .max(1)
+                    .min(end);
+                start..=end
+            })
+    }
+
+    fn footer(&self) -> Vec<(&'static str, &str)> {
+        self.notes
+            .iter()
+            .map(|note| ("note", note.as_str()))
+            .chain(self.help.as_deref().map(|help| ("help", help)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    const SOURCE: &str =
+        "let v = vec![1];\nlet first = &v[0];\nv.push(4);\nprintln!(\"{first}\");\n";
+
+    fn diagnostic() -> DiagnosticBuilder {
+        let mut builder = DiagnosticBuilder::new();
+        builder
+            .code(Some("E0502".to_string()))
+            .message("cannot borrow `v` as mutable")
+            .path(Some("src/main.rs".to_string()))
+            .labels(vec![
+                Annotation::new(29..34, "immutable borrow occurs here"),
+                Annotation::new(36..45, "mutable borrow occurs here"),
+            ])
+            .notes(vec!["borrows last until their last use".to_string()])
+            .help(Some("clone the first element".to_string()))
+            .context_lines(0)
+            .color_depth(ColorDepth::NoColor);
+        builder
+    }
+
+    #[test]
+    fn test_terminal() {
+        let mut buffer = Vec::new();
+        diagnostic()
+            .build()
+            .unwrap()
+            .format_terminal(SOURCE, &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                "error[E0502]: cannot borrow `v` as mutable\n",
+                "  --> src/main.rs:2:13\n",
+                "   │\n",
+                " 2 │ let first = &v[0];\n",
+                "   │             ^^^^^ immutable borrow occurs here\n",
+                " 3 │ v.push(4);\n",
+                "   │ ^^^^^^^^^ mutable borrow occurs here\n",
+                "   │\n",
+                "   = note: borrows last until their last use\n",
+                "   = help: clone the first element\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let mut buffer = Vec::new();
+        diagnostic()
+            .theme(themes::get("dracula").ok())
+            .build()
+            .unwrap()
+            .format_html(SOURCE, &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.starts_with(concat!(
+            r#"<div class="lumis-diagnostic diagnostic-error" style="color: #f8f8f2; background-color: #282a36;">"#,
+            r#"<div class="diagnostic-header"><strong class="diagnostic-severity" style="color: #ff5555;">error[E0502]</strong>: <span class="diagnostic-message">cannot borrow `v` as mutable</span></div>"#,
+            r#"<div class="diagnostic-location">src/main.rs:2:13</div><pre class="lumis""#
+        )));
+        assert!(result.contains(r#"data-line="2""#));
+        assert!(!result.contains(r#"data-line="4""#));
+        assert!(result.contains(r#"<li data-annotation="2">mutable borrow occurs here</li></ol>"#));
+        assert!(result.ends_with(r#"<li class="diagnostic-help"><strong>help:</strong> clone the first element</li></ul></div>"#));
+    }
+}
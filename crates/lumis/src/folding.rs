@@ -0,0 +1,261 @@
+//! Code folding: collapsible function bodies, blocks and large literals.
+//!
+//! Long examples are easier to skim when their function bodies, classes and big literals can
+//! be collapsed. Foldable regions are found on the parse tree: nodes like blocks, functions,
+//! classes, arrays and objects spanning at least [`CodeFolding::min_lines`] lines.
+//!
+//! The HTML formatters accept a `code_folding` option rendering each region either as a
+//! `<details>` element, with the first line of the region as its `<summary>`, or as a
+//! `data-fold-end` attribute on the first line for scripts to collapse the lines themselves.
+//! Either way, each line keeps its own `<div class="line">`, except the first line of a
+//! `<details>` region, a `<span class="line">` since `<summary>` only allows phrasing content.
+//!
+//! Only the main language is folded, not injected languages.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::folding::find_folds;
+//! use lumis::languages::Language;
+//!
+//! let code = "fn main() {\n    let a = 1;\n    let b = 2;\n}\n";
+//! let folds = find_folds(code, Language::Rust, 3);
+//!
+//! // The body is folded under the signature, the closing brace stays visible
+//! assert_eq!((folds[0].start_line, folds[0].end_line), (1, 3));
+//! ```
+
+use crate::languages::Language;
+use tree_sitter::{Node, Parser, TreeCursor};
+
+/// Minimum number of lines of a foldable node when none is given.
+pub const DEFAULT_MIN_LINES: usize = 4;
+
+/// Parts of the node kinds that can be folded, matching eg: `block`, `function_item`,
+/// `class_definition`, `array` or `object` in most grammars.
+const FOLDABLE_KINDS: &[&str] = &[
+    "array",
+    "block",
+    "body",
+    "class",
+    "declaration_list",
+    "dictionary",
+    "enum",
+    "function",
+    "hash",
+    "impl",
+    "interface",
+    "lambda",
+    "list",
+    "map",
+    "method",
+    "module",
+    "object",
+    "struct",
+    "table",
+    "trait",
+    "tuple",
+];
+
+/// How folds are rendered in HTML.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FoldMode {
+    /// Wrap each region in `<details class="fold" open>`, with its first line in `<summary>` as
+    /// a `<span class="line">`.
+    ///
+    /// Works without JavaScript: clicking the first line collapses the region.
+    #[default]
+    Details,
+    /// Add a `data-fold-end` attribute to the first line of each region, holding the
+    /// `data-line` of its last folded line, for scripts to collapse the lines in between.
+    DataAttributes,
+}
+
+/// Options of code folding in HTML output.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::folding::{CodeFolding, FoldMode};
+///
+/// let folding = CodeFolding {
+///     min_lines: 10,
+///     mode: FoldMode::DataAttributes,
+/// };
+/// assert_eq!(CodeFolding::default().min_lines, 4);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CodeFolding {
+    /// Only fold nodes spanning at least this many lines.
+    pub min_lines: usize,
+    /// How folds are rendered.
+    pub mode: FoldMode,
+}
+
+impl Default for CodeFolding {
+    fn default() -> Self {
+        Self {
+            min_lines: DEFAULT_MIN_LINES,
+            mode: FoldMode::default(),
+        }
+    }
+}
+
+/// A foldable region of the source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fold {
+    /// 1-based line shown when the region is collapsed, where the node starts.
+    pub start_line: usize,
+    /// 1-based last line hidden when the region is collapsed.
+    ///
+    /// A line starting with the closing token of the node, like `}` or `end`, isn't folded.
+    pub end_line: usize,
+    /// Kind of the node, eg: `function_item`.
+    pub kind: String,
+}
+
+/// Find the foldable regions of `source` spanning at least `min_lines` lines, sorted by
+/// their first line.
+///
+/// Regions never start on the same line and are properly nested, so each one can be wrapped
+/// in its own element. When several nodes start on the same line, the outermost one is kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::folding::find_folds;
+/// use lumis::languages::Language;
+///
+/// let code = "[\n  1,\n  [\n    2,\n    3\n  ]\n]\n";
+/// let folds: Vec<_> = find_folds(code, Language::JSON, 3)
+///     .into_iter()
+///     .map(|fold| (fold.start_line, fold.end_line))
+///     .collect();
+///
+/// assert_eq!(folds, vec![(1, 6), (3, 5)]);
+/// ```
+pub fn find_folds(source: &str, language: Language, min_lines: usize) -> Vec<Fold> {
+    let mut parser = Parser::new();
+    if parser.set_language(&language.config().language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let mut folds: Vec<Fold> = Vec::new();
+
+    visit_named(&mut tree.walk(), &mut |node| {
+        let kind = node.kind();
+        if node.parent().is_none() || !FOLDABLE_KINDS.iter().any(|part| kind.contains(part)) {
+            return;
+        }
+        // Blocks opening at the start of a line, like the body of a Python function, are
+        // folded under the line of their parent instead.
+        if (kind.contains("block") || kind.contains("body")) && starts_line(source, node) {
+            return;
+        }
+
+        let start = node.start_position().row;
+        let mut end = node.end_position().row;
+        if node.end_position().column == 0 && end > start {
+            end -= 1;
+        }
+        if end - start + 1 < min_lines.max(2) {
+            return;
+        }
+        if ends_with_closing_line(source, node, end) {
+            end -= 1;
+        }
+        if end <= start {
+            return;
+        }
+
+        // Nodes are visited outermost first, so a region already starting on this line
+        // encloses this one.
+        let nested = match folds.last() {
+            Some(last) if last.start_line == start + 1 => return,
+            _ => folds
+                .iter()
+                .rev()
+                .find(|fold| fold.end_line > start)
+                .is_none_or(|fold| end < fold.end_line),
+        };
+        if nested {
+            folds.push(Fold {
+                start_line: start + 1,
+                end_line: end + 1,
+                kind: kind.to_string(),
+            });
+        }
+    });
+
+    folds
+}
+
+/// Whether the last line of `node` starts with its closing token, like a `}` or an `end`.
+fn ends_with_closing_line(source: &str, node: Node, end_row: usize) -> bool {
+    let mut last = node;
+    while let Some(child) = last.child(last.child_count().saturating_sub(1)) {
+        last = child;
+    }
+    if last.is_named() || last.start_position().row != end_row {
+        return false;
+    }
+
+    starts_line(source, last)
+}
+
+/// Whether only whitespace precedes `node` on its first line.
+fn starts_line(source: &str, node: Node) -> bool {
+    let line_start = source[..node.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..node.start_byte()].trim().is_empty()
+}
+
+/// Visit the named nodes of the tree in document order, parents before their children.
+fn visit_named<'tree>(cursor: &mut TreeCursor<'tree>, visit: &mut impl FnMut(Node<'tree>)) {
+    loop {
+        if cursor.node().is_named() {
+            visit(cursor.node());
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(source: &str, language: Language, min_lines: usize) -> Vec<(usize, usize)> {
+        find_folds(source, language, min_lines)
+            .into_iter()
+            .map(|fold| (fold.start_line, fold.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_functions() {
+        let code = "impl Foo {\n    fn a() {\n        1;\n        2;\n    }\n\n    fn b() {}\n}\n";
+
+        assert_eq!(lines(code, Language::Rust, 3), vec![(1, 7), (2, 4)]);
+        assert_eq!(lines(code, Language::Rust, 5), vec![(1, 7)]);
+        assert_eq!(lines(code, Language::Rust, 20), vec![]);
+    }
+
+    #[test]
+    fn test_body_without_closing_token() {
+        let code = "def a():\n    x = 1\n    return x\n\nprint(a())\n";
+
+        assert_eq!(lines(code, Language::Python, 2), vec![(1, 3)]);
+
+        let code = "class A:\n    def a(self):\n        x = 1\n        return x\n";
+        assert_eq!(lines(code, Language::Python, 3), vec![(1, 4), (2, 4)]);
+    }
+}
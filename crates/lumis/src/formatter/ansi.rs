@@ -26,6 +26,7 @@
 use crate::highlight::{highlight_iter, HighlightError, Style};
 use crate::languages::Language;
 use crate::themes::Theme;
+use std::io::IsTerminal;
 use std::ops::Range;
 
 /// ANSI reset sequence to clear all formatting.
@@ -33,6 +34,109 @@ use std::ops::Range;
 /// Use this to reset terminal colors and styles back to default.
 pub const ANSI_RESET: &str = "\u{1b}[0m";
 
+/// RGB values of the 16 standard terminal colors, as defined by xterm.
+const ANSI_16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Levels of the 6x6x6 color cube in the 256 color palette.
+const ANSI_256_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Number of colors used to render theme colors in the terminal.
+///
+/// Themes define 24-bit colors, which are mapped to the nearest palette entry
+/// when the terminal supports fewer colors.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::ansi::{rgb_to_ansi_with_depth, ColorDepth};
+///
+/// assert_eq!(rgb_to_ansi_with_depth(255, 85, 85, false, ColorDepth::TrueColor), "\u{1b}[38;2;255;85;85m");
+/// assert_eq!(rgb_to_ansi_with_depth(255, 85, 85, false, ColorDepth::Ansi256), "\u{1b}[38;5;203m");
+/// assert_eq!(rgb_to_ansi_with_depth(255, 85, 85, false, ColorDepth::Ansi16), "\u{1b}[91m");
+/// assert_eq!(rgb_to_ansi_with_depth(255, 85, 85, false, ColorDepth::NoColor), "");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorDepth {
+    /// Detect the color depth from the environment, see [`ColorDepth::detect`].
+    Auto,
+    /// 24-bit colors.
+    #[default]
+    TrueColor,
+    /// The xterm 256 color palette.
+    Ansi256,
+    /// The 16 standard terminal colors.
+    Ansi16,
+    /// No escape sequences at all, plain text.
+    NoColor,
+}
+
+impl ColorDepth {
+    /// Detect the color depth supported by the terminal from the environment.
+    ///
+    /// - `NO_COLOR` set to a non-empty value disables colors.
+    /// - `CLICOLOR_FORCE` set to a value other than `0` enables colors even when
+    ///   stdout is not a terminal, otherwise colors are disabled when it is not.
+    /// - `COLORTERM` set to `truecolor` or `24bit` selects 24-bit colors.
+    /// - `TERM` containing `256color` selects the 256 color palette, `dumb` disables
+    ///   colors and any other value selects the 16 standard colors.
+    pub fn detect() -> Self {
+        Self::detect_from(
+            |name| std::env::var(name).ok(),
+            std::io::stdout().is_terminal(),
+        )
+    }
+
+    fn detect_from(env: impl Fn(&str) -> Option<String>, is_terminal: bool) -> Self {
+        let is_set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+
+        if is_set("NO_COLOR") {
+            return Self::NoColor;
+        }
+
+        let forced = env("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+        if !forced && !is_terminal {
+            return Self::NoColor;
+        }
+
+        if let Some(colorterm) = env("COLORTERM") {
+            if colorterm == "truecolor" || colorterm == "24bit" {
+                return Self::TrueColor;
+            }
+        }
+
+        match env("TERM") {
+            Some(term) if term.contains("256color") => Self::Ansi256,
+            Some(term) if term == "dumb" && !forced => Self::NoColor,
+            _ => Self::Ansi16,
+        }
+    }
+
+    /// Resolve [`ColorDepth::Auto`] into a concrete depth, leaving other values as is.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => Self::detect(),
+            depth => depth,
+        }
+    }
+}
+
 /// Convert a hex color string to RGB tuple.
 ///
 /// # Arguments
@@ -96,6 +200,109 @@ pub fn rgb_to_ansi(r: u8, g: u8, b: u8, is_background: bool) -> String {
     }
 }
 
+/// Generate ANSI color escape sequence from RGB values for the given [`ColorDepth`].
+///
+/// The color is mapped to the nearest entry of the palette supported by `depth`.
+/// Returns an empty string for [`ColorDepth::NoColor`], and [`ColorDepth::Auto`]
+/// is resolved from the environment.
+pub fn rgb_to_ansi_with_depth(
+    r: u8,
+    g: u8,
+    b: u8,
+    is_background: bool,
+    depth: ColorDepth,
+) -> String {
+    match depth.resolve() {
+        ColorDepth::TrueColor | ColorDepth::Auto => rgb_to_ansi(r, g, b, is_background),
+        ColorDepth::Ansi256 => {
+            let layer = if is_background { 48 } else { 38 };
+            format!("\u{1b}[{};5;{}m", layer, rgb_to_ansi_256(r, g, b))
+        }
+        ColorDepth::Ansi16 => {
+            let index = rgb_to_ansi_16(r, g, b);
+            let base = match (is_background, index < 8) {
+                (false, true) => 30,
+                (false, false) => 90 - 8,
+                (true, true) => 40,
+                (true, false) => 100 - 8,
+            };
+            format!("\u{1b}[{}m", base + index)
+        }
+        ColorDepth::NoColor => String::new(),
+    }
+}
+
+/// Find the nearest entry of the xterm 256 color palette.
+///
+/// Only the 6x6x6 color cube (16-231) and the grayscale ramp (232-255) are considered,
+/// since the first 16 colors are commonly redefined by terminal color schemes.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::ansi::rgb_to_ansi_256;
+///
+/// assert_eq!(rgb_to_ansi_256(255, 0, 0), 196);
+/// assert_eq!(rgb_to_ansi_256(128, 128, 128), 244);
+/// ```
+pub fn rgb_to_ansi_256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |value: u8| {
+        ANSI_256_CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (**level as i32 - value as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = (
+        ANSI_256_CUBE_LEVELS[ri as usize],
+        ANSI_256_CUBE_LEVELS[gi as usize],
+        ANSI_256_CUBE_LEVELS[bi as usize],
+    );
+    let cube_code = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_index;
+    let gray_code = 232 + gray_index;
+
+    if distance((r, g, b), (gray_level, gray_level, gray_level)) < distance((r, g, b), cube) {
+        gray_code
+    } else {
+        cube_code
+    }
+}
+
+/// Find the nearest of the 16 standard terminal colors, returning its index (0-15).
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::ansi::rgb_to_ansi_16;
+///
+/// assert_eq!(rgb_to_ansi_16(250, 10, 10), 9);
+/// assert_eq!(rgb_to_ansi_16(20, 20, 20), 0);
+/// ```
+pub fn rgb_to_ansi_16(r: u8, g: u8, b: u8) -> u8 {
+    ANSI_16_PALETTE
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| distance((r, g, b), **color))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+/// Perceptual distance between two colors using the "redmean" approximation.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let red_mean = (a.0 as i32 + b.0 as i32) / 2;
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (((512 + red_mean) * dr * dr) / 256 + 4 * dg * dg + ((767 - red_mean) * db * db) / 256) as u32
+}
+
 /// Convert a Style to ANSI escape sequences.
 ///
 /// Combines all style attributes (foreground, background, bold, italic, etc.)
@@ -125,17 +332,45 @@ pub fn rgb_to_ansi(r: u8, g: u8, b: u8, is_background: bool) -> String {
 /// let ansi = style_to_ansi(&style);
 /// ```
 pub fn style_to_ansi(style: &Style) -> String {
+    style_to_ansi_with_depth(style, ColorDepth::TrueColor)
+}
+
+/// Convert a Style to ANSI escape sequences for the given [`ColorDepth`].
+///
+/// Same as [`style_to_ansi`] but colors are mapped to the palette supported by `depth`.
+/// Returns an empty string for [`ColorDepth::NoColor`].
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::{ansi::{style_to_ansi_with_depth, ColorDepth}, highlight::Style};
+///
+/// let style = Style {
+///     fg: Some("#ff5555".to_string()),
+///     bold: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(style_to_ansi_with_depth(&style, ColorDepth::Ansi256), "\u{1b}[38;5;203m\u{1b}[1m");
+/// assert_eq!(style_to_ansi_with_depth(&style, ColorDepth::NoColor), "");
+/// ```
+pub fn style_to_ansi_with_depth(style: &Style, depth: ColorDepth) -> String {
+    let depth = depth.resolve();
+    if depth == ColorDepth::NoColor {
+        return String::new();
+    }
+
     let mut codes = Vec::new();
 
     if let Some(fg) = &style.fg {
         if let Some((r, g, b)) = hex_to_rgb(fg) {
-            codes.push(rgb_to_ansi(r, g, b, false));
+            codes.push(rgb_to_ansi_with_depth(r, g, b, false, depth));
         }
     }
 
     if let Some(bg) = &style.bg {
         if let Some((r, g, b)) = hex_to_rgb(bg) {
-            codes.push(rgb_to_ansi(r, g, b, true));
+            codes.push(rgb_to_ansi_with_depth(r, g, b, true, depth));
         }
     }
 
@@ -193,7 +428,15 @@ pub fn style_to_ansi(style: &Style) -> String {
 /// assert_eq!(wrapped, "\u{1b}[0m\u{1b}[38;2;139;233;253mfn\u{1b}[0m");
 /// ```
 pub fn wrap_with_ansi(text: &str, style: &Style) -> String {
-    let ansi_codes = style_to_ansi(style);
+    wrap_with_ansi_with_depth(text, style, ColorDepth::TrueColor)
+}
+
+/// Wrap text with ANSI codes based on a Style for the given [`ColorDepth`].
+///
+/// Same as [`wrap_with_ansi`] but colors are mapped to the palette supported by `depth`.
+/// With [`ColorDepth::NoColor`] the text is returned unchanged.
+pub fn wrap_with_ansi_with_depth(text: &str, style: &Style, depth: ColorDepth) -> String {
+    let ansi_codes = style_to_ansi_with_depth(style, depth);
 
     if ansi_codes.is_empty() {
         text.to_string()
@@ -385,6 +628,108 @@ mod tests {
         assert!(result.ends_with("\u{1b}[0m"));
     }
 
+    #[test]
+    fn test_rgb_to_ansi_256() {
+        assert_eq!(rgb_to_ansi_256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi_256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi_256(0, 0, 255), 21);
+        assert_eq!(rgb_to_ansi_256(40, 42, 54), 236);
+        assert_eq!(rgb_to_ansi_256(139, 233, 253), 117);
+    }
+
+    #[test]
+    fn test_rgb_to_ansi_16() {
+        assert_eq!(rgb_to_ansi_16(0, 0, 0), 0);
+        assert_eq!(rgb_to_ansi_16(255, 255, 255), 15);
+        assert_eq!(rgb_to_ansi_16(200, 10, 10), 1);
+        assert_eq!(rgb_to_ansi_16(40, 42, 54), 0);
+    }
+
+    #[test]
+    fn test_rgb_to_ansi_with_depth_background() {
+        assert_eq!(
+            rgb_to_ansi_with_depth(40, 42, 54, true, ColorDepth::Ansi256),
+            "\u{1b}[48;5;236m"
+        );
+        assert_eq!(
+            rgb_to_ansi_with_depth(255, 255, 255, true, ColorDepth::Ansi16),
+            "\u{1b}[107m"
+        );
+        assert_eq!(
+            rgb_to_ansi_with_depth(0, 0, 0, true, ColorDepth::Ansi16),
+            "\u{1b}[40m"
+        );
+    }
+
+    #[test]
+    fn test_wrap_with_ansi_no_color() {
+        let style = Style {
+            fg: Some("#8be9fd".to_string()),
+            bold: true,
+            ..Default::default()
+        };
+        let result = wrap_with_ansi_with_depth("fn", &style, ColorDepth::NoColor);
+        assert_eq!(result, "fn");
+    }
+
+    #[test]
+    fn test_detect_color_depth() {
+        let detect = |vars: &[(&str, &str)], is_terminal: bool| {
+            ColorDepth::detect_from(
+                |name| {
+                    vars.iter()
+                        .find(|(key, _)| *key == name)
+                        .map(|(_, value)| value.to_string())
+                },
+                is_terminal,
+            )
+        };
+
+        assert_eq!(
+            detect(&[("COLORTERM", "truecolor")], true),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(&[("COLORTERM", "24bit")], true),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color")], true),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(detect(&[("TERM", "screen")], true), ColorDepth::Ansi16);
+        assert_eq!(detect(&[("TERM", "dumb")], true), ColorDepth::NoColor);
+        assert_eq!(detect(&[], true), ColorDepth::Ansi16);
+
+        // NO_COLOR wins over everything else
+        assert_eq!(
+            detect(&[("NO_COLOR", "1"), ("COLORTERM", "truecolor")], true),
+            ColorDepth::NoColor
+        );
+        assert_eq!(detect(&[("NO_COLOR", "")], true), ColorDepth::Ansi16);
+
+        // Not a terminal unless forced
+        assert_eq!(
+            detect(&[("COLORTERM", "truecolor")], false),
+            ColorDepth::NoColor
+        );
+        assert_eq!(
+            detect(
+                &[("COLORTERM", "truecolor"), ("CLICOLOR_FORCE", "1")],
+                false
+            ),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(&[("TERM", "dumb"), ("CLICOLOR_FORCE", "1")], false),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            detect(&[("CLICOLOR_FORCE", "0")], false),
+            ColorDepth::NoColor
+        );
+    }
+
     #[test]
     fn test_wrap_with_ansi_empty_style() {
         let style = Style::default();
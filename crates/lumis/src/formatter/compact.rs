@@ -0,0 +1,170 @@
+//! Short class names for the styles used by [`HtmlLinked`](super::HtmlLinked).
+//!
+//! The theme CSS files style every scope of a theme with a long class name like
+//! `string-special-symbol`. With the `compact_classes` option, `HtmlLinked` resolves the style
+//! of each token from a [`Theme`] instead and gives each distinct style a short class name like
+//! `a1`, the first time it's used. [`CompactClasses::css()`] then returns a stylesheet with only
+//! the rules of the classes used.
+//!
+//! Clones of a [`CompactClasses`] share their class names, so the same instance can style a
+//! whole batch of documents with a single stylesheet. Use a new instance for each document to
+//! get a stylesheet per document.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::formatter::{CompactClasses, Formatter};
+//! use lumis::{HtmlLinkedBuilder, languages::Language, themes};
+//!
+//! let classes = CompactClasses::new(themes::get("dracula").unwrap());
+//!
+//! let formatter = HtmlLinkedBuilder::new()
+//!     .lang(Language::Rust)
+//!     .compact_classes(Some(classes.clone()))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut output = Vec::new();
+//! formatter.format("fn main() {}", &mut output).unwrap();
+//! formatter.format("let x = 1;", &mut output).unwrap();
+//! let html = String::from_utf8(output).unwrap();
+//!
+//! assert!(html.contains(r#"<span class="a1">fn</span>"#));
+//! assert!(classes.css().contains("pre.lumis .a1 {"));
+//! ```
+
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::themes::Theme;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Prefix of the class names, followed by the 1-based index of the style.
+const CLASS_PREFIX: &str = "a";
+
+/// Short class names given to the styles of a theme as they're used.
+#[derive(Clone, Debug)]
+pub struct CompactClasses {
+    theme: Theme,
+    styles: Arc<Mutex<Styles>>,
+}
+
+/// CSS declarations of the styles used so far, by order of first use.
+#[derive(Debug, Default)]
+struct Styles {
+    declarations: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl CompactClasses {
+    /// Create class names for the styles of `theme`, with no style used yet.
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            styles: Arc::default(),
+        }
+    }
+
+    /// Theme the styles are resolved from.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Class name of `style`, given the next short name the first time it's used.
+    ///
+    /// Styles without any declaration don't get a class.
+    pub fn class(&self, style: &Style) -> Option<String> {
+        let declarations = style.css(true, "\n  ");
+        if declarations.is_empty() {
+            return None;
+        }
+
+        let mut styles = self.styles.lock().unwrap_or_else(|err| err.into_inner());
+        let count = styles.declarations.len();
+        let index = *styles.indexes.entry(declarations.clone()).or_insert(count);
+        if index == count {
+            styles.declarations.push(declarations);
+        }
+
+        Some(format!("{}{}", CLASS_PREFIX, index + 1))
+    }
+
+    /// Class name of the style of `scope` in `language`, like the `style` of the same token
+    /// in [`HtmlInline`](super::HtmlInline).
+    pub fn scope_class(&self, scope: &str, language: Language) -> Option<String> {
+        let style = self
+            .theme
+            .get_style(&format!("{}.{}", scope, language.id_name()))?;
+        self.class(style)
+    }
+
+    /// Number of classes given so far.
+    pub fn len(&self) -> usize {
+        self.styles
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .declarations
+            .len()
+    }
+
+    /// Whether no class was given yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stylesheet with the colors of the `<pre>` element, the rules of the classes given so
+    /// far and the line number gutter.
+    pub fn css(&self) -> String {
+        let mut rules = Vec::new();
+
+        match self.theme.pre_style("\n  ") {
+            Some(pre_style) => rules.push(format!("pre.lumis {{\n  {pre_style}\n}}\n")),
+            None => rules.push("pre.lumis {}\n".to_string()),
+        }
+
+        let styles = self.styles.lock().unwrap_or_else(|err| err.into_inner());
+        for (i, declarations) in styles.declarations.iter().enumerate() {
+            rules.push(format!(
+                "pre.lumis .{}{} {{\n  {}\n}}\n",
+                CLASS_PREFIX,
+                i + 1,
+                declarations
+            ));
+        }
+
+        let line_number_css = self
+            .theme
+            .line_number_style()
+            .map(|style| style.css(true, "\n  "))
+            .filter(|css| !css.is_empty())
+            .map(|css| format!("\n  {css}"))
+            .unwrap_or_default();
+        rules.push(format!(
+            "pre.lumis .line-number {{\n  user-select: none;\n  -webkit-user-select: none;{line_number_css}\n}}\n"
+        ));
+
+        rules.join("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    #[test]
+    fn test_classes_are_shared_by_clones() {
+        let classes = CompactClasses::new(themes::get("dracula").unwrap());
+        let keyword = classes.scope_class("keyword", Language::Rust);
+        let other = classes.clone();
+
+        assert_eq!(keyword.as_deref(), Some("a1"));
+        assert_eq!(
+            other.scope_class("string", Language::Rust).as_deref(),
+            Some("a2")
+        );
+        assert_eq!(other.scope_class("keyword.return", Language::Rust), keyword);
+        assert_eq!(classes.class(&Style::default()), None);
+        assert_eq!(classes.len(), 2);
+    }
+}
@@ -0,0 +1,718 @@
+//! Syntax-aware parsing of unified diffs.
+//!
+//! The [`Diff`](crate::languages::Language::Diff) language only highlights the `+` and `-`
+//! markers of a diff. This module parses a unified diff (e.g. the output of `git diff`),
+//! detects the language of each file from its `+++` path and highlights the old and new
+//! sides of every hunk with the real grammar, so the code inside the hunks keeps its colors.
+//!
+//! The result is a list of [`DiffLine`]s that the [`HtmlDiff`](super::HtmlDiff) and
+//! [`TerminalDiff`](super::TerminalDiff) formatters render with the `diff.plus` and
+//! `diff.minus` backgrounds of the theme, and that custom formatters can render too.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lumis::formatter::diff::{highlight_unified, DiffLineKind};
+//! use lumis::languages::Language;
+//!
+//! let diff = "\
+//! --- a/src/main.rs
+//! +++ b/src/main.rs
+//! @@ -1,1 +1,1 @@
+//! -fn main() {}
+//! +fn main() { run() }
+//! ";
+//!
+//! let lines = highlight_unified(diff, None).unwrap();
+//!
+//! assert_eq!(lines[3].kind, DiffLineKind::Removed);
+//! assert_eq!(lines[3].old_line, Some(1));
+//! assert_eq!(lines[4].kind, DiffLineKind::Added);
+//! assert_eq!(lines[4].language, Language::Rust);
+//! assert_eq!(lines[4].text(), "fn main() { run() }");
+//! ```
+//!
+//! # Side-by-side diffs
+//!
+//! [`highlight_side_by_side()`] compares two versions of a source instead, pairing the
+//! changed lines in [`DiffRow`]s and finding the words that changed inside each pair:
+//!
+//! ```rust
+//! use lumis::formatter::diff::highlight_side_by_side;
+//! use lumis::languages::Language;
+//!
+//! let rows = highlight_side_by_side("let a = 1;\n", "let a = 2;\n", Language::Rust, None).unwrap();
+//!
+//! let old = rows[0].old.as_ref().unwrap();
+//! let new = rows[0].new.as_ref().unwrap();
+//! assert_eq!(old.changes, vec![8..10]);
+//! assert_eq!(new.changes, vec![8..10]);
+//! ```
+
+use super::lines::collect_lines;
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::themes::Theme;
+use similar::{ChangeTag, DiffTag, TextDiff};
+use std::io;
+use std::ops::Range;
+
+/// Kind of a line in a unified diff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiffLineKind {
+    /// File header lines like `diff --git`, `index`, `---` and `+++`.
+    Header,
+    /// Hunk header, e.g. `@@ -1,3 +1,4 @@`.
+    Hunk,
+    /// Unchanged line inside a hunk.
+    Context,
+    /// Line added in the new side.
+    Added,
+    /// Line removed from the old side.
+    Removed,
+    /// The `\ No newline at end of file` marker.
+    NoNewline,
+}
+
+impl DiffLineKind {
+    /// The theme scope used to style the line, if any.
+    pub fn scope(&self) -> Option<&'static str> {
+        match self {
+            DiffLineKind::Added => Some("diff.plus"),
+            DiffLineKind::Removed => Some("diff.minus"),
+            _ => None,
+        }
+    }
+
+    /// The marker that prefixes the line in the diff, empty for headers.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            DiffLineKind::Context => " ",
+            DiffLineKind::Added => "+",
+            DiffLineKind::Removed => "-",
+            _ => "",
+        }
+    }
+
+    /// CSS class suffix used by the HTML formatters.
+    pub fn class(&self) -> &'static str {
+        match self {
+            DiffLineKind::Header => "diff-header",
+            DiffLineKind::Hunk => "diff-hunk",
+            DiffLineKind::Context => "diff-context",
+            DiffLineKind::Added => "diff-added",
+            DiffLineKind::Removed => "diff-removed",
+            DiffLineKind::NoNewline => "diff-no-newline",
+        }
+    }
+}
+
+/// A highlighted line of a unified diff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    /// Kind of the line.
+    pub kind: DiffLineKind,
+    /// Line number in the old file, for context and removed lines.
+    pub old_line: Option<usize>,
+    /// Line number in the new file, for context and added lines.
+    pub new_line: Option<usize>,
+    /// Language used to highlight the line, [`Language::Diff`] for headers.
+    pub language: Language,
+    /// Styled segments of the line, without the marker and the line ending.
+    pub segments: Vec<(String, Style)>,
+}
+
+impl DiffLine {
+    /// The text of the line, without the marker.
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+}
+
+/// Parse and highlight a unified diff.
+///
+/// The language of each file is guessed from its `+++` path (or the `---` path for
+/// deleted files). Removed lines are highlighted with the old side of the hunks and
+/// context and added lines with the new side. Header lines are highlighted with the
+/// [`Diff`](Language::Diff) grammar.
+pub fn highlight_unified(diff: &str, theme: Option<Theme>) -> io::Result<Vec<DiffLine>> {
+    let mut lines = Vec::new();
+    let mut file = FileDiff::default();
+    let (mut old_line, mut new_line) = (0usize, 0usize);
+    let (mut old_remaining, mut new_remaining) = (0usize, 0usize);
+
+    let mut raw_lines = diff.lines().peekable();
+    while let Some(raw) = raw_lines.next() {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+
+        // A hunk shorter than its header claims must not swallow the next hunk or file.
+        // A `--- ` line only starts a new file when a `+++ ` line follows, otherwise it is
+        // a removed line starting with `--`.
+        if raw.starts_with("diff ")
+            || raw.starts_with("@@ ")
+            || (raw.starts_with("--- ")
+                && raw_lines
+                    .peek()
+                    .is_some_and(|next| next.starts_with("+++ ")))
+        {
+            (old_remaining, new_remaining) = (0, 0);
+        }
+        let in_hunk = old_remaining > 0 || new_remaining > 0;
+
+        if in_hunk && !raw.starts_with('\\') {
+            let (kind, content) = match raw.as_bytes().first() {
+                Some(b'+') => (DiffLineKind::Added, &raw[1..]),
+                Some(b'-') => (DiffLineKind::Removed, &raw[1..]),
+                Some(b' ') => (DiffLineKind::Context, &raw[1..]),
+                _ => (DiffLineKind::Context, raw),
+            };
+
+            let (old, new) = match kind {
+                DiffLineKind::Added => (None, Some(new_line)),
+                DiffLineKind::Removed => (Some(old_line), None),
+                _ => (Some(old_line), Some(new_line)),
+            };
+            if old.is_some() {
+                old_line += 1;
+                old_remaining = old_remaining.saturating_sub(1);
+            }
+            if new.is_some() {
+                new_line += 1;
+                new_remaining = new_remaining.saturating_sub(1);
+            }
+
+            file.push(lines.len(), kind, content);
+            lines.push(DiffLine {
+                kind,
+                old_line: old,
+                new_line: new,
+                language: Language::PlainText,
+                segments: Vec::new(),
+            });
+            continue;
+        }
+
+        let kind = if raw.starts_with('\\') {
+            DiffLineKind::NoNewline
+        } else if let Some(hunk) = parse_hunk_header(raw) {
+            (old_line, old_remaining, new_line, new_remaining) = hunk;
+            DiffLineKind::Hunk
+        } else {
+            if raw.starts_with("diff ") || (raw.starts_with("--- ") && !file.is_empty()) {
+                file.highlight(&mut lines, theme.clone())?;
+            }
+            if let Some(path) = raw.strip_prefix("--- ") {
+                file.old_path = Some(strip_path(path).to_string());
+            } else if let Some(path) = raw.strip_prefix("+++ ") {
+                file.new_path = Some(strip_path(path).to_string());
+            }
+            DiffLineKind::Header
+        };
+
+        lines.push(DiffLine {
+            kind,
+            old_line: None,
+            new_line: None,
+            language: Language::Diff,
+            segments: collect_lines(raw, Language::Diff, theme.clone(), None)?
+                .into_iter()
+                .flatten()
+                .collect(),
+        });
+    }
+
+    file.highlight(&mut lines, theme)?;
+
+    Ok(lines)
+}
+
+/// Lines of the file currently being parsed, split into its old and new sides.
+#[derive(Default)]
+struct FileDiff {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    old_source: String,
+    new_source: String,
+    // Index of each line in the output, with its side and line index in that side.
+    lines: Vec<(usize, Side, usize)>,
+    old_count: usize,
+    new_count: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+}
+
+impl FileDiff {
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    fn push(&mut self, index: usize, kind: DiffLineKind, content: &str) {
+        if kind != DiffLineKind::Added {
+            self.old_source.push_str(content);
+            self.old_source.push('\n');
+            if kind == DiffLineKind::Removed {
+                self.lines.push((index, Side::Old, self.old_count));
+            }
+            self.old_count += 1;
+        }
+
+        if kind != DiffLineKind::Removed {
+            self.new_source.push_str(content);
+            self.new_source.push('\n');
+            self.lines.push((index, Side::New, self.new_count));
+            self.new_count += 1;
+        }
+    }
+
+    /// Highlight both sides of the file and fill the segments of its lines.
+    fn highlight(&mut self, lines: &mut [DiffLine], theme: Option<Theme>) -> io::Result<()> {
+        let file = std::mem::take(self);
+        if file.is_empty() {
+            return Ok(());
+        }
+
+        let path = file
+            .new_path
+            .as_deref()
+            .filter(|path| *path != "/dev/null")
+            .or(file.old_path.as_deref());
+        let language = Language::guess(path, &file.new_source);
+
+        let old = collect_lines(&file.old_source, language, theme.clone(), None)?;
+        let new = collect_lines(&file.new_source, language, theme, None)?;
+
+        for (index, side, line) in file.lines {
+            let segments = match side {
+                Side::Old => old.get(line),
+                Side::New => new.get(line),
+            };
+            lines[index].language = language;
+            lines[index].segments = segments.cloned().unwrap_or_default();
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse `@@ -old_start,old_count +new_start,new_count @@` into its four numbers.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (ranges, _) = ranges.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+
+    let parse = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+
+    let (old_start, old_count) = parse(old)?;
+    let (new_start, new_count) = parse(new)?;
+    Some((old_start, old_count, new_start, new_count))
+}
+
+/// Remove the `a/` or `b/` prefix and the trailing timestamp from a header path.
+fn strip_path(path: &str) -> &str {
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// A line of one side of a side-by-side diff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SideLine {
+    /// [`Context`](DiffLineKind::Context), [`Removed`](DiffLineKind::Removed) for the old
+    /// side or [`Added`](DiffLineKind::Added) for the new side.
+    pub kind: DiffLineKind,
+    /// Line number in its source, 1-based.
+    pub line: usize,
+    /// Styled segments of the line, without the line ending.
+    pub segments: Vec<(String, Style)>,
+    /// Byte ranges of the line text that changed compared to the paired line.
+    pub changes: Vec<Range<usize>>,
+}
+
+impl SideLine {
+    /// The text of the line.
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+
+    /// The segments of the line split at the boundaries of [`changes`](SideLine::changes),
+    /// with a flag telling whether each piece changed.
+    pub fn split_changes(&self) -> Vec<(&str, &Style, bool)> {
+        let mut pieces = Vec::new();
+        let mut offset = 0;
+
+        for (text, style) in &self.segments {
+            let end = offset + text.len();
+            let mut bounds = vec![offset, end];
+            for change in &self.changes {
+                bounds.extend([change.start, change.end]);
+            }
+            bounds.retain(|bound| (offset..=end).contains(bound));
+            bounds.sort_unstable();
+            bounds.dedup();
+
+            for pair in bounds.windows(2) {
+                let changed = self
+                    .changes
+                    .iter()
+                    .any(|change| change.start <= pair[0] && pair[1] <= change.end);
+                pieces.push((&text[pair[0] - offset..pair[1] - offset], style, changed));
+            }
+            offset = end;
+        }
+
+        pieces
+    }
+}
+
+/// A row of a side-by-side diff.
+///
+/// Unchanged lines fill both sides, while removed and added lines are paired in order
+/// and leave the other side empty when one side has more lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffRow {
+    /// Line of the old source.
+    pub old: Option<SideLine>,
+    /// Line of the new source.
+    pub new: Option<SideLine>,
+}
+
+/// Compare and highlight two versions of a source.
+///
+/// Both sources are highlighted with `language` in full, so each side keeps its own
+/// parse context. Lines are matched with a line diff, and paired removed and added lines
+/// are compared word by word to fill [`SideLine::changes`].
+pub fn highlight_side_by_side(
+    old: &str,
+    new: &str,
+    language: Language,
+    theme: Option<Theme>,
+) -> io::Result<Vec<DiffRow>> {
+    let old_lines = collect_lines(old, language, theme.clone(), None)?;
+    let new_lines = collect_lines(new, language, theme, None)?;
+
+    let side_line = |lines: &[Vec<(String, Style)>], index: usize, kind| SideLine {
+        kind,
+        line: index + 1,
+        segments: lines.get(index).cloned().unwrap_or_default(),
+        changes: Vec::new(),
+    };
+
+    let mut rows = Vec::new();
+
+    for op in TextDiff::from_lines(old, new).ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+
+        if tag == DiffTag::Equal {
+            for (old_index, new_index) in old_range.zip(new_range) {
+                rows.push(DiffRow {
+                    old: Some(side_line(&old_lines, old_index, DiffLineKind::Context)),
+                    new: Some(side_line(&new_lines, new_index, DiffLineKind::Context)),
+                });
+            }
+            continue;
+        }
+
+        for i in 0..old_range.len().max(new_range.len()) {
+            let mut old_line = (i < old_range.len())
+                .then(|| side_line(&old_lines, old_range.start + i, DiffLineKind::Removed));
+            let mut new_line = (i < new_range.len())
+                .then(|| side_line(&new_lines, new_range.start + i, DiffLineKind::Added));
+
+            if let (Some(old_line), Some(new_line)) = (&mut old_line, &mut new_line) {
+                (old_line.changes, new_line.changes) =
+                    word_changes(&old_line.text(), &new_line.text());
+            }
+
+            rows.push(DiffRow {
+                old: old_line,
+                new: new_line,
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Byte ranges of the words that changed between two lines.
+///
+/// Returns no ranges when the lines have nothing but whitespace in common, since
+/// emphasizing the whole line adds nothing to the line background.
+fn word_changes(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let diff = TextDiff::from_words(old, new);
+    let mut old_changes: Vec<Range<usize>> = Vec::new();
+    let mut new_changes: Vec<Range<usize>> = Vec::new();
+    let (mut old_offset, mut new_offset) = (0, 0);
+    let mut in_common = false;
+
+    let push = |changes: &mut Vec<Range<usize>>, range: Range<usize>| match changes.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => changes.push(range),
+    };
+
+    for change in diff.iter_all_changes() {
+        let len = change.value().len();
+        match change.tag() {
+            ChangeTag::Equal => {
+                in_common |= !change.value().trim().is_empty();
+                old_offset += len;
+                new_offset += len;
+            }
+            ChangeTag::Delete => {
+                push(&mut old_changes, old_offset..old_offset + len);
+                old_offset += len;
+            }
+            ChangeTag::Insert => {
+                push(&mut new_changes, new_offset..new_offset + len);
+                new_offset += len;
+            }
+        }
+    }
+
+    if in_common {
+        (old_changes, new_changes)
+    } else {
+        (Vec::new(), Vec::new())
+    }
+}
+
+/// Resolve the style of the line background and marker for `kind`.
+pub(super) fn line_style(theme: Option<&Theme>, kind: DiffLineKind) -> Option<Style> {
+    theme.and_then(|theme| theme.diff_style(kind.scope()?))
+}
+
+/// Resolve the style of the words that changed in a line of `kind`.
+pub(super) fn change_style(theme: Option<&Theme>, kind: DiffLineKind) -> Option<Style> {
+    theme.and_then(|theme| theme.diff_emphasis_style(kind.scope()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    const DIFF: &str = "\
+diff --git a/lib/app.ex b/lib/app.ex
+index 1111111..2222222 100644
+--- a/lib/app.ex
++++ b/lib/app.ex
+@@ -1,3 +1,3 @@
+ defmodule App do
+-  def hello, do: :world
++  def hello, do: \"world\"
+ end
+diff --git a/README.md b/README.md
+--- a/README.md
++++ b/README.md
+@@ -10 +10,2 @@ intro
+-old
++new
++more
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_parse_unified() {
+        let lines = highlight_unified(DIFF, None).unwrap();
+        let kinds: Vec<_> = lines.iter().map(|line| line.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Hunk,
+                DiffLineKind::Context,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+                DiffLineKind::Context,
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Hunk,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+                DiffLineKind::Added,
+                DiffLineKind::NoNewline,
+            ]
+        );
+
+        assert_eq!((lines[6].old_line, lines[6].new_line), (Some(2), None));
+        assert_eq!((lines[7].old_line, lines[7].new_line), (None, Some(2)));
+        assert_eq!((lines[8].old_line, lines[8].new_line), (Some(3), Some(3)));
+        assert_eq!((lines[15].old_line, lines[15].new_line), (None, Some(11)));
+
+        assert_eq!(lines[6].text(), "  def hello, do: :world");
+        assert_eq!(lines[7].text(), "  def hello, do: \"world\"");
+        assert_eq!(lines[0].text(), "diff --git a/lib/app.ex b/lib/app.ex");
+    }
+
+    #[test]
+    fn test_language_per_file() {
+        let lines = highlight_unified(DIFF, None).unwrap();
+
+        assert_eq!(lines[0].language, Language::Diff);
+        assert_eq!(lines[7].language, Language::Elixir);
+        assert_eq!(lines[14].language, Language::Markdown);
+    }
+
+    #[test]
+    fn test_highlights_code_with_the_file_language() {
+        let theme = themes::get("dracula").unwrap();
+        let lines = highlight_unified(DIFF, Some(theme.clone())).unwrap();
+
+        let atom = lines[6]
+            .segments
+            .iter()
+            .find(|(text, _)| text == ":world")
+            .unwrap();
+        assert_eq!(
+            atom.1.fg,
+            theme.get_style("string.special.symbol").unwrap().fg
+        );
+
+        let string = lines[7]
+            .segments
+            .iter()
+            .find(|(text, _)| text == "\"world\"")
+            .unwrap();
+        assert_eq!(string.1.fg, theme.get_style("string").unwrap().fg);
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let old = "a = 1\nb = 2\nc = 3\n";
+        let new = "a = 1\nb = 20\nd = 4\ne = 5\n";
+        let rows = highlight_side_by_side(old, new, Language::Python, None).unwrap();
+
+        let sides: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.old.as_ref().map(|line| (line.line, line.text())),
+                    row.new.as_ref().map(|line| (line.line, line.text())),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            sides,
+            vec![
+                (Some((1, "a = 1".into())), Some((1, "a = 1".into()))),
+                (Some((2, "b = 2".into())), Some((2, "b = 20".into()))),
+                (Some((3, "c = 3".into())), Some((3, "d = 4".into()))),
+                (None, Some((4, "e = 5".into()))),
+            ]
+        );
+
+        assert_eq!(rows[0].old.as_ref().unwrap().kind, DiffLineKind::Context);
+        assert_eq!(rows[1].old.as_ref().unwrap().kind, DiffLineKind::Removed);
+        assert_eq!(rows[1].new.as_ref().unwrap().kind, DiffLineKind::Added);
+        assert_eq!(rows[1].old.as_ref().unwrap().changes, vec![4..5]);
+        assert_eq!(rows[1].new.as_ref().unwrap().changes, vec![4..6]);
+        // only whitespace and punctuation in common
+        assert_eq!(rows[2].new.as_ref().unwrap().changes, vec![0..1, 4..5]);
+    }
+
+    #[test]
+    fn test_split_changes() {
+        let style = Style::default();
+        let line = SideLine {
+            kind: DiffLineKind::Added,
+            line: 1,
+            segments: vec![
+                ("let".into(), style.clone()),
+                (" x = 10;".into(), style.clone()),
+            ],
+            changes: vec![2..5, 8..10],
+        };
+
+        let pieces: Vec<_> = line
+            .split_changes()
+            .into_iter()
+            .map(|(text, _, changed)| (text, changed))
+            .collect();
+
+        assert_eq!(
+            pieces,
+            vec![
+                ("le", false),
+                ("t", true),
+                (" x", true),
+                (" = ", false),
+                ("10", true),
+                (";", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deleted_file_uses_old_path() {
+        let diff = "--- a/old.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-fn a() {}\n";
+        let lines = highlight_unified(diff, None).unwrap();
+
+        assert_eq!(lines[3].language, Language::Rust);
+        assert_eq!(lines[3].text(), "fn a() {}");
+    }
+
+    #[test]
+    fn test_multi_byte_first_char() {
+        let lines = highlight_unified("@@ -1,2 +1,2 @@\n a\nécrit\n", None).unwrap();
+
+        assert_eq!(lines[2].kind, DiffLineKind::Context);
+        assert_eq!(lines[2].text(), "écrit");
+    }
+
+    #[test]
+    fn test_short_hunk_does_not_swallow_next_file() {
+        let diff = "\
+--- a/a.rs
++++ b/a.rs
+@@ -1,5 +1,5 @@
+-fn a() {}
++fn b() {}
+--- a/b.ex
++++ b/b.ex
+@@ -1 +1 @@
+--- removed comment
++x = 1
+";
+        let lines = highlight_unified(diff, None).unwrap();
+        let kinds: Vec<_> = lines.iter().map(|line| line.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Hunk,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+                DiffLineKind::Header,
+                DiffLineKind::Header,
+                DiffLineKind::Hunk,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+            ]
+        );
+        assert_eq!(lines[8].language, Language::Elixir);
+        assert_eq!(lines[8].text(), "-- removed comment");
+    }
+}
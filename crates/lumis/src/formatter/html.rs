@@ -29,9 +29,20 @@
 //! - [`Formatter`](crate::formatter::Formatter) trait documentation
 //! - [`examples/custom_html_formatter.rs`](https://github.com/leandrocp/lumis/blob/main/examples/custom_html_formatter.rs)
 
+use super::html_multi_themes::{ThemeEntry, ThemeList};
+use super::whitespace::{self, Whitespace};
+use crate::annotations::Annotation;
+use crate::brackets::BracketColors;
+use crate::folding::{CodeFolding, Fold, FoldMode};
+use crate::highlight::highlight_iter_with_language;
 use crate::languages::Language;
-use crate::themes::Theme;
+use crate::navigation::{Occurrence, Symbols};
+use crate::semantic::SemanticColors;
+use crate::themes::{Style, Theme};
+use crate::vendor::tree_sitter_highlight::{HighlightEvent, Highlighter};
+use std::collections::HashMap;
 use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
 
 /// Generate an HTML `<span>` element with inline CSS styles.
 ///
@@ -243,6 +254,9 @@ pub fn text_decoration(td: &TextDecoration) -> &'static str {
 /// Returns only the attributes string (without the span tags), useful when you
 /// need more control over the HTML structure.
 ///
+/// Accepts anything convertible into a [`ThemeList`], like a `HashMap` or a reference to
+/// one, whose themes are sorted by name.
+///
 /// # Example
 ///
 /// ```rust
@@ -258,7 +272,28 @@ pub fn text_decoration(td: &TextDecoration) -> &'static str {
 pub fn span_multi_themes_attrs(
     scope: &str,
     language: Option<Language>,
-    themes: &std::collections::HashMap<String, Theme>,
+    themes: impl Into<ThemeList>,
+    default_theme: Option<&str>,
+    css_variable_prefix: &str,
+    italic: bool,
+    include_highlights: bool,
+) -> String {
+    multi_themes_attrs(
+        scope,
+        language,
+        &themes.into(),
+        default_theme,
+        css_variable_prefix,
+        italic,
+        include_highlights,
+    )
+}
+
+/// [`span_multi_themes_attrs()`] borrowing the themes, used by the formatters for each token.
+pub(crate) fn multi_themes_attrs(
+    scope: &str,
+    language: Option<Language>,
+    themes: &ThemeList,
     default_theme: Option<&str>,
     css_variable_prefix: &str,
     italic: bool,
@@ -364,7 +399,12 @@ pub fn span_multi_themes_attrs(
                 ));
             }
 
-            for (theme_name, theme) in themes.iter() {
+            for ThemeEntry {
+                name: theme_name,
+                theme,
+                ..
+            } in themes
+            {
                 if theme_name != default_name {
                     if let Some(style) = theme.get_style(&specialized_scope) {
                         let sanitized = sanitize_theme_name(theme_name);
@@ -399,7 +439,12 @@ pub fn span_multi_themes_attrs(
             }
         }
     } else {
-        for (theme_name, theme) in themes.iter() {
+        for ThemeEntry {
+            name: theme_name,
+            theme,
+            ..
+        } in themes
+        {
             if let Some(style) = theme.get_style(&specialized_scope) {
                 let sanitized = sanitize_theme_name(theme_name);
 
@@ -466,7 +511,8 @@ pub fn span_multi_themes_attrs(
 /// * `text` - The text content to wrap
 /// * `scope` - The tree-sitter scope name (e.g., "keyword", "string")
 /// * `language` - Optional language for specialized scope lookup
-/// * `themes` - Map of theme name to Theme
+/// * `themes` - Themes with their names, in the order of their CSS variables, as a [`ThemeList`]
+///   or a map, sorted by name
 /// * `default_theme` - Optional name of the default theme (gets inline styles)
 /// * `css_variable_prefix` - CSS variable prefix (e.g., "--lumis")
 /// * `italic` - Whether to enable italic styling
@@ -502,22 +548,23 @@ pub fn span_multi_themes(
     text: &str,
     scope: &str,
     language: Option<Language>,
-    themes: &std::collections::HashMap<String, Theme>,
+    themes: impl Into<ThemeList>,
     default_theme: Option<&str>,
     css_variable_prefix: &str,
     italic: bool,
     include_highlights: bool,
 ) -> String {
     let escaped = escape(text);
+    let themes = themes.into();
 
     if themes.is_empty() {
         return escaped;
     }
 
-    let attrs = span_multi_themes_attrs(
+    let attrs = multi_themes_attrs(
         scope,
         language,
-        themes,
+        &themes,
         default_theme,
         css_variable_prefix,
         italic,
@@ -586,6 +633,608 @@ pub fn escape_braces(text: &str) -> String {
     text.replace('{', "&lbrace;").replace('}', "&rbrace;")
 }
 
+/// Generate the line number gutter for a line.
+///
+/// Creates a `<span class="line-number" aria-hidden="true">N </span>` element with the
+/// number right aligned to `width` digits. The gutter is meant to be non-selectable so
+/// copying the code excludes it: formatters with inline styles pass
+/// [`LINE_NUMBER_STYLE`] in `style`, while the CSS files for linked themes include a
+/// `.line-number` rule.
+///
+/// # Arguments
+///
+/// * `line_number` - The number displayed in the gutter
+/// * `width` - Number of digits of the largest line number
+/// * `style` - Optional inline style attribute content
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::html;
+///
+/// let gutter = html::line_number(7, 2, None);
+/// assert_eq!(gutter, r#"<span class="line-number" aria-hidden="true"> 7 </span>"#);
+/// ```
+pub fn line_number(line_number: usize, width: usize, style: Option<&str>) -> String {
+    let style_attr = if let Some(s) = style {
+        format!(" style=\"{}\"", s)
+    } else {
+        String::new()
+    };
+
+    format!(
+        "<span class=\"line-number\"{} aria-hidden=\"true\">{:>width$} </span>",
+        style_attr,
+        line_number,
+        width = width
+    )
+}
+
+/// Inline style that prevents the line number gutter from being selected.
+pub const LINE_NUMBER_STYLE: &str = "user-select: none; -webkit-user-select: none;";
+
+/// Number of digits needed to display the line numbers of `line_count` lines
+/// starting at `line_number_start`.
+pub(crate) fn line_number_width(line_number_start: usize, line_count: usize) -> usize {
+    (line_number_start + line_count.saturating_sub(1))
+        .to_string()
+        .len()
+}
+
+/// CSS declarations for the `<mark>` elements and the footnote markers of annotations,
+/// colored with the [`annotation_style()`](Theme::annotation_style) of a theme.
+///
+/// Markers are non-selectable so copying the code excludes them.
+pub(crate) fn annotation_declarations(style: Option<&Style>) -> (Vec<String>, Vec<String>) {
+    let fg = style.and_then(|style| style.fg.as_deref());
+    let bg = style.and_then(|style| style.bg.as_deref());
+
+    let mut mark = vec!["color: inherit;".to_string()];
+    if let Some(bg) = bg {
+        mark.push(format!("background-color: {bg};"));
+    }
+    mark.push(match fg {
+        Some(fg) => format!("text-decoration: underline {fg};"),
+        None => "text-decoration: underline;".to_string(),
+    });
+
+    let mut marker = vec![
+        "user-select: none;".to_string(),
+        "-webkit-user-select: none;".to_string(),
+    ];
+    if let Some(fg) = fg {
+        marker.push(format!("color: {fg};"));
+    }
+
+    (mark, marker)
+}
+
+/// Attributes of the `<span>` of a bracket by scope, language and nesting depth.
+pub(crate) type BracketAttrs<'a> = &'a dyn Fn(&str, Language, usize) -> String;
+
+/// Attributes of the `<span>` of a local variable by scope, language and color index.
+pub(crate) type SemanticAttrs<'a> = &'a dyn Fn(&str, Language, usize) -> String;
+
+/// Attributes of the `<span>` of visible whitespace or an indent guide.
+pub(crate) type WhitespaceAttrs<'a> = &'a dyn Fn(whitespace::Kind) -> String;
+
+/// Decorations rendered on top of the token stream by [`decorated_lines()`].
+#[derive(Default)]
+pub(crate) struct LineDecorations<'a> {
+    /// Annotations wrapped in `<mark>` elements.
+    pub annotations: &'a [Annotation],
+    /// Inline style of the annotation marks.
+    pub mark_style: Option<&'a str>,
+    /// Inline style of the annotation footnote markers.
+    pub marker_style: Option<&'a str>,
+    /// Definitions and references linked to each other.
+    pub symbols: Option<&'a Symbols>,
+    /// Prefix of the anchor ids of the definitions.
+    pub symbol_id_prefix: &'a str,
+    /// Inline style of the `<a>` of the references.
+    pub reference_style: Option<&'a str>,
+    /// Matching brackets, with the attributes of their `<span>`.
+    pub brackets: Option<(&'a BracketColors, BracketAttrs<'a>)>,
+    /// Identifiers of local variables, with the attributes of their `<span>`.
+    pub semantic: Option<(&'a SemanticColors, SemanticAttrs<'a>)>,
+    /// Tab expansion, visible whitespace and indent guides, with the attributes of their `<span>`.
+    pub whitespace: Option<(Whitespace, WhitespaceAttrs<'a>)>,
+    /// Collapse the line breaks and the indentation around them into a single space, for
+    /// inline code.
+    pub collapse_lines: bool,
+}
+
+/// Render the lines of `source` with the given decorations.
+///
+/// Built on the token stream of [`highlight_iter()`](crate::highlight::highlight_iter).
+/// Each token is wrapped in a `<span>` with the attributes returned by `attrs`, and the
+/// lines keep the shape of the lines of the HTML renderer, including the trailing newline,
+/// so formatters can use them in place of the renderer lines when there are decorations.
+///
+/// Annotations are numbered from 1 in the order they're given. The number is used in
+/// `data-annotation` and in the `<sup class="annotation-marker">` placed after the
+/// annotated text, while the label and note become the tooltip of the mark.
+///
+/// Definitions are wrapped in a `<span class="symbol-definition">` with the anchor `id` of
+/// the definition, and references in an `<a class="symbol-reference">` linking to it. Both
+/// carry the id of the definition, prefixed by the symbol id prefix, in `data-symbol`.
+///
+/// Brackets are wrapped in a `<span>` with the attributes returned by the bracket attributes
+/// function, given the nesting depth of their pair, and the identifiers of local variables
+/// with the attributes returned by the semantic attributes function, given their color index.
+///
+/// Tabs are expanded with the whitespace options, and visible whitespace and indent guides
+/// are wrapped in a `<span>` with the attributes returned by the whitespace attributes function.
+///
+/// With `collapse_lines`, the text of the tokens is trimmed to the text of its line, blank
+/// lines are dropped and the others are joined with a space, into a single line without
+/// the trailing newline.
+pub(crate) fn decorated_lines<F>(
+    source: &str,
+    language: Language,
+    decorations: &LineDecorations,
+    attrs: F,
+) -> io::Result<Vec<String>>
+where
+    F: Fn(&str, Language) -> String,
+{
+    let LineDecorations {
+        annotations,
+        mark_style,
+        marker_style,
+        symbols,
+        symbol_id_prefix,
+        reference_style,
+        brackets,
+        semantic,
+        whitespace,
+        collapse_lines,
+    } = *decorations;
+    let ranges: Vec<Range<usize>> = annotations
+        .iter()
+        .map(|annotation| annotation.byte_range(source))
+        .collect();
+    let style_attr = |style: Option<&str>| {
+        style
+            .map(|style| format!(" style=\"{}\"", style))
+            .unwrap_or_default()
+    };
+    let marker = |i: usize| {
+        format!(
+            "<sup class=\"annotation-marker\"{} data-annotation=\"{}\">{}</sup>",
+            style_attr(marker_style),
+            i + 1,
+            i + 1
+        )
+    };
+    // Markers of empty annotations, which point at a position rather than a text.
+    let points = |offset: usize| {
+        ranges
+            .iter()
+            .enumerate()
+            .filter(move |(_, range)| range.is_empty() && range.start == offset)
+            .map(|(i, _)| marker(i))
+            .collect::<String>()
+    };
+
+    // Close the open marks at `offset`, followed by the markers of the annotations ending there.
+    let close = |line: &mut String, open: &mut Vec<usize>, offset: usize| {
+        while let Some(i) = open.pop() {
+            line.push_str("</mark>");
+            if ranges[i].end == offset {
+                line.push_str(&marker(i));
+            }
+        }
+    };
+
+    let render = |range: Range<usize>| match whitespace {
+        Some((whitespace, whitespace_attrs)) => whitespace
+            .render(source, range)
+            .into_iter()
+            .map(|(text, kind, _)| match kind {
+                whitespace::Kind::Text => escape(&text),
+                kind => format!("<span {}>{}</span>", whitespace_attrs(kind), escape(&text)),
+            })
+            .collect(),
+        None => escape(&source[range]),
+    };
+
+    // Range of `start..end` within the text of its line, without the surrounding whitespace.
+    let trimmed = |start: usize, end: usize| {
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = &source[line_start..line_end];
+        let text_start = line_start + text.len() - text.trim_start().len();
+        let text_end = line_start + text.trim_end().len();
+        start.max(text_start)..end.min(text_end)
+    };
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut open: Vec<usize> = Vec::new();
+    // Whether a space is due before the next text, when collapsing a line break.
+    let mut line_break = false;
+    // Injected languages resolved by name, so each one is guessed only once.
+    let mut languages: HashMap<String, Language> = HashMap::new();
+
+    highlight_iter_with_language(
+        source,
+        language,
+        None,
+        |_text, range, scope, language, _style| {
+            let language = match languages.get(language) {
+                Some(&language) => language,
+                None => *languages
+                    .entry(language.to_string())
+                    .or_insert_with(|| Language::guess(Some(language), "")),
+            };
+            let mut start = range.start;
+
+            while start < range.end {
+                let mut end = match source[start..range.end].find('\n') {
+                    Some(0) => start + 1,
+                    Some(offset) => start + offset,
+                    None => range.end,
+                };
+                let boundaries = ranges
+                    .iter()
+                    .flat_map(|range| [range.start, range.end])
+                    .chain(symbols.and_then(|symbols| symbols.next_boundary(start)))
+                    .chain(brackets.and_then(|(colors, _)| colors.next_boundary(start)))
+                    .chain(semantic.and_then(|(colors, _)| colors.next_boundary(start)));
+                for boundary in boundaries {
+                    if boundary > start && boundary < end {
+                        end = boundary;
+                    }
+                }
+
+                let text = &source[start..end];
+                let covering: Vec<usize> = (0..ranges.len())
+                    .filter(|&i| ranges[i].start <= start && end <= ranges[i].end)
+                    .filter(|&i| !ranges[i].is_empty())
+                    .collect();
+
+                // Marks are kept open across tokens and closed at the end of each line.
+                if text == "\n" || covering != open {
+                    close(&mut line, &mut open, start);
+                }
+                line.push_str(&points(start));
+
+                if text == "\n" {
+                    if collapse_lines {
+                        line_break = !line.is_empty();
+                    } else {
+                        line.push('\n');
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    start = end;
+                    continue;
+                }
+
+                let text_range = if collapse_lines {
+                    trimmed(start, end)
+                } else {
+                    start..end
+                };
+                if text_range.is_empty() {
+                    start = end;
+                    continue;
+                }
+                if std::mem::take(&mut line_break) {
+                    line.push(' ');
+                }
+
+                if open.is_empty() {
+                    for &i in &covering {
+                        line.push_str(&format!(
+                            "<mark class=\"annotation\"{} data-annotation=\"{}\" title=\"{}\">",
+                            style_attr(mark_style),
+                            i + 1,
+                            escape(&annotations[i].title())
+                        ));
+                    }
+                    open = covering;
+                }
+
+                let colored_attrs = brackets
+                    .and_then(|(colors, bracket_attrs)| {
+                        Some(bracket_attrs(scope, language, colors.depth_at(start)?))
+                    })
+                    .or_else(|| {
+                        semantic.and_then(|(colors, semantic_attrs)| {
+                            Some(semantic_attrs(scope, language, colors.color_at(start)?))
+                        })
+                    });
+                let token = if let Some(colored_attrs) = colored_attrs {
+                    format!("<span {}>{}</span>", colored_attrs, render(text_range))
+                } else if scope.is_empty() {
+                    render(text_range)
+                } else {
+                    format!(
+                        "<span {}>{}</span>",
+                        attrs(scope, language),
+                        render(text_range)
+                    )
+                };
+
+                match symbols.and_then(|symbols| symbols.at(start)) {
+                    Some(Occurrence::Definition(def)) => {
+                        let symbol = format!("{}{}", symbol_id_prefix, def.id);
+                        let id = if def.range.start == start {
+                            format!(" id=\"{}\"", symbol)
+                        } else {
+                            String::new()
+                        };
+                        line.push_str(&format!(
+                            "<span class=\"symbol-definition\"{} data-symbol=\"{}\">{}</span>",
+                            id, symbol, token
+                        ));
+                    }
+                    Some(Occurrence::Reference(def)) => {
+                        let symbol = format!("{}{}", symbol_id_prefix, def.id);
+                        line.push_str(&format!(
+                            "<a class=\"symbol-reference\"{} href=\"#{}\" data-symbol=\"{}\">{}</a>",
+                            style_attr(reference_style),
+                            symbol,
+                            symbol,
+                            token
+                        ));
+                    }
+                    None => line.push_str(&token),
+                }
+
+                start = end;
+            }
+
+            Ok::<_, io::Error>(())
+        },
+    )
+    .map_err(io::Error::other)?;
+
+    close(&mut line, &mut open, source.len());
+    line.push_str(&points(source.len()));
+    if collapse_lines {
+        lines.push(line);
+    } else if !line.is_empty() || lines.is_empty() {
+        line.push('\n');
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// Attributes of the `<span>` of a bracket or a local variable with inline styles: the
+/// theme style of `scope` with `color` as foreground.
+pub(crate) fn recolored_inline_attrs(
+    scope: &str,
+    language: Language,
+    theme: Option<&Theme>,
+    color: Option<&str>,
+    italic: bool,
+    include_highlights: bool,
+) -> String {
+    let style = theme
+        .filter(|_| !scope.is_empty())
+        .and_then(|theme| theme.get_style(&format!("{}.{}", scope, language.id_name())))
+        .cloned()
+        .unwrap_or_default();
+
+    let mut attrs = String::new();
+    if include_highlights && !scope.is_empty() {
+        attrs.push_str(&format!("data-highlight=\"{}\" ", scope));
+    }
+    let style = Style {
+        fg: color.map(str::to_string).or(style.fg),
+        ..style
+    };
+    attrs.push_str(&format!("style=\"{}\"", style.css(italic, " ")));
+    attrs
+}
+
+/// Scopes of the nested highlights covering a run of text, outermost first, with the
+/// language each one belongs to.
+pub(crate) type ScopeStack<'a> = &'a [(&'static str, Language)];
+
+/// Render `source` as lines of flat, non-overlapping `<span>` runs instead of nested spans.
+///
+/// `attrs` returns the attributes of a run from the scopes covering it. Adjacent runs with
+/// the same attributes are merged into one span, and runs without attributes aren't wrapped.
+pub(crate) fn merged_lines<F>(source: &str, language: Language, attrs: F) -> io::Result<Vec<String>>
+where
+    F: Fn(ScopeStack) -> String,
+{
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(language.config(), source.as_bytes(), None, |injected| {
+            Some(Language::guess(Some(injected), "").config())
+        })
+        .map_err(io::Error::other)?;
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut stack: Vec<(&'static str, Language)> = Vec::new();
+    // Attributes and escaped text of the run waiting to be merged with the next one.
+    let mut run: Option<(String, String)> = None;
+
+    let flush = |line: &mut String, run: &mut Option<(String, String)>| match run.take() {
+        Some((attrs, text)) if attrs.is_empty() => line.push_str(&text),
+        Some((attrs, text)) => line.push_str(&format!("<span {}>{}</span>", attrs, text)),
+        None => {}
+    };
+
+    for event in events {
+        match event.map_err(io::Error::other)? {
+            HighlightEvent::HighlightStart {
+                highlight,
+                language,
+            } => stack.push((
+                crate::constants::HIGHLIGHT_NAMES[highlight.0],
+                Language::guess(Some(&language), ""),
+            )),
+            HighlightEvent::HighlightEnd => {
+                stack.pop();
+            }
+            HighlightEvent::Source { start, end } => {
+                let run_attrs = attrs(&stack);
+                for (i, text) in source[start..end].split('\n').enumerate() {
+                    if i > 0 {
+                        flush(&mut line, &mut run);
+                        line.push('\n');
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match run {
+                        Some((ref attrs, ref mut run_text)) if *attrs == run_attrs => {
+                            run_text.push_str(&escape(text))
+                        }
+                        _ => {
+                            flush(&mut line, &mut run);
+                            run = Some((run_attrs.clone(), escape(text)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    flush(&mut line, &mut run);
+    if !line.is_empty() || lines.is_empty() {
+        line.push('\n');
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// Attributes of a run of text merged by [`merged_lines()`] with inline styles: the inherited
+/// style of `scopes` and, with `include_highlights`, the innermost scope.
+pub(crate) fn merged_inline_attrs(
+    scopes: ScopeStack,
+    theme: Option<&Theme>,
+    italic: bool,
+    include_highlights: bool,
+) -> String {
+    let mut attrs = Vec::new();
+    if let Some((scope, _)) = scopes.last().filter(|_| include_highlights) {
+        attrs.push(format!("data-highlight=\"{}\"", scope));
+    }
+    let css = theme
+        .map(|theme| inherited_style(theme, scopes).css(italic, " "))
+        .unwrap_or_default();
+    if !css.is_empty() {
+        attrs.push(format!("style=\"{}\"", css));
+    }
+    attrs.join(" ")
+}
+
+/// Style of a run of text covered by the nested highlights of `scopes`, as it renders with
+/// nested spans: the inner scopes override the colors of the outer ones and inherit the rest.
+pub(crate) fn inherited_style(theme: &Theme, scopes: ScopeStack) -> Style {
+    scopes
+        .iter()
+        .filter_map(|(scope, language)| {
+            theme.get_style(&format!("{}.{}", scope, language.id_name()))
+        })
+        .fold(Style::default(), |outer, inner| Style {
+            fg: inner.fg.clone().or(outer.fg),
+            bg: inner.bg.clone().or(outer.bg),
+            bold: inner.bold || outer.bold,
+            italic: inner.italic || outer.italic,
+            text_decoration: TextDecoration {
+                underline: match inner.text_decoration.underline {
+                    UnderlineStyle::None => outer.text_decoration.underline,
+                    underline => underline,
+                },
+                strikethrough: inner.text_decoration.strikethrough
+                    || outer.text_decoration.strikethrough,
+            },
+        })
+}
+
+/// Attributes of the `<span>` of a bracket with CSS classes: the class of `scope` and a
+/// `rainbow-bracket-N` class styled by the theme CSS files.
+///
+/// Custom palettes aren't part of the theme CSS, so their color is set inline.
+pub(crate) fn bracket_linked_attrs(
+    scope: &str,
+    class_prefix: &str,
+    colors: &BracketColors,
+    depth: usize,
+    custom_palette: bool,
+) -> String {
+    let mut class = format!(
+        "rainbow-bracket-{}",
+        depth % crate::brackets::PALETTE_SIZE + 1
+    );
+    if !scope.is_empty() {
+        class = format!("{}{} {}", class_prefix, scope_to_class(scope), class);
+    }
+
+    match colors.color(depth).filter(|_| custom_palette) {
+        Some(color) => format!("class=\"{}\" style=\"color: {};\"", class, color),
+        None => format!("class=\"{}\"", class),
+    }
+}
+
+/// Attributes of the `<span>` of a local variable with CSS classes: the class of `scope` and
+/// a `semantic-N` class styled by the theme CSS files.
+pub(crate) fn semantic_linked_attrs(scope: &str, class_prefix: &str, color: usize) -> String {
+    let class = format!("semantic-{}", color % crate::semantic::PALETTE_SIZE + 1);
+    if scope.is_empty() {
+        format!("class=\"{}\"", class)
+    } else {
+        format!(
+            "class=\"{}{} {}\"",
+            class_prefix,
+            scope_to_class(scope),
+            class
+        )
+    }
+}
+
+/// Declarations of the `<a>` of the references of code navigation, which keep the color of
+/// the token and drop the link underline.
+pub(crate) const SYMBOL_REFERENCE_CSS: &str = "color: inherit; text-decoration: none;";
+
+/// `<script>` highlighting all the uses of a symbol while one of them is hovered, for the
+/// output of the `code_navigation` option.
+///
+/// Include it once per page, after the highlighted code or with `defer`. It adds a single
+/// `.symbol-hover` rule to the page and toggles that class on the elements of the hovered
+/// symbol in the same `<pre>`.
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::html;
+///
+/// let script = html::code_navigation_script();
+/// assert!(script.starts_with("<script>"));
+/// assert!(script.contains("symbol-hover"));
+/// ```
+pub fn code_navigation_script() -> &'static str {
+    concat!(
+        "<script>(() => {\n",
+        "  const style = document.createElement(\"style\");\n",
+        "  style.textContent = \".symbol-hover { background-color: color-mix(in srgb, currentColor 25%, transparent); }\";\n",
+        "  document.head.append(style);\n",
+        "  const toggle = (event, hover) => {\n",
+        "    const symbol = event.target.closest && event.target.closest(\"[data-symbol]\");\n",
+        "    if (!symbol) return;\n",
+        "    const root = symbol.closest(\"pre\") || document;\n",
+        "    const selector = `[data-symbol=\"${CSS.escape(symbol.dataset.symbol)}\"]`;\n",
+        "    for (const use of root.querySelectorAll(selector)) use.classList.toggle(\"symbol-hover\", hover);\n",
+        "  };\n",
+        "  document.addEventListener(\"mouseover\", (event) => toggle(event, true));\n",
+        "  document.addEventListener(\"mouseout\", (event) => toggle(event, false));\n",
+        "})();</script>\n"
+    )
+}
+
 /// Wrap content in a line div with optional class and style attributes.
 ///
 /// Creates a `<div class="line..." data-line="N">content</div>` element
@@ -637,6 +1286,83 @@ pub fn wrap_line(
     )
 }
 
+/// Folds of a source with the markup wrapping its lines, see [`folding`](crate::folding).
+pub(crate) struct LineFolds {
+    folds: Vec<Fold>,
+    mode: FoldMode,
+    line_number_start: usize,
+}
+
+impl LineFolds {
+    /// Find the folds of `source`, leaving out the ones not entirely inside `visible_lines`.
+    pub(crate) fn new(
+        source: &str,
+        language: Language,
+        folding: &CodeFolding,
+        visible_lines: Option<&RangeInclusive<usize>>,
+        line_number_start: usize,
+    ) -> Self {
+        let folds = crate::folding::find_folds(source, language, folding.min_lines)
+            .into_iter()
+            .filter(|fold| {
+                visible_lines.is_none_or(|lines| {
+                    lines.contains(&fold.start_line) && lines.contains(&fold.end_line)
+                })
+            })
+            .collect();
+
+        Self {
+            folds,
+            mode: folding.mode,
+            line_number_start,
+        }
+    }
+
+    /// Wrap `line`, the `<div class="line">` of the 1-based `line_number` of the source, with
+    /// the markup opening and closing the folds around it.
+    pub(crate) fn wrap(&self, line_number: usize, line: String) -> String {
+        let start = self
+            .folds
+            .iter()
+            .find(|fold| fold.start_line == line_number);
+        let closing = self
+            .folds
+            .iter()
+            .filter(|fold| fold.end_line == line_number)
+            .count();
+
+        match self.mode {
+            FoldMode::Details => {
+                let mut wrapped = line;
+                if start.is_some() {
+                    // `<summary>` only allows phrasing content, so its line is a `<span>`.
+                    let summary = match wrapped
+                        .strip_prefix("<div")
+                        .and_then(|line| line.strip_suffix("</div>"))
+                    {
+                        Some(line) => format!("<span{line}</span>"),
+                        None => wrapped,
+                    };
+                    wrapped = format!("<details class=\"fold\" open><summary>{summary}</summary>");
+                }
+                wrapped.push_str(&"</details>".repeat(closing));
+                wrapped
+            }
+            FoldMode::DataAttributes => match start {
+                Some(fold) => line.replacen(
+                    "data-line=",
+                    &format!(
+                        "data-fold-end=\"{}\" data-line=",
+                        self.line_number_start + fold.end_line - 1
+                    ),
+                    1,
+                ),
+                None => line,
+            },
+        }
+    }
+}
+
 /// Map tree-sitter scope to CSS class name.
 ///
 /// Converts scope names to their corresponding CSS class names using the
@@ -788,11 +1514,61 @@ pub fn closing_tags(output: &mut dyn Write) -> io::Result<()> {
     close_pre_tag(output)
 }
 
+/// Write the highlighted `lines` as a single `<code class="lumis-inline">` element, for code
+/// inside prose.
+///
+/// There's no `<pre>` element nor line wrappers: the lines are expected to be rendered by
+/// [`decorated_lines()`] with `collapse_lines`, and are written as they are. `class` is
+/// appended to the classes of the element, and the colors of `theme` are inlined.
+pub(crate) fn write_inline_code(
+    output: &mut dyn Write,
+    lang: &Language,
+    class: Option<&str>,
+    theme: Option<&Theme>,
+    lines: &[String],
+) -> io::Result<()> {
+    let class = match class {
+        Some(class) => format!("lumis-inline language-{} {class}", lang.id_name()),
+        None => format!("lumis-inline language-{}", lang.id_name()),
+    };
+    let style = theme
+        .and_then(|theme| theme.pre_style(" "))
+        .map(|style| format!(" style=\"{style}\""))
+        .unwrap_or_default();
+
+    write!(
+        output,
+        "<code class=\"{class}\" translate=\"no\"{style}>{}</code>",
+        escape_braces(&lines.concat())
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_str_eq;
 
+    #[test]
+    fn test_decorated_lines_collapse_lines() {
+        let annotations = [Annotation::new(9..13, "a > b")];
+        let lines = decorated_lines(
+            "foo(\n    bar,  \n\n)\n",
+            Language::Rust,
+            &LineDecorations {
+                annotations: &annotations,
+                collapse_lines: true,
+                ..Default::default()
+            },
+            |scope, _| format!("class=\"{}\"", scope),
+        )
+        .unwrap();
+
+        assert_str_eq!(
+            lines.concat(),
+            r#"<span class="function.call">foo</span><span class="punctuation.bracket">(</span> <mark class="annotation" data-annotation="1" title="a &gt; b"><span class="variable">bar</span><span class="punctuation.delimiter">,</span></mark><sup class="annotation-marker" data-annotation="1">1</sup> <span class="punctuation.bracket">)</span>"#
+        );
+    }
+
     #[test]
     fn test_escape_all_entities() {
         assert_eq!(
@@ -0,0 +1,409 @@
+//! HTML formatter for syntax-aware unified diffs.
+//!
+//! This module provides the [`HtmlDiff`] formatter that renders a unified diff with the
+//! code of each file highlighted with its own language, see the [diff](super::diff) module.
+//! Added and removed lines get the background of the `diff.plus` and `diff.minus` scopes
+//! of the theme.
+//!
+//! # Example Output
+//!
+//! For an added line of a Rust file with the dracula theme, the formatter generates:
+//!
+//! ```html
+//! <div class="line diff-added" style="background-color: #445352;" data-new-line="1"><span style="color: #b3f6c0;">+</span><span style="color: #8be9fd;">fn</span> ...
+//! </div>
+//! ```
+//!
+//! [`HtmlDiff::format_side_by_side()`] renders two versions of a source as a two-column
+//! `<table>` instead, with a line number gutter on each side and the words that changed
+//! inside modified lines wrapped in `<span class="diff-change">`.
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::diff::{
+    change_style, highlight_side_by_side, highlight_unified, line_style, DiffLine, SideLine,
+};
+use super::Formatter;
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::themes::Theme;
+use derive_builder::Builder;
+use std::io::{self, Write};
+
+/// HTML formatter for unified diffs with inline styles.
+///
+/// Use [`HtmlDiffBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Code review bots posting colorized diffs
+/// - Rendering patches and changelogs in web pages
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{HtmlDiffBuilder, themes, formatter::Formatter};
+///
+/// let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -1 +1 @@\n-fn a() {}\n+fn b() {}\n";
+///
+/// let formatter = HtmlDiffBuilder::new()
+///     .theme(themes::get("dracula").ok())
+///     .line_numbers(true)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(diff, &mut output).unwrap();
+/// let html = String::from_utf8(output).unwrap();
+///
+/// assert!(html.contains(r#"<div class="line diff-added" style="background-color: #445352;" data-new-line="1">"#));
+/// assert!(html.contains(r#"<span style="color: #50fa7b;">b</span>"#));
+/// ```
+#[derive(Builder, Clone, Debug, Default)]
+#[builder(default)]
+pub struct HtmlDiff {
+    theme: Option<Theme>,
+    pre_class: Option<String>,
+    italic: bool,
+    /// Show a gutter with the old and new line numbers.
+    line_numbers: bool,
+}
+
+impl HtmlDiffBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl HtmlDiff {
+    pub fn new(theme: Option<Theme>) -> Self {
+        Self {
+            theme,
+            ..Default::default()
+        }
+    }
+
+    /// Render a side-by-side comparison of two versions of a source.
+    ///
+    /// Both sources are highlighted with `language`. Each row of the `<table>` holds the
+    /// old line on the left and the new line on the right, each with its own line number,
+    /// see [`highlight_side_by_side()`](super::diff::highlight_side_by_side).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lumis::{HtmlDiffBuilder, languages::Language, themes};
+    ///
+    /// let formatter = HtmlDiffBuilder::new()
+    ///     .theme(themes::get("github_light").ok())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut output = Vec::new();
+    /// formatter
+    ///     .format_side_by_side("let a = 1;\n", "let a = 2;\n", Language::Rust, &mut output)
+    ///     .unwrap();
+    /// let html = String::from_utf8(output).unwrap();
+    ///
+    /// assert!(html.starts_with(r#"<table class="lumis diff-side-by-side""#));
+    /// assert!(html.contains(r#"<span class="diff-change" style="color: #0550ae; background-color: #91bc9d;">2</span>"#));
+    /// ```
+    pub fn format_side_by_side(
+        &self,
+        old: &str,
+        new: &str,
+        language: Language,
+        output: &mut dyn Write,
+    ) -> io::Result<()> {
+        let rows = highlight_side_by_side(old, new, language, self.theme.clone())?;
+
+        let class = match &self.pre_class {
+            Some(pre_class) => format!("lumis diff-side-by-side {}", pre_class),
+            None => "lumis diff-side-by-side".to_string(),
+        };
+        let style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.pre_style(" "))
+            .map_or("border-collapse: collapse;".to_string(), |pre_style| {
+                format!("{} border-collapse: collapse;", pre_style)
+            });
+        write!(
+            output,
+            "<table class=\"{}\" style=\"{}\"><tbody>",
+            class, style
+        )?;
+
+        for row in &rows {
+            write!(output, "<tr>")?;
+            self.write_side(output, row.old.as_ref(), language)?;
+            self.write_side(output, row.new.as_ref(), language)?;
+            write!(output, "</tr>")?;
+        }
+
+        write!(output, "</tbody></table>")
+    }
+
+    fn gutter_style(&self) -> String {
+        let gutter_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .map(|style| style.css(false, " "))
+            .unwrap_or_default();
+
+        format!(
+            "{} {}",
+            crate::formatter::html::LINE_NUMBER_STYLE,
+            gutter_style
+        )
+        .trim()
+        .to_string()
+    }
+
+    fn write_segment(
+        &self,
+        output: &mut dyn Write,
+        text: &str,
+        style: &Style,
+        class: Option<&str>,
+    ) -> io::Result<()> {
+        let escaped = crate::formatter::html::escape(text);
+        let css = style.css(self.italic, " ");
+        let class = class
+            .map(|class| format!(" class=\"{}\"", class))
+            .unwrap_or_default();
+
+        if css.is_empty() && class.is_empty() {
+            write!(output, "{}", escaped)
+        } else if css.is_empty() {
+            write!(output, "<span{}>{}</span>", class, escaped)
+        } else {
+            write!(
+                output,
+                "<span{} style=\"{}\">{}</span>",
+                class, css, escaped
+            )
+        }
+    }
+
+    fn write_side(
+        &self,
+        output: &mut dyn Write,
+        line: Option<&SideLine>,
+        language: Language,
+    ) -> io::Result<()> {
+        let Some(line) = line else {
+            write!(
+                output,
+                "<td class=\"line-number\" style=\"{}\" aria-hidden=\"true\"></td><td class=\"line diff-empty\"></td>",
+                self.gutter_style()
+            )?;
+            return Ok(());
+        };
+
+        write!(
+            output,
+            "<td class=\"line-number\" style=\"{} text-align: right; vertical-align: top;\" aria-hidden=\"true\">{}</td>",
+            self.gutter_style(),
+            line.line
+        )?;
+
+        let bg = line_style(self.theme.as_ref(), line.kind).and_then(|style| style.bg);
+        let change_bg = change_style(self.theme.as_ref(), line.kind).and_then(|style| style.bg);
+
+        write!(output, "<td class=\"line {}\" style=\"", line.kind.class())?;
+        if let Some(bg) = &bg {
+            write!(output, "background-color: {}; ", bg)?;
+        }
+        write!(
+            output,
+            "white-space: pre;\" data-line=\"{}\"><code class=\"language-{}\">",
+            line.line,
+            language.id_name()
+        )?;
+
+        for (text, style, changed) in line.split_changes() {
+            if changed {
+                let style = Style {
+                    bg: change_bg.clone().or(style.bg.clone()),
+                    ..style.clone()
+                };
+                self.write_segment(output, text, &style, Some("diff-change"))?;
+            } else {
+                self.write_segment(output, text, style, None)?;
+            }
+        }
+
+        write!(output, "</code></td>")
+    }
+
+    fn write_line(
+        &self,
+        output: &mut dyn Write,
+        line: &DiffLine,
+        number_width: usize,
+    ) -> io::Result<()> {
+        let style = line_style(self.theme.as_ref(), line.kind);
+
+        write!(output, "<div class=\"line {}\"", line.kind.class())?;
+        if let Some(bg) = style.as_ref().and_then(|style| style.bg.as_ref()) {
+            write!(output, " style=\"background-color: {};\"", bg)?;
+        }
+        if let Some(old_line) = line.old_line {
+            write!(output, " data-old-line=\"{}\"", old_line)?;
+        }
+        if let Some(new_line) = line.new_line {
+            write!(output, " data-new-line=\"{}\"", new_line)?;
+        }
+        write!(output, ">")?;
+
+        if self.line_numbers {
+            let number = |number: Option<usize>| {
+                number.map_or(" ".repeat(number_width), |number| {
+                    format!("{:>width$}", number, width = number_width)
+                })
+            };
+            write!(
+                output,
+                "<span class=\"line-number\" style=\"{}\" aria-hidden=\"true\">{} {} </span>",
+                self.gutter_style(),
+                number(line.old_line),
+                number(line.new_line)
+            )?;
+        }
+
+        let marker = line.kind.marker();
+        match style.as_ref().and_then(|style| style.fg.as_ref()) {
+            Some(fg) if !marker.trim().is_empty() => {
+                write!(output, "<span style=\"color: {};\">{}</span>", fg, marker)?
+            }
+            _ => write!(output, "{}", marker)?,
+        }
+
+        for (text, style) in &line.segments {
+            self.write_segment(output, text, style, None)?;
+        }
+
+        write!(output, "\n</div>")
+    }
+}
+
+impl Formatter for HtmlDiff {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let lines = highlight_unified(source, self.theme.clone())?;
+        let number_width = lines
+            .iter()
+            .flat_map(|line| [line.old_line, line.new_line])
+            .flatten()
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+
+        crate::formatter::html::open_pre_tag(
+            output,
+            self.pre_class.as_deref(),
+            self.theme.as_ref(),
+        )?;
+        crate::formatter::html::open_code_tag(output, &Language::Diff)?;
+
+        for line in &lines {
+            self.write_line(output, line, number_width)?;
+        }
+
+        crate::formatter::html::closing_tags(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    const DIFF: &str = "\
+--- a/app.rb
++++ b/app.rb
+@@ -1,2 +1,2 @@
+ x = 1
+-puts x
++puts \"<x>\"
+";
+
+    fn render(formatter: &HtmlDiff) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(DIFF, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_lines() {
+        let result = render(&HtmlDiff::new(None));
+
+        assert!(result.starts_with(
+            r#"<pre class="lumis"><code class="language-diff" translate="no" tabindex="0">"#
+        ));
+        assert!(result.contains(
+            "<div class=\"line diff-context\" data-old-line=\"1\" data-new-line=\"1\"> x = 1\n</div>"
+        ));
+        assert!(
+            result.contains("<div class=\"line diff-removed\" data-old-line=\"2\">-puts x\n</div>")
+        );
+        assert!(result.contains(
+            "<div class=\"line diff-added\" data-new-line=\"2\">+puts &quot;&lt;x&gt;&quot;\n</div>"
+        ));
+        assert!(result.ends_with("</code></pre>"));
+    }
+
+    #[test]
+    fn test_theme_backgrounds() {
+        let theme = themes::get("github_light").unwrap();
+        let result = render(&HtmlDiff::new(Some(theme)));
+
+        assert!(result.contains(
+            r#"<div class="line diff-removed" style="background-color: #e4b7be;" data-old-line="2"><span style="color: #d1242f;">-</span>"#
+        ));
+        assert!(result.contains(
+            r#"<div class="line diff-added" style="background-color: #b8d0bf;" data-new-line="2"><span style="color: #1a7f37;">+</span>"#
+        ));
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let mut buffer = Vec::new();
+        HtmlDiff::new(None)
+            .format_side_by_side("a\nb c\n", "a\nb d\ne\n", Language::PlainText, &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let gutter = r#"<td class="line-number" style="user-select: none; -webkit-user-select: none; text-align: right; vertical-align: top;" aria-hidden="true">"#;
+        assert!(result.starts_with(
+            r#"<table class="lumis diff-side-by-side" style="border-collapse: collapse;"><tbody><tr>"#
+        ));
+        assert!(result.contains(&format!(
+            r#"{}2</td><td class="line diff-removed" style="white-space: pre;" data-line="2"><code class="language-plaintext">b <span class="diff-change">c</span></code></td>"#,
+            gutter
+        )));
+        assert!(result.contains(&format!(
+            r#"{}2</td><td class="line diff-added" style="white-space: pre;" data-line="2"><code class="language-plaintext">b <span class="diff-change">d</span></code></td>"#,
+            gutter
+        )));
+        assert!(result.contains(
+            r#"<td class="line-number" style="user-select: none; -webkit-user-select: none;" aria-hidden="true"></td><td class="line diff-empty"></td>"#
+        ));
+        assert!(result.ends_with("</tr></tbody></table>"));
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        let formatter = HtmlDiffBuilder::new().line_numbers(true).build().unwrap();
+        let result = render(&formatter);
+
+        assert!(result.contains(
+            r#"<span class="line-number" style="user-select: none; -webkit-user-select: none;" aria-hidden="true">2   </span>-puts x"#
+        ));
+        assert!(result.contains(
+            r#"<span class="line-number" style="user-select: none; -webkit-user-select: none;" aria-hidden="true">  2 </span>+puts"#
+        ));
+    }
+}
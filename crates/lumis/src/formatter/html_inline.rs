@@ -15,8 +15,16 @@
 //!
 //! See the [formatter](crate::formatter) module for more information and examples.
 
+use super::html::{BracketAttrs, LineDecorations, SemanticAttrs, WhitespaceAttrs};
+use super::whitespace::{Kind, Whitespace};
 use super::{Formatter, HtmlElement};
+use crate::annotations::Annotation;
+use crate::brackets::{BracketColors, RainbowBrackets};
+use crate::folding::CodeFolding;
+use crate::highlight::SourceRange;
 use crate::languages::Language;
+use crate::navigation::Symbols;
+use crate::semantic::SemanticColors;
 use crate::themes::Theme;
 use crate::vendor::tree_sitter_highlight::{Highlighter, HtmlRenderer};
 use derive_builder::Builder;
@@ -163,6 +171,63 @@ pub struct HtmlInline {
     include_highlights: bool,
     highlight_lines: Option<HighlightLines>,
     header: Option<HtmlElement>,
+    /// Render a non-selectable line number gutter styled with
+    /// [`Theme::line_number_style()`](crate::themes::Theme::line_number_style).
+    line_numbers: bool,
+    /// Number of the first line, used in the gutter and in `data-line`. Defaults to 1.
+    line_number_start: usize,
+    /// Only render the lines touched by this window of the source.
+    ///
+    /// Lines are rendered whole, so a [`SourceRange::Bytes`] window starting or ending in the
+    /// middle of a line is widened to the full lines it touches. The whole source is still
+    /// parsed so the window keeps the context of the full file, and `data-line` keeps the line
+    /// numbers of the full file.
+    range: Option<SourceRange>,
+    /// Labels and notes attached to ranges of the source, see [`annotations`](crate::annotations).
+    ///
+    /// The annotated text is wrapped in `<mark>` elements styled with the theme's
+    /// [`annotation_style()`](Theme::annotation_style).
+    annotations: Vec<Annotation>,
+    /// Link references to their definitions, see [`navigation`](crate::navigation).
+    ///
+    /// Definitions get an anchor `id`, references become `<a href="#def-...">` links to them,
+    /// and [`code_navigation_script()`](crate::formatter::html::code_navigation_script)
+    /// highlights all the uses of a symbol while one of them is hovered.
+    code_navigation: bool,
+    /// Prefix of the anchor ids of `code_navigation`, eg: `intro-` for `id="intro-def-2-9"`,
+    /// to keep them unique when several snippets are rendered on the same page.
+    #[builder(setter(into))]
+    symbol_id_prefix: String,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Give each local variable its own stable color, see [`semantic`](crate::semantic).
+    semantic_colors: bool,
+    /// Expand tabs to the next multiple of this width, also used by `show_whitespace` and
+    /// `indent_guides`. Defaults to 4 when one of them is set.
+    tab_width: Option<usize>,
+    /// Render tabs as `→` and trailing spaces as `·`, styled with the theme's
+    /// [`whitespace_style()`](Theme::whitespace_style).
+    show_whitespace: bool,
+    /// Draw a `│` guide at each indentation level, every `tab_width` columns.
+    indent_guides: bool,
+    /// Make function bodies, blocks and large literals collapsible, see
+    /// [`folding`](crate::folding).
+    code_folding: Option<CodeFolding>,
+    /// Flatten the nested spans of the highlights into runs, merging adjacent runs with the
+    /// same style and leaving unstyled text unwrapped.
+    ///
+    /// Shrinks the output without changing how it renders. Annotations, code navigation,
+    /// rainbow brackets, semantic colors, whitespace and inline code keep a span per
+    /// token.
+    merge_spans: bool,
+    /// Render a single `<code class="lumis-inline">` element for code inside prose, like
+    /// `Enum.map/2` in a paragraph, styled with the colors of the theme.
+    ///
+    /// There's no `<pre>` element nor line wrappers, and line breaks are collapsed into a
+    /// space. The `pre_class` is added to the classes of the `<code>` element, while the
+    /// options of the lines like `line_numbers`, `highlight_lines`, `range` and
+    /// `code_folding` don't apply.
+    inline_code: bool,
 }
 
 impl HtmlInlineBuilder {
@@ -190,6 +255,38 @@ impl HtmlInline {
             include_highlights,
             highlight_lines,
             header,
+            line_numbers: false,
+            line_number_start: 1,
+            range: None,
+            annotations: Vec::new(),
+            code_navigation: false,
+            symbol_id_prefix: String::new(),
+            rainbow_brackets: None,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+            code_folding: None,
+            merge_spans: false,
+            inline_code: false,
+        }
+    }
+
+    fn whitespace(&self) -> Option<Whitespace> {
+        Whitespace::new(self.tab_width, self.show_whitespace, self.indent_guides)
+    }
+
+    fn get_line_number_style(&self) -> String {
+        let theme_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .map(|style| style.css(self.italic, " "))
+            .filter(|css| !css.is_empty());
+
+        match theme_style {
+            Some(css) => format!("{} {}", crate::formatter::html::LINE_NUMBER_STYLE, css),
+            None => crate::formatter::html::LINE_NUMBER_STYLE.to_string(),
         }
     }
 
@@ -239,6 +336,20 @@ impl Default for HtmlInline {
             include_highlights: false,
             highlight_lines: None,
             header: None,
+            line_numbers: false,
+            line_number_start: 1,
+            range: None,
+            annotations: Vec::new(),
+            code_navigation: false,
+            symbol_id_prefix: String::new(),
+            rainbow_brackets: None,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+            code_folding: None,
+            merge_spans: false,
+            inline_code: false,
         }
     }
 }
@@ -251,6 +362,146 @@ impl Formatter for HtmlInline {
             write!(buffer, "{}", header.open_tag)?;
         }
 
+        let symbols = self
+            .code_navigation
+            .then(|| Symbols::resolve(source, self.lang));
+
+        let plain = !self.inline_code
+            && self.annotations.is_empty()
+            && symbols.is_none()
+            && self.rainbow_brackets.is_none()
+            && !self.semantic_colors
+            && self.whitespace().is_none();
+
+        let lines: Vec<String> = if plain && self.merge_spans {
+            crate::formatter::html::merged_lines(source, self.lang, |scopes| {
+                crate::formatter::html::merged_inline_attrs(
+                    scopes,
+                    self.theme.as_ref(),
+                    self.italic,
+                    self.include_highlights,
+                )
+            })?
+        } else if plain {
+            let mut highlighter = Highlighter::new();
+            let events = highlighter
+                .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
+                    Some(Language::guess(Some(injected), "").config())
+                })
+                .map_err(io::Error::other)?;
+
+            let mut renderer = HtmlRenderer::new();
+
+            renderer
+                .render(
+                    events,
+                    source.as_bytes(),
+                    &move |highlight, language, output| {
+                        let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
+                        let lang = Language::guess(Some(language), "");
+                        let attrs = crate::formatter::html::span_inline_attrs(
+                            scope,
+                            Some(lang),
+                            self.theme.as_ref(),
+                            self.italic,
+                            self.include_highlights,
+                        );
+                        output.extend(attrs.as_bytes());
+                    },
+                )
+                .map_err(io::Error::other)?;
+
+            renderer.lines().map(str::to_string).collect()
+        } else {
+            let annotation_style = self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.annotation_style());
+            let (mark_css, marker_css) =
+                crate::formatter::html::annotation_declarations(annotation_style.as_ref());
+            let brackets = self
+                .rainbow_brackets
+                .as_ref()
+                .map(|rainbow| BracketColors::new(source, self.lang, rainbow, self.theme.as_ref()));
+            let bracket_attrs = |scope: &str, lang: Language, depth: usize| {
+                crate::formatter::html::recolored_inline_attrs(
+                    scope,
+                    lang,
+                    self.theme.as_ref(),
+                    brackets.as_ref().and_then(|colors| colors.color(depth)),
+                    self.italic,
+                    self.include_highlights,
+                )
+            };
+            let whitespace_css = self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.whitespace_style())
+                .map(|style| style.css(self.italic, " "))
+                .unwrap_or_default();
+            let whitespace_attrs = |_kind: Kind| format!("style=\"{}\"", whitespace_css);
+            let identifiers = self
+                .semantic_colors
+                .then(|| SemanticColors::new(source, self.lang, self.theme.as_ref()));
+            let semantic_attrs = |scope: &str, lang: Language, color: usize| {
+                crate::formatter::html::recolored_inline_attrs(
+                    scope,
+                    lang,
+                    self.theme.as_ref(),
+                    identifiers.as_ref().map(|colors| colors.color(color)),
+                    self.italic,
+                    self.include_highlights,
+                )
+            };
+
+            crate::formatter::html::decorated_lines(
+                source,
+                self.lang,
+                &LineDecorations {
+                    annotations: &self.annotations,
+                    mark_style: Some(&mark_css.join(" ")),
+                    marker_style: Some(&marker_css.join(" ")),
+                    symbols: symbols.as_ref(),
+                    symbol_id_prefix: &self.symbol_id_prefix,
+                    reference_style: Some(crate::formatter::html::SYMBOL_REFERENCE_CSS),
+                    brackets: brackets
+                        .as_ref()
+                        .map(|colors| (colors, &bracket_attrs as BracketAttrs)),
+                    semantic: identifiers
+                        .as_ref()
+                        .map(|colors| (colors, &semantic_attrs as SemanticAttrs)),
+                    whitespace: self
+                        .whitespace()
+                        .map(|whitespace| (whitespace, &whitespace_attrs as WhitespaceAttrs)),
+                    collapse_lines: self.inline_code,
+                },
+                |scope, lang| {
+                    crate::formatter::html::span_inline_attrs(
+                        scope,
+                        Some(lang),
+                        self.theme.as_ref(),
+                        self.italic,
+                        self.include_highlights,
+                    )
+                },
+            )?
+        };
+
+        if self.inline_code {
+            crate::formatter::html::write_inline_code(
+                &mut buffer,
+                &self.lang,
+                self.pre_class.as_deref(),
+                self.theme.as_ref(),
+                &lines,
+            )?;
+            if let Some(ref header) = self.header {
+                write!(buffer, "{}", header.close_tag)?;
+            }
+            output.write_all(&buffer)?;
+            return Ok(());
+        }
+
         crate::formatter::html::open_pre_tag(
             &mut buffer,
             self.pre_class.as_deref(),
@@ -258,44 +509,52 @@ impl Formatter for HtmlInline {
         )?;
         crate::formatter::html::open_code_tag(&mut buffer, &self.lang)?;
 
-        let mut highlighter = Highlighter::new();
-        let events = highlighter
-            .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
-                Some(Language::guess(Some(injected), "").config())
-            })
-            .map_err(io::Error::other)?;
-
-        let mut renderer = HtmlRenderer::new();
-
-        renderer
-            .render(
-                events,
-                source.as_bytes(),
-                &move |highlight, language, output| {
-                    let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
-                    let lang = Language::guess(Some(language), "");
-                    let attrs = crate::formatter::html::span_inline_attrs(
-                        scope,
-                        Some(lang),
-                        self.theme.as_ref(),
-                        self.italic,
-                        self.include_highlights,
-                    );
-                    output.extend(attrs.as_bytes());
-                },
+        let visible_lines = self.range.as_ref().map(|range| range.line_range(source));
+        let last_line = visible_lines
+            .as_ref()
+            .map_or(usize::MAX, |lines| *lines.end())
+            .min(lines.len());
+        let number_width =
+            crate::formatter::html::line_number_width(self.line_number_start, last_line);
+        let folds = self.code_folding.as_ref().map(|folding| {
+            crate::formatter::html::LineFolds::new(
+                source,
+                self.lang,
+                folding,
+                visible_lines.as_ref(),
+                self.line_number_start,
             )
-            .map_err(io::Error::other)?;
+        });
+        let line_number_style = self.get_line_number_style();
 
-        for (i, line) in renderer.lines().enumerate() {
+        for (i, line) in lines.iter().enumerate() {
             let line_number = i + 1;
-            let line_with_braces = crate::formatter::html::escape_braces(line);
+            if visible_lines
+                .as_ref()
+                .is_some_and(|lines| !lines.contains(&line_number))
+            {
+                continue;
+            }
+            let mut line_with_braces = crate::formatter::html::escape_braces(line);
+            if self.line_numbers {
+                let gutter = crate::formatter::html::line_number(
+                    self.line_number_start + i,
+                    number_width,
+                    Some(&line_number_style),
+                );
+                line_with_braces.insert_str(0, &gutter);
+            }
             let (class_suffix, style) = self.get_line_attrs(line_number);
             let wrapped = crate::formatter::html::wrap_line(
-                line_number,
+                self.line_number_start + i,
                 &line_with_braces,
                 class_suffix.as_deref(),
                 style.as_deref(),
             );
+            let wrapped = match &folds {
+                Some(folds) => folds.wrap(line_number, wrapped),
+                None => wrapped,
+            };
             write!(&mut buffer, "{}", wrapped)?;
         }
 
@@ -592,4 +851,154 @@ mod tests {
 </div></code></pre></section>"#;
         assert_str_eq!(result, expected);
     }
+
+    #[test]
+    fn test_line_numbers() {
+        let theme = themes::get("dracula").unwrap();
+        let formatter = HtmlInlineBuilder::new()
+            .theme(Some(theme))
+            .line_numbers(true)
+            .line_number_start(9)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("a\nb\n", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let gutter = r#"<span class="line-number" style="user-select: none; -webkit-user-select: none; color: #6272a4;" aria-hidden="true">"#;
+        assert!(result.contains(&format!(
+            r#"<div class="line" data-line="9">{} 9 </span>a"#,
+            gutter
+        )));
+        assert!(result.contains(&format!(
+            r#"<div class="line" data-line="10">{}10 </span>b"#,
+            gutter
+        )));
+    }
+
+    #[test]
+    fn test_range() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Rust)
+            .range(Some(SourceRange::Lines(2..=3)))
+            .include_highlights(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter
+            .format("/* a\nb */\nlet x = 1;\nlet y = 2;\n", &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(!result.contains(r#"data-line="1""#));
+        assert!(result.contains(
+            r#"<div class="line" data-line="2"><span data-highlight="comment">b */</span>"#
+        ));
+        assert!(result.contains(r#"data-line="3""#));
+        assert!(!result.contains(r#"data-line="4""#));
+    }
+
+    #[test]
+    fn test_annotations() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .annotations(vec![
+                Annotation::new(8..10, "borrow here").with_note("ends with the block")
+            ])
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("let r = &x;", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        // Both tokens share the mark, followed by the footnote marker
+        assert!(result.contains(concat!(
+            r#"<mark class="annotation" style="color: inherit; background-color: #3c505e; text-decoration: underline #8be9fd;" data-annotation="1" title="borrow here"#,
+            "\n",
+            r#"ends with the block"><span style="color: #ff79c6;">&amp;</span><span style="color: #f8f8f2;">x</span></mark>"#,
+            r#"<sup class="annotation-marker" style="user-select: none; -webkit-user-select: none; color: #8be9fd;" data-annotation="1">1</sup>"#
+        )));
+    }
+
+    #[test]
+    fn test_code_navigation() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .code_navigation(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter
+            .format("fn add() {}\nfn main() { add(); }", &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.contains(r#"<span class="symbol-definition" id="def-1-4" data-symbol="def-1-4"><span style="color: #50fa7b;">add</span></span>"#));
+        assert!(result.contains(r##"<a class="symbol-reference" style="color: inherit; text-decoration: none;" href="#def-1-4" data-symbol="def-1-4"><span style="color: #50fa7b;">add</span></a>"##));
+        assert!(result.contains(r#"id="def-2-4""#));
+        assert!(!result.contains("<style>"));
+    }
+
+    #[test]
+    fn test_symbol_id_prefix() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Rust)
+            .code_navigation(true)
+            .symbol_id_prefix("intro-")
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("let x = 1;\nx;", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.contains(r#"id="intro-def-1-5" data-symbol="intro-def-1-5""#));
+        assert!(result.contains(r##"href="#intro-def-1-5" data-symbol="intro-def-1-5""##));
+    }
+
+    #[test]
+    fn test_merge_spans() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Elixir)
+            .theme(themes::get("dracula").ok())
+            .merge_spans(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("@lang :rust\nf(x)", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis" style="color: #f8f8f2; background-color: #282a36;"><code class="language-elixir" translate="no" tabindex="0"><div class="line" data-line="1"><span style="color: #bd93f9;">@lang :rust</span>
+</div><div class="line" data-line="2"><span style="color: #50fa7b;">f</span><span style="color: #f8f8f2;">(x)</span>
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_inline_code() {
+        let formatter = HtmlInlineBuilder::new()
+            .lang(Language::Elixir)
+            .theme(Some(themes::get("dracula").unwrap()))
+            .inline_code(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter
+            .format("Enum.map(list,\n  fn x -> x end)\n", &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert_str_eq!(
+            result,
+            r#"<code class="lumis-inline language-elixir" translate="no" style="color: #f8f8f2; background-color: #282a36;"><span style="color: #ffb86c;">Enum</span><span style="color: #ff79c6;">.</span><span style="color: #50fa7b;">map</span><span style="color: #f8f8f2;">(</span><span style="color: #f8f8f2;">list</span><span style="color: #f8f8f2;">,</span> <span style="color: #ff79c6;">fn</span> <span style="color: #f8f8f2;">x</span> <span style="color: #ff79c6;">-&gt;</span> <span style="color: #f8f8f2;">x</span> <span style="color: #ff79c6;">end</span><span style="color: #f8f8f2;">)</span></code>"#
+        );
+    }
 }
@@ -15,8 +15,17 @@
 //!
 //! See the [formatter](crate::formatter) module for more information and examples.
 
+use super::compact::CompactClasses;
+use super::html::{BracketAttrs, LineDecorations, SemanticAttrs, WhitespaceAttrs};
+use super::whitespace::{Kind, Whitespace};
 use super::{Formatter, HtmlElement};
+use crate::annotations::Annotation;
+use crate::brackets::{BracketColors, RainbowBrackets};
+use crate::folding::CodeFolding;
+use crate::highlight::{SourceRange, Style};
 use crate::languages::Language;
+use crate::navigation::Symbols;
+use crate::semantic::SemanticColors;
 use crate::vendor::tree_sitter_highlight::{Highlighter, HtmlRenderer};
 use derive_builder::Builder;
 use std::{
@@ -134,6 +143,64 @@ pub struct HtmlLinked {
     pre_class: Option<String>,
     highlight_lines: Option<HighlightLines>,
     header: Option<HtmlElement>,
+    /// Render a line number gutter, styled and made non-selectable by the `.line-number` CSS rule.
+    line_numbers: bool,
+    /// Number of the first line, used in the gutter and in `data-line`. Defaults to 1.
+    line_number_start: usize,
+    /// Only render the lines touched by this window of the source.
+    ///
+    /// Lines are rendered whole, so a [`SourceRange::Bytes`] window starting or ending in the
+    /// middle of a line is widened to the full lines it touches. The whole source is still
+    /// parsed so the window keeps the context of the full file, and `data-line` keeps the line
+    /// numbers of the full file.
+    range: Option<SourceRange>,
+    /// Labels and notes attached to ranges of the source, see [`annotations`](crate::annotations).
+    ///
+    /// The annotated text is wrapped in `<mark class="annotation">` elements, styled by the
+    /// `mark.annotation` and `.annotation-marker` CSS rules.
+    annotations: Vec<Annotation>,
+    /// Link references to their definitions, see [`navigation`](crate::navigation).
+    ///
+    /// Definitions get an anchor `id`, references become `<a href="#def-...">` links to them,
+    /// and [`code_navigation_script()`](crate::formatter::html::code_navigation_script)
+    /// highlights all the uses of a symbol while one of them is hovered.
+    code_navigation: bool,
+    /// Prefix of the anchor ids of `code_navigation`, eg: `intro-` for `id="intro-def-2-9"`,
+    /// to keep them unique when several snippets are rendered on the same page.
+    #[builder(setter(into))]
+    symbol_id_prefix: String,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Give each local variable its own stable color, see [`semantic`](crate::semantic).
+    semantic_colors: bool,
+    /// Expand tabs to the next multiple of this width, also used by `show_whitespace` and
+    /// `indent_guides`. Defaults to 4 when one of them is set.
+    tab_width: Option<usize>,
+    /// Render tabs as `→` and trailing spaces as `·`, styled with the theme's
+    /// [`whitespace_style()`](Theme::whitespace_style).
+    show_whitespace: bool,
+    /// Draw a `│` guide at each indentation level, every `tab_width` columns.
+    indent_guides: bool,
+    /// Make function bodies, blocks and large literals collapsible, see
+    /// [`folding`](crate::folding).
+    code_folding: Option<CodeFolding>,
+    /// Style tokens with short class names resolved from a theme instead of the scope
+    /// classes, see [`compact`](crate::formatter::compact).
+    ///
+    /// The stylesheet of the classes used is returned by [`CompactClasses::css()`].
+    compact_classes: Option<CompactClasses>,
+    /// Prefix of the classes of the scopes, eg: `hl-` for `class="hl-keyword"`, to match a
+    /// stylesheet generated with the same [`class_prefix`](crate::css::StylesheetBuilder::class_prefix).
+    class_prefix: Option<String>,
+    /// Render a single `<code class="lumis-inline">` element for code inside prose, like
+    /// `Enum.map/2` in a paragraph.
+    ///
+    /// There's no `<pre>` element nor line wrappers, and line breaks are collapsed into a
+    /// space. The `pre_class` is added to the classes of the `<code>` element, while the
+    /// options of the lines like `line_numbers`, `highlight_lines`, `range` and
+    /// `code_folding` don't apply. The theme CSS files only style `pre.lumis`, so style it
+    /// with a [`Stylesheet`](crate::css::Stylesheet) generated for `code.lumis-inline`.
+    inline_code: bool,
 }
 
 impl HtmlLinkedBuilder {
@@ -154,8 +221,48 @@ impl HtmlLinked {
             pre_class,
             highlight_lines,
             header,
+            line_numbers: false,
+            line_number_start: 1,
+            range: None,
+            annotations: Vec::new(),
+            code_navigation: false,
+            symbol_id_prefix: String::new(),
+            rainbow_brackets: None,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+            code_folding: None,
+            compact_classes: None,
+            class_prefix: None,
+            inline_code: false,
         }
     }
+
+    fn whitespace(&self) -> Option<Whitespace> {
+        Whitespace::new(self.tab_width, self.show_whitespace, self.indent_guides)
+    }
+
+    /// Attributes of the `<span>` of `scope`, with its scope class or its compact class.
+    fn scope_attrs(&self, scope: &str, lang: Language) -> String {
+        match &self.compact_classes {
+            Some(compact) => compact_attrs(compact.scope_class(scope, lang)),
+            None => match &self.class_prefix {
+                Some(prefix) => format!(
+                    "class=\"{}{}\"",
+                    prefix,
+                    crate::formatter::html::scope_to_class(scope)
+                ),
+                None => crate::formatter::html::span_linked_attrs(scope),
+            },
+        }
+    }
+}
+
+fn compact_attrs(class: Option<String>) -> String {
+    class
+        .map(|class| format!("class=\"{}\"", class))
+        .unwrap_or_default()
 }
 
 impl Default for HtmlLinked {
@@ -165,6 +272,21 @@ impl Default for HtmlLinked {
             pre_class: None,
             highlight_lines: None,
             header: None,
+            line_numbers: false,
+            line_number_start: 1,
+            range: None,
+            annotations: Vec::new(),
+            code_navigation: false,
+            symbol_id_prefix: String::new(),
+            rainbow_brackets: None,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+            code_folding: None,
+            compact_classes: None,
+            class_prefix: None,
+            inline_code: false,
         }
     }
 }
@@ -177,32 +299,191 @@ impl Formatter for HtmlLinked {
             write!(buffer, "{}", header.open_tag)?;
         }
 
+        let symbols = self
+            .code_navigation
+            .then(|| Symbols::resolve(source, self.lang));
+
+        let lines: Vec<String> = if !self.inline_code
+            && self.annotations.is_empty()
+            && symbols.is_none()
+            && self.rainbow_brackets.is_none()
+            && !self.semantic_colors
+            && self.whitespace().is_none()
+        {
+            let mut highlighter = Highlighter::new();
+            let events = highlighter
+                .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
+                    Some(Language::guess(Some(injected), "").config())
+                })
+                .map_err(io::Error::other)?;
+
+            let mut renderer = HtmlRenderer::new();
+
+            renderer
+                .render(
+                    events,
+                    source.as_bytes(),
+                    &move |highlight, language, output| {
+                        let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
+                        let attrs = self.scope_attrs(scope, Language::guess(Some(language), ""));
+                        output.extend(attrs.as_bytes());
+                    },
+                )
+                .map_err(io::Error::other)?;
+
+            renderer.lines().map(str::to_string).collect()
+        } else {
+            let compact_theme = self.compact_classes.as_ref().map(CompactClasses::theme);
+            let brackets = self
+                .rainbow_brackets
+                .as_ref()
+                .map(|rainbow| BracketColors::new(source, self.lang, rainbow, compact_theme));
+            let custom_palette = self
+                .rainbow_brackets
+                .as_ref()
+                .is_some_and(|rainbow| !rainbow.palette.is_empty());
+            // Compact class of the theme style of `scope` with `color` as foreground.
+            let recolored_attrs =
+                |compact: &CompactClasses, scope: &str, lang: Language, color: Option<&str>| {
+                    let style = compact
+                        .theme()
+                        .get_style(&format!("{}.{}", scope, lang.id_name()))
+                        .cloned()
+                        .unwrap_or_default();
+                    compact_attrs(compact.class(&Style {
+                        fg: color.map(str::to_string).or(style.fg),
+                        ..style
+                    }))
+                };
+            let bracket_attrs = |scope: &str, lang: Language, depth: usize| {
+                let colors = brackets.as_ref();
+                match &self.compact_classes {
+                    Some(compact) => recolored_attrs(
+                        compact,
+                        scope,
+                        lang,
+                        colors.and_then(|colors| colors.color(depth)),
+                    ),
+                    None => colors
+                        .map(|colors| {
+                            crate::formatter::html::bracket_linked_attrs(
+                                scope,
+                                self.class_prefix.as_deref().unwrap_or_default(),
+                                colors,
+                                depth,
+                                custom_palette,
+                            )
+                        })
+                        .unwrap_or_default(),
+                }
+            };
+            let whitespace_attrs = |kind: Kind| match (&self.compact_classes, kind) {
+                (Some(compact), _) => compact_attrs(
+                    compact
+                        .theme()
+                        .whitespace_style()
+                        .and_then(|style| compact.class(&style)),
+                ),
+                (None, Kind::Guide) => "class=\"indent-guide\"".to_string(),
+                (None, _) => "class=\"visible-whitespace\"".to_string(),
+            };
+            let identifiers = self
+                .semantic_colors
+                .then(|| SemanticColors::new(source, self.lang, compact_theme));
+            let semantic_attrs =
+                |scope: &str, lang: Language, color: usize| match &self.compact_classes {
+                    Some(compact) => recolored_attrs(
+                        compact,
+                        scope,
+                        lang,
+                        identifiers.as_ref().map(|colors| colors.color(color)),
+                    ),
+                    None => crate::formatter::html::semantic_linked_attrs(
+                        scope,
+                        self.class_prefix.as_deref().unwrap_or_default(),
+                        color,
+                    ),
+                };
+            // Compact stylesheets don't include the annotation rules, so marks are styled inline.
+            let (mark_css, marker_css) = crate::formatter::html::annotation_declarations(
+                compact_theme
+                    .and_then(|theme| theme.annotation_style())
+                    .as_ref(),
+            );
+            let (mark_style, marker_style) = match compact_theme {
+                Some(_) => (Some(mark_css.join(" ")), Some(marker_css.join(" "))),
+                None => (None, None),
+            };
+
+            crate::formatter::html::decorated_lines(
+                source,
+                self.lang,
+                &LineDecorations {
+                    annotations: &self.annotations,
+                    mark_style: mark_style.as_deref(),
+                    marker_style: marker_style.as_deref(),
+                    symbols: symbols.as_ref(),
+                    symbol_id_prefix: &self.symbol_id_prefix,
+                    reference_style: compact_theme
+                        .map(|_| crate::formatter::html::SYMBOL_REFERENCE_CSS),
+                    brackets: brackets
+                        .as_ref()
+                        .map(|colors| (colors, &bracket_attrs as BracketAttrs)),
+                    semantic: identifiers
+                        .as_ref()
+                        .map(|colors| (colors, &semantic_attrs as SemanticAttrs)),
+                    whitespace: self
+                        .whitespace()
+                        .map(|whitespace| (whitespace, &whitespace_attrs as WhitespaceAttrs)),
+                    collapse_lines: self.inline_code,
+                },
+                |scope, lang| self.scope_attrs(scope, lang),
+            )?
+        };
+
+        if self.inline_code {
+            crate::formatter::html::write_inline_code(
+                &mut buffer,
+                &self.lang,
+                self.pre_class.as_deref(),
+                None,
+                &lines,
+            )?;
+            if let Some(ref header) = self.header {
+                write!(buffer, "{}", header.close_tag)?;
+            }
+            output.write_all(&buffer)?;
+            return Ok(());
+        }
+
         crate::formatter::html::open_pre_tag(&mut buffer, self.pre_class.as_deref(), None)?;
         crate::formatter::html::open_code_tag(&mut buffer, &self.lang)?;
 
-        let mut highlighter = Highlighter::new();
-        let events = highlighter
-            .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
-                Some(Language::guess(Some(injected), "").config())
-            })
-            .map_err(io::Error::other)?;
-
-        let mut renderer = HtmlRenderer::new();
-
-        renderer
-            .render(
-                events,
-                source.as_bytes(),
-                &move |highlight, _language, output| {
-                    let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
-                    let attrs = crate::formatter::html::span_linked_attrs(scope);
-                    output.extend(attrs.as_bytes());
-                },
+        let visible_lines = self.range.as_ref().map(|range| range.line_range(source));
+        let last_line = visible_lines
+            .as_ref()
+            .map_or(usize::MAX, |lines| *lines.end())
+            .min(lines.len());
+        let number_width =
+            crate::formatter::html::line_number_width(self.line_number_start, last_line);
+        let folds = self.code_folding.as_ref().map(|folding| {
+            crate::formatter::html::LineFolds::new(
+                source,
+                self.lang,
+                folding,
+                visible_lines.as_ref(),
+                self.line_number_start,
             )
-            .map_err(io::Error::other)?;
+        });
 
-        for (i, line) in renderer.lines().enumerate() {
+        for (i, line) in lines.iter().enumerate() {
             let line_number = i + 1;
+            if visible_lines
+                .as_ref()
+                .is_some_and(|lines| !lines.contains(&line_number))
+            {
+                continue;
+            }
             let class_suffix = self.highlight_lines.as_ref().and_then(|hl| {
                 if hl.lines.iter().any(|range| range.contains(&line_number)) {
                     Some(format!(" {}", hl.class))
@@ -211,13 +492,25 @@ impl Formatter for HtmlLinked {
                 }
             });
 
-            let line_with_braces = crate::formatter::html::escape_braces(line);
+            let mut line_with_braces = crate::formatter::html::escape_braces(line);
+            if self.line_numbers {
+                let gutter = crate::formatter::html::line_number(
+                    self.line_number_start + i,
+                    number_width,
+                    None,
+                );
+                line_with_braces.insert_str(0, &gutter);
+            }
             let wrapped = crate::formatter::html::wrap_line(
-                line_number,
+                self.line_number_start + i,
                 &line_with_braces,
                 class_suffix.as_deref(),
                 None,
             );
+            let wrapped = match &folds {
+                Some(folds) => folds.wrap(line_number, wrapped),
+                None => wrapped,
+            };
             write!(&mut buffer, "{}", wrapped)?;
         }
 
@@ -235,6 +528,7 @@ impl Formatter for HtmlLinked {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::folding::FoldMode;
     use crate::formatter::HtmlLinkedBuilder;
 
     #[cfg(test)]
@@ -389,4 +683,205 @@ mod tests {
 </div></code></pre></section>"#;
         assert_str_eq!(result, expected);
     }
+
+    #[test]
+    fn test_line_numbers() {
+        let formatter = HtmlLinkedBuilder::new()
+            .line_numbers(true)
+            .line_number_start(99)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("a\nb", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis"><code class="language-plaintext" translate="no" tabindex="0"><div class="line" data-line="99"><span class="line-number" aria-hidden="true"> 99 </span>a
+</div><div class="line" data-line="100"><span class="line-number" aria-hidden="true">100 </span>b
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let formatter = HtmlLinkedBuilder::new()
+            .annotations(vec![
+                Annotation::new(2..5, "spans lines"),
+                Annotation::new(0..0, "start"),
+            ])
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("ab\ncd", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis"><code class="language-plaintext" translate="no" tabindex="0"><div class="line" data-line="1"><sup class="annotation-marker" data-annotation="2">2</sup>ab
+</div><div class="line" data-line="2"><mark class="annotation" data-annotation="1" title="spans lines">cd</mark><sup class="annotation-marker" data-annotation="1">1</sup>
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_code_navigation() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::Rust)
+            .code_navigation(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("let x = 1;\nx;", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r##"<pre class="lumis"><code class="language-rust" translate="no" tabindex="0"><div class="line" data-line="1"><span class="keyword">let</span> <span class="symbol-definition" id="def-1-5" data-symbol="def-1-5"><span class="variable">x</span></span> <span class="operator">=</span> <span class="number">1</span><span class="punctuation-delimiter">;</span>
+</div><div class="line" data-line="2"><a class="symbol-reference" href="#def-1-5" data-symbol="def-1-5"><span class="variable">x</span></a><span class="punctuation-delimiter">;</span>
+</div></code></pre>"##;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_rainbow_brackets() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::Rust)
+            .rainbow_brackets(Some(RainbowBrackets::default()))
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("f(g())", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis"><code class="language-rust" translate="no" tabindex="0"><div class="line" data-line="1"><span class="function-call">f</span><span class="punctuation-bracket rainbow-bracket-1">(</span><span class="function-call">g</span><span class="punctuation-bracket rainbow-bracket-2">(</span><span class="punctuation-bracket rainbow-bracket-2">)</span><span class="punctuation-bracket rainbow-bracket-1">)</span>
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_semantic_colors() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::Rust)
+            .semantic_colors(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("let a = 1;\na;", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis"><code class="language-rust" translate="no" tabindex="0"><div class="line" data-line="1"><span class="keyword">let</span> <span class="variable semantic-5">a</span> <span class="operator">=</span> <span class="number">1</span><span class="punctuation-delimiter">;</span>
+</div><div class="line" data-line="2"><span class="variable semantic-5">a</span><span class="punctuation-delimiter">;</span>
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::PlainText)
+            .show_whitespace(true)
+            .indent_guides(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("a\t \n    b", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let expected = r#"<pre class="lumis"><code class="language-plaintext" translate="no" tabindex="0"><div class="line" data-line="1">a<span class="visible-whitespace">→  ·</span>
+</div><div class="line" data-line="2"><span class="indent-guide">│</span>   b
+</div></code></pre>"#;
+        assert_str_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_code_folding() {
+        let code = "[\n  1,\n  2\n]";
+        let format = |mode| {
+            let formatter = HtmlLinkedBuilder::new()
+                .lang(Language::JSON)
+                .line_number_start(10)
+                .code_folding(Some(CodeFolding { min_lines: 3, mode }))
+                .build()
+                .unwrap();
+            let mut buffer = Vec::new();
+            formatter.format(code, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        let expected = r#"<pre class="lumis"><code class="language-json" translate="no" tabindex="0"><details class="fold" open><summary><span class="line" data-line="10"><span class="punctuation-bracket">[</span>
+</span></summary><div class="line" data-line="11">  <span class="number">1</span><span class="punctuation-delimiter">,</span>
+</div><div class="line" data-line="12">  <span class="number">2</span>
+</div></details><div class="line" data-line="13"><span class="punctuation-bracket">]</span>
+</div></code></pre>"#;
+        assert_str_eq!(format(FoldMode::Details), expected);
+
+        assert!(format(FoldMode::DataAttributes)
+            .contains(r#"<div class="line" data-fold-end="12" data-line="10">"#));
+    }
+
+    #[test]
+    fn test_compact_classes() {
+        let classes = CompactClasses::new(crate::themes::get("dracula").unwrap());
+        let format = |code: &str| {
+            let formatter = HtmlLinkedBuilder::new()
+                .lang(Language::Elixir)
+                .compact_classes(Some(classes.clone()))
+                .build()
+                .unwrap();
+            let mut buffer = Vec::new();
+            formatter.format(code, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        let expected = r#"<pre class="lumis"><code class="language-elixir" translate="no" tabindex="0"><div class="line" data-line="1"><span class="a1">def</span> <span class="a2">f</span>
+</div></code></pre>"#;
+        assert_str_eq!(format("def f"), expected);
+        assert!(format(":ok").contains(r#"<span class="a3">:ok</span>"#));
+        assert!(
+            format("def g").contains(r#"<span class="a1">def</span> <span class="a2">g</span>"#)
+        );
+
+        assert_str_eq!(
+            classes.css(),
+            "pre.lumis {\n  color: #f8f8f2;\n  background-color: #282a36;\n}\npre.lumis .a1 {\n  color: #ff79c6;\n}\npre.lumis .a2 {\n  color: #f8f8f2;\n}\npre.lumis .a3 {\n  color: #bd93f9;\n}\npre.lumis .line-number {\n  user-select: none;\n  -webkit-user-select: none;\n  color: #6272a4;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_class_prefix() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::JSON)
+            .class_prefix(Some("hl-".to_string()))
+            .rainbow_brackets(Some(RainbowBrackets::default()))
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("[1]", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.contains(
+            r#"<span class="hl-punctuation-bracket rainbow-bracket-1">[</span><span class="hl-number">1</span>"#
+        ));
+    }
+
+    #[test]
+    fn test_inline_code() {
+        let formatter = HtmlLinkedBuilder::new()
+            .lang(Language::Elixir)
+            .pre_class(Some("docs".to_string()))
+            .inline_code(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("Enum.map/2", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(
+            result,
+            r#"<code class="lumis-inline language-elixir docs" translate="no"><span class="module">Enum</span><span class="operator">.</span><span class="variable">map</span><span class="operator">/</span><span class="number">2</span></code>"#
+        );
+    }
 }
@@ -9,15 +9,13 @@
 //!
 //! ```rust
 //! use lumis::{HtmlMultiThemesBuilder, languages::Language, themes, formatter::Formatter};
-//! use std::collections::HashMap;
-//!
-//! let mut theme_map = HashMap::new();
-//! theme_map.insert("light".to_string(), themes::get("github_light").unwrap());
-//! theme_map.insert("dark".to_string(), themes::get("github_dark").unwrap());
 //!
 //! let formatter = HtmlMultiThemesBuilder::new()
 //!     .lang(Language::Rust)
-//!     .themes(theme_map)
+//!     .themes([
+//!         ("light", themes::get("github_light").unwrap()),
+//!         ("dark", themes::get("github_dark").unwrap()),
+//!     ])
 //!     .default_theme("light")
 //!     .build()
 //!     .unwrap();
@@ -26,6 +24,9 @@
 //! formatter.format("fn main() {}", &mut output).unwrap();
 //! ```
 //!
+//! Themes are rendered in the order they're given, so the output is the same on every run.
+//! A map of themes is also accepted, sorted by name. See [`ThemeList`].
+//!
 //! # How It Works
 //!
 //! Generated HTML includes inline colors and font styles for the default theme, plus CSS
@@ -81,21 +82,47 @@
 //! }
 //! ```
 //!
-//! **Option 4: CSS `light-dark()` function (modern browsers)**
+//! **Option 4: More than two themes**
+//!
+//! [`HtmlMultiThemes::css()`] generates the rules of Option 2 for every theme but the default
+//! one, so any number of themes can be switched with a `data-theme` attribute on an ancestor,
+//! eg: `<html data-theme="sepia">`. Give a theme its own selector with
+//! [`ThemeEntry::with_selector()`] to switch it with a class or another attribute instead:
+//!
+//! ```rust
+//! use lumis::formatter::ThemeEntry;
+//! use lumis::{HtmlMultiThemesBuilder, languages::Language, themes};
+//!
+//! let formatter = HtmlMultiThemesBuilder::new()
+//!     .lang(Language::Rust)
+//!     .themes(vec![
+//!         ThemeEntry::new("light", themes::get("github_light").unwrap()),
+//!         ThemeEntry::new("dark", themes::get("github_dark").unwrap()),
+//!         ThemeEntry::new("high-contrast", themes::get("github_dark_high_contrast").unwrap())
+//!             .with_selector(".high-contrast"),
+//!         ThemeEntry::new("sepia", themes::get("gruvbox_light").unwrap()),
+//!     ])
+//!     .default_theme("light")
+//!     .build()
+//!     .unwrap();
+//!
+//! // Rules for `[data-theme="dark"]`, `.high-contrast` and `[data-theme="sepia"]`
+//! let css = formatter.css();
+//! ```
+//!
+//! **Option 5: CSS `light-dark()` function (modern browsers)**
 //!
 //! For browsers that support the [CSS `light-dark()` function](https://developer.mozilla.org/en-US/docs/Web/CSS/Reference/Values/color_value/light-dark), you can use a more elegant approach:
 //!
 //! ```rust
 //! use lumis::{HtmlMultiThemesBuilder, languages::Language, themes, formatter::Formatter};
-//! use std::collections::HashMap;
-//!
-//! let mut theme_map = HashMap::new();
-//! theme_map.insert("light".to_string(), themes::get("github_light").unwrap());
-//! theme_map.insert("dark".to_string(), themes::get("github_dark").unwrap());
 //!
 //! let formatter = HtmlMultiThemesBuilder::new()
 //!     .lang(Language::Rust)
-//!     .themes(theme_map)
+//!     .themes([
+//!         ("light", themes::get("github_light").unwrap()),
+//!         ("dark", themes::get("github_dark").unwrap()),
+//!     ])
 //!     .default_theme("light-dark()")
 //!     .build()
 //!     .unwrap();
@@ -120,13 +147,21 @@
 //! and [html_multi_themesi.html](https://github.com/leandrocp/lumis/blob/main/examples/html_multi_themesi.html) for a demo.
 //!
 
+use super::html::{BracketAttrs, LineDecorations, SemanticAttrs, WhitespaceAttrs};
+use super::whitespace::{Kind, Whitespace};
 use super::{Formatter, HtmlElement};
+use crate::annotations::Annotation;
+use crate::brackets::{BracketColors, RainbowBrackets};
+use crate::folding::CodeFolding;
 use crate::formatter::html_inline::HighlightLines;
+use crate::highlight::{SourceRange, Style};
 use crate::languages::Language;
+use crate::navigation::Symbols;
+use crate::semantic::SemanticColors;
 use crate::themes::Theme;
 use crate::vendor::tree_sitter_highlight::{Highlighter, HtmlRenderer};
 use derive_builder::Builder;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self, Write};
 use std::str::FromStr;
 
@@ -159,6 +194,205 @@ impl FromStr for DefaultTheme {
     }
 }
 
+/// A theme of [`HtmlMultiThemes`] with its name and the selector switching to it.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::formatter::ThemeEntry;
+/// use lumis::themes;
+///
+/// let entry = ThemeEntry::new("sepia", themes::get("gruvbox_light").unwrap())
+///     .with_selector(".reader-mode");
+/// assert_eq!(entry.active_selector(), ".reader-mode");
+///
+/// let entry = ThemeEntry::new("dark", themes::get("github_dark").unwrap());
+/// assert_eq!(entry.active_selector(), r#"[data-theme="dark"]"#);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThemeEntry {
+    /// Name of the theme, used in its CSS variables like `--lumis-dark` and as a class of the
+    /// `<pre>` element.
+    pub name: String,
+    pub theme: Theme,
+    /// Selector of an ancestor of the `<pre>` element switching to this theme in the
+    /// stylesheet returned by [`HtmlMultiThemes::css()`]. Defaults to `[data-theme="NAME"]`.
+    pub selector: Option<String>,
+}
+
+impl ThemeEntry {
+    pub fn new(name: impl Into<String>, theme: Theme) -> Self {
+        Self {
+            name: name.into(),
+            theme,
+            selector: None,
+        }
+    }
+
+    /// Switch to this theme with `selector`, eg: `.high-contrast` or `[data-mode="print"]`.
+    pub fn with_selector(mut self, selector: impl Into<String>) -> Self {
+        self.selector = Some(selector.into());
+        self
+    }
+
+    /// Selector switching to this theme, either the custom one or `[data-theme="NAME"]`.
+    pub fn active_selector(&self) -> String {
+        self.selector
+            .clone()
+            .unwrap_or_else(|| format!("[data-theme=\"{}\"]", self.name))
+    }
+}
+
+/// Themes of [`HtmlMultiThemes`], rendered in the order they're given.
+///
+/// The order of the themes sets the order of the `<pre>` classes and of the CSS variables, so
+/// the output is the same on every run. Lists can be created from a `Vec` or an array of
+/// [`ThemeEntry`] or of `(name, theme)` pairs, keeping their order, or from a map, sorted by
+/// name. Adding a theme with the name of a listed one replaces it in place.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::formatter::{ThemeEntry, ThemeList};
+/// use lumis::themes;
+/// use std::collections::HashMap;
+///
+/// let list = ThemeList::from([
+///     ("light", themes::get("github_light").unwrap()),
+///     ("dark", themes::get("github_dark").unwrap()),
+/// ]);
+/// let names: Vec<&str> = list.iter().map(|entry| entry.name.as_str()).collect();
+/// assert_eq!(names, vec!["light", "dark"]);
+///
+/// let mut map = HashMap::new();
+/// map.insert("light".to_string(), themes::get("github_light").unwrap());
+/// map.insert("dark".to_string(), themes::get("github_dark").unwrap());
+/// let names: Vec<String> = ThemeList::from(map).iter().map(|entry| entry.name.clone()).collect();
+/// assert_eq!(names, vec!["dark", "light"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ThemeList(Vec<ThemeEntry>);
+
+impl ThemeList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `entry` at the end of the list, or in place of the theme with the same name.
+    pub fn push(&mut self, entry: ThemeEntry) {
+        match self.0.iter_mut().find(|listed| listed.name == entry.name) {
+            Some(listed) => *listed = entry,
+            None => self.0.push(entry),
+        }
+    }
+
+    /// Theme named `name`.
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.0
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| &entry.theme)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ThemeEntry> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a ThemeList {
+    type Item = &'a ThemeEntry;
+    type IntoIter = std::slice::Iter<'a, ThemeEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<ThemeEntry> for ThemeList {
+    fn from_iter<I: IntoIterator<Item = ThemeEntry>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for entry in iter {
+            list.push(entry);
+        }
+        list
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, Theme)> for ThemeList {
+    fn from_iter<I: IntoIterator<Item = (S, Theme)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(name, theme)| ThemeEntry::new(name, theme))
+            .collect()
+    }
+}
+
+impl From<Vec<ThemeEntry>> for ThemeList {
+    fn from(entries: Vec<ThemeEntry>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<const N: usize> From<[ThemeEntry; N]> for ThemeList {
+    fn from(entries: [ThemeEntry; N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<S: Into<String>> From<Vec<(S, Theme)>> for ThemeList {
+    fn from(themes: Vec<(S, Theme)>) -> Self {
+        themes.into_iter().collect()
+    }
+}
+
+impl<S: Into<String>, const N: usize> From<[(S, Theme); N]> for ThemeList {
+    fn from(themes: [(S, Theme); N]) -> Self {
+        themes.into_iter().collect()
+    }
+}
+
+impl From<BTreeMap<String, Theme>> for ThemeList {
+    fn from(themes: BTreeMap<String, Theme>) -> Self {
+        themes.into_iter().collect()
+    }
+}
+
+/// Themes of a map, sorted by name since the map has no order.
+impl From<HashMap<String, Theme>> for ThemeList {
+    fn from(themes: HashMap<String, Theme>) -> Self {
+        themes.into_iter().collect::<BTreeMap<_, _>>().into()
+    }
+}
+
+impl From<&BTreeMap<String, Theme>> for ThemeList {
+    fn from(themes: &BTreeMap<String, Theme>) -> Self {
+        themes.clone().into()
+    }
+}
+
+impl From<&HashMap<String, Theme>> for ThemeList {
+    fn from(themes: &HashMap<String, Theme>) -> Self {
+        themes.clone().into()
+    }
+}
+
+impl From<&ThemeList> for ThemeList {
+    fn from(themes: &ThemeList) -> Self {
+        themes.clone()
+    }
+}
+
 /// HTML formatter with multiple theme support.
 ///
 /// This formatter generates HTML with inline CSS styles for a default theme and CSS variables
@@ -168,15 +402,13 @@ impl FromStr for DefaultTheme {
 ///
 /// ```rust
 /// use lumis::{HtmlMultiThemesBuilder, languages::Language, themes, formatter::Formatter};
-/// use std::collections::HashMap;
-///
-/// let mut themes = HashMap::new();
-/// themes.insert("light".to_string(), themes::get("github_light").unwrap());
-/// themes.insert("dark".to_string(), themes::get("github_dark").unwrap());
 ///
 /// let formatter = HtmlMultiThemesBuilder::new()
 ///     .lang(Language::Rust)
-///     .themes(themes)
+///     .themes([
+///         ("light", themes::get("github_light").unwrap()),
+///         ("dark", themes::get("github_dark").unwrap()),
+///     ])
 ///     .default_theme("light")
 ///     .build()
 ///     .unwrap();
@@ -185,7 +417,10 @@ impl FromStr for DefaultTheme {
 #[builder(default, build_fn(skip))]
 pub struct HtmlMultiThemes {
     lang: Language,
-    themes: HashMap<String, Theme>,
+    /// Themes in the order of their `<pre>` classes and CSS variables, from a [`ThemeList`],
+    /// a `Vec` or array of `(name, theme)` pairs, or a map sorted by name.
+    #[builder(setter(into))]
+    themes: ThemeList,
     #[builder(setter(custom))]
     default_theme: Option<DefaultTheme>,
     #[builder(setter(into))]
@@ -195,6 +430,56 @@ pub struct HtmlMultiThemes {
     include_highlights: bool,
     highlight_lines: Option<HighlightLines>,
     header: Option<HtmlElement>,
+    /// Render a non-selectable line number gutter styled with the
+    /// [`Theme::line_number_style()`](crate::themes::Theme::line_number_style) of each theme.
+    line_numbers: bool,
+    /// Number of the first line, used in the gutter and in `data-line`. Defaults to 1.
+    line_number_start: usize,
+    /// Only render the lines touched by this window of the source.
+    ///
+    /// Lines are rendered whole, so a [`SourceRange::Bytes`] window starting or ending in the
+    /// middle of a line is widened to the full lines it touches. The whole source is still
+    /// parsed so the window keeps the context of the full file, and `data-line` keeps the line
+    /// numbers of the full file.
+    range: Option<SourceRange>,
+    /// Labels and notes attached to ranges of the source, see [`annotations`](crate::annotations).
+    ///
+    /// The annotated text is wrapped in `<mark>` elements styled with the
+    /// [`annotation_style()`](Theme::annotation_style) of the default theme.
+    annotations: Vec<Annotation>,
+    /// Link references to their definitions, see [`navigation`](crate::navigation).
+    ///
+    /// Definitions get an anchor `id`, references become `<a href="#def-...">` links to them,
+    /// and [`code_navigation_script()`](crate::formatter::html::code_navigation_script)
+    /// highlights all the uses of a symbol while one of them is hovered.
+    code_navigation: bool,
+    /// Prefix of the anchor ids of `code_navigation`, eg: `intro-` for `id="intro-def-2-9"`,
+    /// to keep them unique when several snippets are rendered on the same page.
+    #[builder(setter(into))]
+    symbol_id_prefix: String,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Give each local variable its own stable color, see [`semantic`](crate::semantic).
+    ///
+    /// Each theme uses its own [`semantic_palette()`](Theme::semantic_palette).
+    semantic_colors: bool,
+    /// Expand tabs to the next multiple of this width, also used by `show_whitespace` and
+    /// `indent_guides`. Defaults to 4 when one of them is set.
+    tab_width: Option<usize>,
+    /// Render tabs as `→` and trailing spaces as `·`, styled with each theme's
+    /// [`whitespace_style()`](Theme::whitespace_style).
+    show_whitespace: bool,
+    /// Draw a `│` guide at each indentation level, every `tab_width` columns.
+    indent_guides: bool,
+    /// Make function bodies, blocks and large literals collapsible, see
+    /// [`folding`](crate::folding).
+    code_folding: Option<CodeFolding>,
+    /// Flatten the nested spans of the highlights into runs, merging adjacent runs with the
+    /// same styles in all themes and leaving unstyled text unwrapped.
+    ///
+    /// Shrinks the output without changing how it renders. Annotations, code navigation,
+    /// rainbow brackets, semantic colors and whitespace keep a span per token.
+    merge_spans: bool,
 }
 
 /// Builder for creating [`HtmlMultiThemes`] formatters.
@@ -205,15 +490,13 @@ pub struct HtmlMultiThemes {
 ///
 /// ```rust
 /// use lumis::{HtmlMultiThemesBuilder, languages::Language, themes};
-/// use std::collections::HashMap;
-///
-/// let mut themes = HashMap::new();
-/// themes.insert("light".to_string(), themes::get("github_light").unwrap());
-/// themes.insert("dark".to_string(), themes::get("github_dark").unwrap());
 ///
 /// let formatter = HtmlMultiThemesBuilder::new()
 ///     .lang(Language::Rust)
-///     .themes(themes)
+///     .themes([
+///         ("light", themes::get("github_light").unwrap()),
+///         ("dark", themes::get("github_dark").unwrap()),
+///     ])
 ///     .default_theme("light")
 ///     .css_variable_prefix("--my-app")
 ///     .build()
@@ -243,6 +526,19 @@ impl HtmlMultiThemesBuilder {
             include_highlights: self.include_highlights.take().unwrap_or(false),
             highlight_lines: self.highlight_lines.take().flatten(),
             header: self.header.take().flatten(),
+            line_numbers: self.line_numbers.take().unwrap_or(false),
+            line_number_start: self.line_number_start.take().unwrap_or(1),
+            range: self.range.take().flatten(),
+            annotations: self.annotations.take().unwrap_or_default(),
+            code_navigation: self.code_navigation.take().unwrap_or(false),
+            symbol_id_prefix: self.symbol_id_prefix.take().unwrap_or_default(),
+            rainbow_brackets: self.rainbow_brackets.take().flatten(),
+            semantic_colors: self.semantic_colors.take().unwrap_or(false),
+            tab_width: self.tab_width.take().flatten(),
+            show_whitespace: self.show_whitespace.take().unwrap_or(false),
+            indent_guides: self.indent_guides.take().unwrap_or(false),
+            code_folding: self.code_folding.take().flatten(),
+            merge_spans: self.merge_spans.take().unwrap_or(false),
         };
 
         if result.themes.is_empty() {
@@ -250,24 +546,17 @@ impl HtmlMultiThemesBuilder {
         }
 
         match &result.default_theme {
-            Some(DefaultTheme::Theme(name)) => {
-                if !result.themes.contains_key(name) {
-                    return Err(format!("Default theme '{}' not found in themes map", name));
-                }
+            Some(DefaultTheme::Theme(name)) if !result.themes.contains(name) => {
+                Err(format!("Default theme '{}' not found in themes map", name))
             }
-            Some(DefaultTheme::LightDark) => {
-                if !result.themes.contains_key("light") || !result.themes.contains_key("dark") {
-                    return Err(
-                        "LightDark mode requires themes named 'light' and 'dark'".to_string()
-                    );
-                }
-            }
-            None => {
-                // No default theme - all themes are CSS variables only
+            Some(DefaultTheme::LightDark)
+                if !result.themes.contains("light") || !result.themes.contains("dark") =>
+            {
+                Err("LightDark mode requires themes named 'light' and 'dark'".to_string())
             }
+            // Without a default theme, all themes are CSS variables only
+            _ => Ok(result),
         }
-
-        Ok(result)
     }
 }
 
@@ -313,7 +602,7 @@ impl Default for HtmlMultiThemes {
     fn default() -> Self {
         Self {
             lang: Language::PlainText,
-            themes: HashMap::new(),
+            themes: ThemeList::new(),
             default_theme: None,
             css_variable_prefix: "--lumis".to_string(),
             pre_class: None,
@@ -321,11 +610,85 @@ impl Default for HtmlMultiThemes {
             include_highlights: false,
             highlight_lines: None,
             header: None,
+            line_numbers: false,
+            line_number_start: 1,
+            range: None,
+            annotations: Vec::new(),
+            code_navigation: false,
+            symbol_id_prefix: String::new(),
+            rainbow_brackets: None,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+            code_folding: None,
+            merge_spans: false,
         }
     }
 }
 
 impl HtmlMultiThemes {
+    /// Stylesheet switching the code to each theme while the
+    /// [`active_selector()`](ThemeEntry::active_selector) of the theme matches an ancestor.
+    ///
+    /// The rules follow the order of the themes. The default theme has no rule since its
+    /// colors are inline, and without a default theme the first theme styles the code until
+    /// another one is selected. With the `light-dark()` default the browser switches the
+    /// colors itself, so the stylesheet is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::formatter::ThemeEntry;
+    /// use lumis::{HtmlMultiThemesBuilder, themes};
+    ///
+    /// let formatter = HtmlMultiThemesBuilder::new()
+    ///     .themes(vec![
+    ///         ThemeEntry::new("light", themes::get("github_light").unwrap()),
+    ///         ThemeEntry::new("dark", themes::get("github_dark").unwrap()),
+    ///         ThemeEntry::new("sepia", themes::get("gruvbox_light").unwrap())
+    ///             .with_selector(".sepia"),
+    ///     ])
+    ///     .default_theme("light")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let css = formatter.css();
+    /// assert!(css.contains("[data-theme=\"dark\"] .lumis span {\n  color: var(--lumis-dark) !important;"));
+    /// assert!(css.contains(".sepia .lumis,\n.sepia .lumis span {"));
+    /// ```
+    pub fn css(&self) -> String {
+        let default_name = match &self.default_theme {
+            Some(DefaultTheme::Theme(name)) => Some(name.as_str()),
+            Some(DefaultTheme::LightDark) => return String::new(),
+            None => None,
+        };
+
+        let mut rules = Vec::new();
+        for (i, entry) in self.themes.iter().enumerate() {
+            if default_name == Some(entry.name.as_str()) {
+                continue;
+            }
+
+            let selectors = if default_name.is_none() && i == 0 {
+                ".lumis,\n.lumis span".to_string()
+            } else {
+                let selector = entry.active_selector();
+                format!("{selector} .lumis,\n{selector} .lumis span")
+            };
+            let variable = format!(
+                "{}-{}",
+                self.css_variable_prefix,
+                crate::formatter::html::sanitize_theme_name(&entry.name)
+            );
+            rules.push(format!(
+                "{selectors} {{\n  color: var({variable}) !important;\n  background-color: var({variable}-bg) !important;\n  font-style: var({variable}-font-style) !important;\n  font-weight: var({variable}-font-weight) !important;\n  text-decoration: var({variable}-text-decoration) !important;\n}}\n"
+            ));
+        }
+
+        rules.join("\n")
+    }
+
     fn generate_pre_classes(&self) -> String {
         let mut classes = vec!["lumis".to_string(), "lumis-themes".to_string()];
 
@@ -333,8 +696,8 @@ impl HtmlMultiThemes {
             classes.push(pre_class.clone());
         }
 
-        for theme_name in self.themes.keys() {
-            classes.push(theme_name.clone());
+        for entry in &self.themes {
+            classes.push(entry.name.clone());
         }
 
         classes.join(" ")
@@ -354,7 +717,12 @@ impl HtmlMultiThemes {
                     }
                 }
 
-                for (theme_name, theme) in &self.themes {
+                for ThemeEntry {
+                    name: theme_name,
+                    theme,
+                    ..
+                } in &self.themes
+                {
                     if theme_name != default_name {
                         let sanitized = crate::formatter::html::sanitize_theme_name(theme_name);
                         if let Some(fg) = theme.fg() {
@@ -389,7 +757,12 @@ impl HtmlMultiThemes {
                 }
             }
             None => {
-                for (theme_name, theme) in &self.themes {
+                for ThemeEntry {
+                    name: theme_name,
+                    theme,
+                    ..
+                } in &self.themes
+                {
                     let sanitized = crate::formatter::html::sanitize_theme_name(theme_name);
                     if let Some(fg) = theme.fg() {
                         styles.push(format!(
@@ -421,6 +794,169 @@ impl HtmlMultiThemes {
         write!(output, ">")
     }
 
+    fn get_annotation_styles(&self) -> (String, String) {
+        let style = match &self.default_theme {
+            Some(DefaultTheme::Theme(name)) => self
+                .themes
+                .get(name)
+                .and_then(|theme| theme.annotation_style()),
+            Some(DefaultTheme::LightDark) => {
+                let light = self.themes.get("light").and_then(Theme::annotation_style);
+                let dark = self.themes.get("dark").and_then(Theme::annotation_style);
+                light.zip(dark).map(|(light, dark)| {
+                    let light_dark = |light: Option<String>, dark: Option<String>| {
+                        Some(format!("light-dark({}, {})", light?, dark?))
+                    };
+                    Style {
+                        fg: light_dark(light.fg, dark.fg),
+                        bg: light_dark(light.bg, dark.bg),
+                        ..Default::default()
+                    }
+                })
+            }
+            None => None,
+        };
+        let (mark, marker) = crate::formatter::html::annotation_declarations(style.as_ref());
+
+        (mark.join(" "), marker.join(" "))
+    }
+
+    fn whitespace(&self) -> Option<Whitespace> {
+        Whitespace::new(self.tab_width, self.show_whitespace, self.indent_guides)
+    }
+
+    /// Lines of flat runs of text, each one styled with the style it inherits in every theme.
+    fn merged_lines(&self, source: &str) -> io::Result<Vec<String>> {
+        let default_theme = match &self.default_theme {
+            Some(DefaultTheme::Theme(name)) => Some(name.as_str()),
+            Some(DefaultTheme::LightDark) => Some("light-dark()"),
+            None => None,
+        };
+
+        crate::formatter::html::merged_lines(source, self.lang, |scopes| {
+            let Some((scope, _)) = scopes.last() else {
+                return String::new();
+            };
+            let themes: ThemeList = self
+                .themes
+                .iter()
+                .map(|ThemeEntry { name, theme, .. }| {
+                    let style = crate::formatter::html::inherited_style(theme, scopes);
+                    let highlights = if style == Style::default() {
+                        BTreeMap::new()
+                    } else {
+                        BTreeMap::from([(scope.to_string(), style)])
+                    };
+                    let merged_theme = Theme {
+                        highlights,
+                        ..Theme::default()
+                    };
+                    (name.clone(), merged_theme)
+                })
+                .collect();
+
+            crate::formatter::html::multi_themes_attrs(
+                scope,
+                None,
+                &themes,
+                default_theme,
+                &self.css_variable_prefix,
+                self.italic,
+                self.include_highlights,
+            )
+        })
+    }
+
+    fn get_line_number_style(&self) -> String {
+        let mut styles = vec![crate::formatter::html::LINE_NUMBER_STYLE.to_string()];
+
+        match &self.default_theme {
+            Some(DefaultTheme::Theme(default_name)) => {
+                if let Some(style) = self
+                    .themes
+                    .get(default_name)
+                    .and_then(|theme| theme.line_number_style())
+                {
+                    if let Some(fg) = &style.fg {
+                        styles.push(format!("color:{};", fg));
+                    }
+                    if let Some(bg) = &style.bg {
+                        styles.push(format!("background-color:{};", bg));
+                    }
+                }
+
+                for ThemeEntry {
+                    name: theme_name,
+                    theme,
+                    ..
+                } in &self.themes
+                {
+                    if theme_name == default_name {
+                        continue;
+                    }
+                    let Some(style) = theme.line_number_style() else {
+                        continue;
+                    };
+                    let sanitized = crate::formatter::html::sanitize_theme_name(theme_name);
+                    if let Some(fg) = &style.fg {
+                        styles.push(format!(
+                            "{}-{}:{};",
+                            self.css_variable_prefix, sanitized, fg
+                        ));
+                    }
+                    if let Some(bg) = &style.bg {
+                        styles.push(format!(
+                            "{}-{}-bg:{};",
+                            self.css_variable_prefix, sanitized, bg
+                        ));
+                    }
+                }
+            }
+            Some(DefaultTheme::LightDark) => {
+                let light = self.themes.get("light").and_then(|t| t.line_number_style());
+                let dark = self.themes.get("dark").and_then(|t| t.line_number_style());
+                if let (Some(light), Some(dark)) = (light, dark) {
+                    if let (Some(light_fg), Some(dark_fg)) = (&light.fg, &dark.fg) {
+                        styles.push(format!("color: light-dark({}, {});", light_fg, dark_fg));
+                    }
+                    if let (Some(light_bg), Some(dark_bg)) = (&light.bg, &dark.bg) {
+                        styles.push(format!(
+                            "background-color: light-dark({}, {});",
+                            light_bg, dark_bg
+                        ));
+                    }
+                }
+            }
+            None => {
+                for ThemeEntry {
+                    name: theme_name,
+                    theme,
+                    ..
+                } in &self.themes
+                {
+                    let Some(style) = theme.line_number_style() else {
+                        continue;
+                    };
+                    let sanitized = crate::formatter::html::sanitize_theme_name(theme_name);
+                    if let Some(fg) = &style.fg {
+                        styles.push(format!(
+                            "{}-{}: {};",
+                            self.css_variable_prefix, sanitized, fg
+                        ));
+                    }
+                    if let Some(bg) = &style.bg {
+                        styles.push(format!(
+                            "{}-{}-bg: {};",
+                            self.css_variable_prefix, sanitized, bg
+                        ));
+                    }
+                }
+            }
+        }
+
+        styles.join(" ")
+    }
+
     fn get_line_attrs(&self, line_number: usize) -> (Option<String>, Option<String>) {
         let is_highlighted = self
             .highlight_lines
@@ -471,54 +1007,242 @@ impl Formatter for HtmlMultiThemes {
             write!(buffer, "{}", header.open_tag)?;
         }
 
+        let symbols = self
+            .code_navigation
+            .then(|| Symbols::resolve(source, self.lang));
+
         self.open_pre_tag(&mut buffer)?;
         crate::formatter::html::open_code_tag(&mut buffer, &self.lang)?;
 
-        let mut highlighter = Highlighter::new();
-        let events = highlighter
-            .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
-                Some(Language::guess(Some(injected), "").config())
-            })
-            .map_err(io::Error::other)?;
-
-        let mut renderer = HtmlRenderer::new();
-
-        renderer
-            .render(
-                events,
-                source.as_bytes(),
-                &move |highlight, language, output| {
-                    let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
-                    let lang = Language::guess(Some(language), "");
-                    let default_theme_str = match &self.default_theme {
-                        Some(DefaultTheme::Theme(name)) => Some(name.as_str()),
-                        Some(DefaultTheme::LightDark) => Some("light-dark()"),
-                        None => None,
+        let plain = self.annotations.is_empty()
+            && symbols.is_none()
+            && self.rainbow_brackets.is_none()
+            && !self.semantic_colors
+            && self.whitespace().is_none();
+
+        let lines: Vec<String> = if plain && self.merge_spans {
+            self.merged_lines(source)?
+        } else if plain {
+            let mut highlighter = Highlighter::new();
+            let events = highlighter
+                .highlight(self.lang.config(), source.as_bytes(), None, |injected| {
+                    Some(Language::guess(Some(injected), "").config())
+                })
+                .map_err(io::Error::other)?;
+
+            let mut renderer = HtmlRenderer::new();
+
+            renderer
+                .render(
+                    events,
+                    source.as_bytes(),
+                    &move |highlight, language, output| {
+                        let scope = crate::constants::HIGHLIGHT_NAMES[highlight.0];
+                        let lang = Language::guess(Some(language), "");
+                        let default_theme_str = match &self.default_theme {
+                            Some(DefaultTheme::Theme(name)) => Some(name.as_str()),
+                            Some(DefaultTheme::LightDark) => Some("light-dark()"),
+                            None => None,
+                        };
+                        let attrs = crate::formatter::html::multi_themes_attrs(
+                            scope,
+                            Some(lang),
+                            &self.themes,
+                            default_theme_str,
+                            &self.css_variable_prefix,
+                            self.italic,
+                            self.include_highlights,
+                        );
+                        output.extend(attrs.as_bytes());
+                    },
+                )
+                .map_err(io::Error::other)?;
+
+            renderer.lines().map(str::to_string).collect()
+        } else {
+            let (mark_style, marker_style) = self.get_annotation_styles();
+            let default_theme = match &self.default_theme {
+                Some(DefaultTheme::Theme(name)) => Some(name.as_str()),
+                Some(DefaultTheme::LightDark) => Some("light-dark()"),
+                None => None,
+            };
+            let brackets = self
+                .rainbow_brackets
+                .as_ref()
+                .map(|rainbow| BracketColors::new(source, self.lang, rainbow, None));
+            let palettes: HashMap<&str, Vec<String>> = self
+                .rainbow_brackets
+                .as_ref()
+                .map(|rainbow| {
+                    self.themes
+                        .iter()
+                        .map(|ThemeEntry { name, theme, .. }| {
+                            (name.as_str(), rainbow.palette(Some(theme)))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            // Each theme styles the whitespace with its own whitespace style.
+            let whitespace_themes: ThemeList = self
+                .themes
+                .iter()
+                .map(|ThemeEntry { name, theme, .. }| {
+                    let highlights = theme
+                        .whitespace_style()
+                        .map(|style| BTreeMap::from([("whitespace".to_string(), style)]))
+                        .unwrap_or_default();
+                    let whitespace_theme = Theme {
+                        highlights,
+                        ..Theme::default()
                     };
-                    let attrs = crate::formatter::html::span_multi_themes_attrs(
+                    (name.clone(), whitespace_theme)
+                })
+                .collect();
+            let whitespace_attrs = |_kind: Kind| {
+                crate::formatter::html::multi_themes_attrs(
+                    "whitespace",
+                    None,
+                    &whitespace_themes,
+                    default_theme,
+                    &self.css_variable_prefix,
+                    self.italic,
+                    self.include_highlights,
+                )
+            };
+            let identifiers = self
+                .semantic_colors
+                .then(|| SemanticColors::new(source, self.lang, None));
+            let semantic_palettes: HashMap<&str, Vec<String>> = self
+                .themes
+                .iter()
+                .filter(|_| self.semantic_colors)
+                .map(|ThemeEntry { name, theme, .. }| (name.as_str(), theme.semantic_palette()))
+                .collect();
+            // Each theme styles brackets and local variables with the colors of its own palettes.
+            let recolored_attrs =
+                |scope: &str, lang: Language, color: &dyn Fn(&str) -> Option<String>| {
+                    let specialized_scope = format!("{}.{}", scope, lang.id_name());
+                    let themes: ThemeList = self
+                        .themes
+                        .iter()
+                        .map(|ThemeEntry { name, theme, .. }| {
+                            let mut style = theme
+                                .get_style(&specialized_scope)
+                                .cloned()
+                                .unwrap_or_default();
+                            style.fg = color(name).or(style.fg);
+                            let recolored_theme = Theme {
+                                highlights: BTreeMap::from([(specialized_scope.clone(), style)]),
+                                ..Theme::default()
+                            };
+                            (name.clone(), recolored_theme)
+                        })
+                        .collect();
+
+                    crate::formatter::html::multi_themes_attrs(
+                        scope,
+                        Some(lang),
+                        &themes,
+                        default_theme,
+                        &self.css_variable_prefix,
+                        self.italic,
+                        self.include_highlights,
+                    )
+                };
+            let bracket_attrs = |scope: &str, lang: Language, depth: usize| {
+                recolored_attrs(scope, lang, &|name| {
+                    let palette = palettes.get(name)?;
+                    palette.get(depth % palette.len().max(1)).cloned()
+                })
+            };
+            let semantic_attrs = |scope: &str, lang: Language, color: usize| {
+                recolored_attrs(scope, lang, &|name| {
+                    let palette = semantic_palettes.get(name)?;
+                    palette.get(color % palette.len().max(1)).cloned()
+                })
+            };
+
+            crate::formatter::html::decorated_lines(
+                source,
+                self.lang,
+                &LineDecorations {
+                    annotations: &self.annotations,
+                    mark_style: Some(&mark_style),
+                    marker_style: Some(&marker_style),
+                    symbols: symbols.as_ref(),
+                    symbol_id_prefix: &self.symbol_id_prefix,
+                    reference_style: Some(crate::formatter::html::SYMBOL_REFERENCE_CSS),
+                    brackets: brackets
+                        .as_ref()
+                        .map(|colors| (colors, &bracket_attrs as BracketAttrs)),
+                    semantic: identifiers
+                        .as_ref()
+                        .map(|colors| (colors, &semantic_attrs as SemanticAttrs)),
+                    whitespace: self
+                        .whitespace()
+                        .map(|whitespace| (whitespace, &whitespace_attrs as WhitespaceAttrs)),
+                    collapse_lines: false,
+                },
+                |scope, lang| {
+                    crate::formatter::html::multi_themes_attrs(
                         scope,
                         Some(lang),
                         &self.themes,
-                        default_theme_str,
+                        default_theme,
                         &self.css_variable_prefix,
                         self.italic,
                         self.include_highlights,
-                    );
-                    output.extend(attrs.as_bytes());
+                    )
                 },
+            )?
+        };
+
+        let visible_lines = self.range.as_ref().map(|range| range.line_range(source));
+        let last_line = visible_lines
+            .as_ref()
+            .map_or(usize::MAX, |lines| *lines.end())
+            .min(lines.len());
+        let number_width =
+            crate::formatter::html::line_number_width(self.line_number_start, last_line);
+        let folds = self.code_folding.as_ref().map(|folding| {
+            crate::formatter::html::LineFolds::new(
+                source,
+                self.lang,
+                folding,
+                visible_lines.as_ref(),
+                self.line_number_start,
             )
-            .map_err(io::Error::other)?;
+        });
+        let line_number_style = self.get_line_number_style();
 
-        for (i, line) in renderer.lines().enumerate() {
+        for (i, line) in lines.iter().enumerate() {
             let line_number = i + 1;
-            let line_with_braces = crate::formatter::html::escape_braces(line);
+            if visible_lines
+                .as_ref()
+                .is_some_and(|lines| !lines.contains(&line_number))
+            {
+                continue;
+            }
+            let mut line_with_braces = crate::formatter::html::escape_braces(line);
+            if self.line_numbers {
+                let gutter = crate::formatter::html::line_number(
+                    self.line_number_start + i,
+                    number_width,
+                    Some(&line_number_style),
+                );
+                line_with_braces.insert_str(0, &gutter);
+            }
             let (class_suffix, style) = self.get_line_attrs(line_number);
             let wrapped = crate::formatter::html::wrap_line(
-                line_number,
+                self.line_number_start + i,
                 &line_with_braces,
                 class_suffix.as_deref(),
                 style.as_deref(),
             );
+            let wrapped = match &folds {
+                Some(folds) => folds.wrap(line_number, wrapped),
+                None => wrapped,
+            };
             write!(&mut buffer, "{}", wrapped)?;
         }
 
@@ -779,4 +1503,131 @@ mod tests {
 
         assert!(html.contains("font-style: light-dark("));
     }
+
+    #[test]
+    fn test_line_numbers() {
+        let mut themes = HashMap::new();
+        themes.insert(
+            "light".to_string(),
+            crate::themes::get("github_light").unwrap(),
+        );
+        themes.insert("dark".to_string(), crate::themes::get("dracula").unwrap());
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .themes(themes)
+            .default_theme("light")
+            .line_numbers(true)
+            .line_number_start(5)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("a", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.contains(r#"<div class="line" data-line="5"><span class="line-number" style="user-select: none; -webkit-user-select: none; color:#57606a; --lumis-dark:#6272a4;" aria-hidden="true">5 </span>a"#));
+    }
+
+    #[test]
+    fn test_annotations_light_dark() {
+        let mut themes = HashMap::new();
+        themes.insert(
+            "light".to_string(),
+            crate::themes::get("github_light").unwrap(),
+        );
+        themes.insert("dark".to_string(), crate::themes::get("dracula").unwrap());
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .themes(themes)
+            .default_theme("light-dark()")
+            .annotations(vec![Annotation::new(0..1, "here")])
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("a", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert!(result.contains(r#"<mark class="annotation" style="color: inherit; background-color: light-dark(#cee1f8, #3c505e); text-decoration: underline light-dark(#0969da, #8be9fd);" data-annotation="1" title="here">a</mark>"#));
+    }
+
+    #[test]
+    fn test_merge_spans() {
+        let mut themes = HashMap::new();
+        themes.insert(
+            "light".to_string(),
+            crate::themes::get("github_light").unwrap(),
+        );
+        themes.insert("dark".to_string(), crate::themes::get("dracula").unwrap());
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .lang(Language::Elixir)
+            .themes(themes)
+            .default_theme("light-dark()")
+            .merge_spans(true)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("@lang :rust", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(result.matches("<span").count(), 1);
+        assert!(result.contains(">@lang :rust</span>"));
+        assert!(result.contains("color: light-dark(#0550ae, #bd93f9);"));
+    }
+
+    #[test]
+    fn test_themes_keep_their_order() {
+        let themes = vec![
+            ("sepia", crate::themes::get("gruvbox_light").unwrap()),
+            ("light", crate::themes::get("github_light").unwrap()),
+            ("dark", crate::themes::get("github_dark").unwrap()),
+        ];
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .lang(Language::Rust)
+            .themes(themes)
+            .default_theme("light")
+            .build()
+            .unwrap();
+
+        let mut output = Vec::new();
+        formatter.format("fn main() {}", &mut output).unwrap();
+        let html = String::from_utf8(output).unwrap();
+
+        assert!(html.contains(r#"<pre class="lumis lumis-themes sepia light dark" style="color:#1f2328; background-color:#ffffff; --lumis-sepia:#3c3836; --lumis-sepia-bg:#fbf1c7; --lumis-dark:#e6edf3; --lumis-dark-bg:#0d1117;">"#));
+        assert!(html.contains(r#"--lumis-sepia:#9d0006; --lumis-sepia-font-style:normal; --lumis-sepia-font-weight:normal; --lumis-sepia-text-decoration:none; --lumis-dark:#ff7b72;"#));
+    }
+
+    #[test]
+    fn test_css_switches_each_theme() {
+        let themes = ThemeList::from([
+            ThemeEntry::new("light", crate::themes::get("github_light").unwrap()),
+            ThemeEntry::new("dark", crate::themes::get("github_dark").unwrap()),
+            ThemeEntry::new("sepia", crate::themes::get("gruvbox_light").unwrap())
+                .with_selector(".sepia"),
+        ]);
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .themes(themes.clone())
+            .css_variable_prefix("--hl")
+            .build()
+            .unwrap();
+        let css = formatter.css();
+
+        assert!(css.starts_with(".lumis,\n.lumis span {\n  color: var(--hl-light) !important;"));
+        assert!(css.contains("[data-theme=\"dark\"] .lumis,\n[data-theme=\"dark\"] .lumis span {\n  color: var(--hl-dark) !important;\n  background-color: var(--hl-dark-bg) !important;"));
+        assert!(css.contains(
+            ".sepia .lumis,\n.sepia .lumis span {\n  color: var(--hl-sepia) !important;"
+        ));
+
+        let formatter = HtmlMultiThemesBuilder::new()
+            .themes(themes)
+            .default_theme("sepia")
+            .build()
+            .unwrap();
+
+        assert!(!formatter.css().contains("sepia"));
+    }
 }
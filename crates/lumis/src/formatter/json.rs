@@ -0,0 +1,339 @@
+//! JSON formatter for structured token streams.
+//!
+//! This module provides the [`Json`] formatter that serializes every highlighted token
+//! instead of rendering it, so clients in any language can build their own rendering
+//! (React components, virtualized lists, editors) on top of lumis.
+//!
+//! # Output Format
+//!
+//! For the Rust code `fn main() {}` with the dracula theme, the formatter generates
+//! (pretty printed and truncated):
+//!
+//! ```json
+//! {
+//!   "language": "rust",
+//!   "theme": "dracula",
+//!   "tokens": [
+//!     {
+//!       "text": "fn",
+//!       "start": 0,
+//!       "end": 2,
+//!       "line": 1,
+//!       "column": 1,
+//!       "scope": "keyword.function",
+//!       "language": "rust",
+//!       "style": {
+//!         "fg": "#8be9fd",
+//!         "bg": null,
+//!         "bold": false,
+//!         "italic": false,
+//!         "text_decoration": { "underline": "none", "strikethrough": false }
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `start` and `end` are byte offsets into the source.
+//! - `line` and `column` are 1-based, with the column counted in characters.
+//! - `scope` is `null` for text outside any highlight.
+//! - `language` is the language of the token, which differs from the top level
+//!   language inside injections (e.g. JavaScript in HTML).
+//! - `style` is the style resolved from the theme, with empty colors without a theme.
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::Formatter;
+use crate::brackets::{rainbow, BracketColors, RainbowBrackets};
+use crate::highlight::{highlight_iter_with_language_in_range, SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::{TextDecoration, Theme};
+use derive_builder::Builder;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// JSON formatter for syntax highlighting.
+///
+/// Serializes the token stream with byte ranges, positions, scopes, injected languages
+/// and resolved styles. Use [`JsonBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Frontends that render tokens themselves
+/// - Consuming lumis output from non-Rust clients
+/// - Debugging scopes and styles resolved for a piece of code
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{JsonBuilder, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() {}";
+/// let theme = themes::get("dracula").unwrap();
+///
+/// let formatter = JsonBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(Some(theme))
+///     .pretty(true)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+///
+/// let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+/// assert_eq!(json["tokens"][0]["text"], "fn");
+/// assert_eq!(json["tokens"][0]["scope"], "keyword.function");
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Json {
+    lang: Language,
+    theme: Option<Theme>,
+    /// Pretty print the output with indentation.
+    pretty: bool,
+    /// Only emit the tokens inside this window of the source.
+    ///
+    /// The whole source is still parsed, and `start`, `end`, `line` and `column`
+    /// keep referring to the full source.
+    range: Option<SourceRange>,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+}
+
+impl JsonBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Json {
+    fn default() -> Self {
+        Self {
+            lang: Language::PlainText,
+            theme: None,
+            pretty: false,
+            range: None,
+            rainbow_brackets: None,
+        }
+    }
+}
+
+impl Json {
+    pub fn new(lang: Language, theme: Option<Theme>) -> Self {
+        Self {
+            lang,
+            theme,
+            pretty: false,
+            range: None,
+            rainbow_brackets: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    language: &'a str,
+    theme: Option<&'a str>,
+    tokens: Vec<Token<'a>>,
+}
+
+#[derive(Serialize)]
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    scope: Option<&'static str>,
+    language: String,
+    style: TokenStyle,
+}
+
+#[derive(Serialize)]
+struct TokenStyle {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    italic: bool,
+    text_decoration: TextDecoration,
+}
+
+impl From<&Style> for TokenStyle {
+    fn from(style: &Style) -> Self {
+        Self {
+            fg: style.fg.clone(),
+            bg: style.bg.clone(),
+            bold: style.bold,
+            italic: style.italic,
+            text_decoration: style.text_decoration,
+        }
+    }
+}
+
+impl Formatter for Json {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let language = self.lang.id_name();
+        let mut tokens = Vec::new();
+
+        let mut line = 1;
+        let mut line_start = 0;
+        let mut position = 0;
+
+        let window = self
+            .range
+            .as_ref()
+            .map_or(0..source.len(), |range| range.byte_range(source));
+
+        let brackets = self
+            .rainbow_brackets
+            .as_ref()
+            .map(|rainbow| BracketColors::new(source, self.lang, rainbow, self.theme.as_ref()));
+
+        highlight_iter_with_language_in_range(
+            source,
+            self.lang,
+            self.theme.clone(),
+            window,
+            rainbow(
+                source,
+                brackets.as_ref(),
+                |_text, range, scope, token_language, style| {
+                    for (offset, _) in source[position..range.start].match_indices('\n') {
+                        line += 1;
+                        line_start = position + offset + 1;
+                    }
+                    position = range.start;
+
+                    tokens.push(Token {
+                        text: &source[range.clone()],
+                        start: range.start,
+                        end: range.end,
+                        line,
+                        column: source[line_start..range.start].chars().count() + 1,
+                        scope: (!scope.is_empty()).then_some(scope),
+                        language: token_language.to_string(),
+                        style: style.into(),
+                    });
+
+                    Ok::<_, io::Error>(())
+                },
+            ),
+        )
+        .map_err(io::Error::other)?;
+
+        let document = Document {
+            language: &language,
+            theme: self.theme.as_ref().map(|theme| theme.name.as_str()),
+            tokens,
+        };
+
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut *output, &document)?;
+        } else {
+            serde_json::to_writer(&mut *output, &document)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+    use serde_json::Value;
+
+    fn render(formatter: &Json, code: &str) -> Value {
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+        serde_json::from_slice(&buffer).unwrap()
+    }
+
+    #[test]
+    fn test_tokens() {
+        let formatter = Json::new(Language::Rust, themes::get("dracula").ok());
+        let json = render(&formatter, "fn main() {}");
+
+        assert_eq!(json["language"], "rust");
+        assert_eq!(json["theme"], "dracula");
+
+        let token = &json["tokens"][0];
+        assert_eq!(token["text"], "fn");
+        assert_eq!(token["start"], 0);
+        assert_eq!(token["end"], 2);
+        assert_eq!(token["line"], 1);
+        assert_eq!(token["column"], 1);
+        assert_eq!(token["scope"], "keyword.function");
+        assert_eq!(token["language"], "rust");
+        assert_eq!(token["style"]["fg"], "#8be9fd");
+        assert_eq!(token["style"]["bg"], Value::Null);
+        assert_eq!(token["style"]["bold"], false);
+        assert_eq!(token["style"]["text_decoration"]["underline"], "none");
+
+        let space = &json["tokens"][1];
+        assert_eq!(space["text"], " ");
+        assert_eq!(space["scope"], Value::Null);
+    }
+
+    #[test]
+    fn test_line_and_column() {
+        let formatter = Json::new(Language::Rust, None);
+        let json = render(&formatter, "let a = 1;\n  let é = 2;");
+
+        let tokens = json["tokens"].as_array().unwrap();
+        let second_let = tokens
+            .iter()
+            .filter(|token| token["text"] == "let")
+            .nth(1)
+            .unwrap();
+        assert_eq!(second_let["line"], 2);
+        assert_eq!(second_let["column"], 3);
+
+        let two = tokens.iter().find(|token| token["text"] == "2").unwrap();
+        assert_eq!(two["line"], 2);
+        assert_eq!(two["column"], 11);
+        assert_eq!(two["start"], 22);
+    }
+
+    #[test]
+    fn test_injected_language() {
+        let formatter = Json::new(Language::HTML, None);
+        let json = render(&formatter, "<script>let x = 1;</script>");
+
+        let tokens = json["tokens"].as_array().unwrap();
+        let keyword = tokens.iter().find(|token| token["text"] == "let").unwrap();
+
+        assert_eq!(json["language"], "html");
+        assert_eq!(keyword["language"], "javascript");
+    }
+
+    #[test]
+    fn test_pretty() {
+        let formatter = JsonBuilder::new().pretty(true).build().unwrap();
+
+        let mut buffer = Vec::new();
+        formatter.format("x", &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert!(output.starts_with("{\n  \"language\": \"plaintext\""));
+    }
+
+    #[test]
+    fn test_range() {
+        let formatter = JsonBuilder::new()
+            .lang(Language::Rust)
+            .range(Some(SourceRange::Lines(2..=2)))
+            .build()
+            .unwrap();
+        let json = render(&formatter, "/* a\nb */\nlet x = 1;\n");
+
+        let tokens = json["tokens"].as_array().unwrap();
+        assert_eq!(tokens[0]["text"], "b */");
+        assert_eq!(tokens[0]["start"], 5);
+        assert_eq!(tokens[0]["line"], 2);
+        assert_eq!(tokens[0]["column"], 1);
+        assert_eq!(tokens[0]["scope"], "comment");
+        assert!(tokens.iter().all(|token| token["line"] == 2));
+    }
+}
@@ -0,0 +1,430 @@
+//! LaTeX formatter for papers and books.
+//!
+//! This module provides the [`Latex`] formatter that renders highlighted code as LaTeX
+//! markup using `\textcolor`, `\colorbox`, `\textbf` and `\textit`, so it can be included
+//! in documents without minted or Pygments. Special characters are escaped and whitespace
+//! is preserved with control spaces.
+//!
+//! Colors are referenced by name and defined once per document with [`preamble()`],
+//! which generates a `\definecolor` for every highlight in a [`Theme`]. Names are derived
+//! from the highlight scope, e.g. `keyword.function` becomes `LumisKeywordFunction`
+//! and its background `LumisKeywordFunctionBg`.
+//!
+//! # Example Output
+//!
+//! For the Rust code `fn main() {}` with the dracula theme, the formatter generates:
+//!
+//! ```latex
+//! {\ttfamily\color{LumisNormal}\noindent
+//! \textcolor{LumisKeywordFunction}{fn}\ \textcolor{LumisFunction}{main}\textcolor{LumisPunctuationBracket}{(}...
+//! }
+//! ```
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::Formatter;
+use crate::brackets::{rainbow, BracketColors, RainbowBrackets};
+use crate::highlight::{highlight_iter_with_language_in_range, SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::{Theme, UnderlineStyle};
+use derive_builder::Builder;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// LaTeX formatter for syntax highlighting.
+///
+/// Generates a block of LaTeX markup that references the colors defined by [`preamble()`].
+/// The output requires the `xcolor` package, which the preamble loads.
+/// Use [`LatexBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Technical books and papers typeset with LaTeX
+/// - Keeping print output consistent with HTML output from the same theme
+/// - Replacing minted and its Pygments dependency
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{LatexBuilder, formatter::latex, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() { println!(\"Hello\"); }";
+/// let theme = themes::get("dracula").unwrap();
+///
+/// let preamble = latex::preamble(&theme);
+/// assert!(preamble.contains("\\definecolor{LumisKeywordFunction}{HTML}{8BE9FD}"));
+///
+/// let formatter = LatexBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(Some(theme))
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+/// let latex = String::from_utf8(output).unwrap();
+/// assert!(latex.contains("\\textcolor{LumisKeywordFunction}{fn}"));
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Latex {
+    lang: Language,
+    theme: Option<Theme>,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Only render this window of the source.
+    ///
+    /// The whole source is still parsed so the window keeps the context of the full file.
+    range: Option<SourceRange>,
+}
+
+impl LatexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Latex {
+    fn default() -> Self {
+        Self {
+            lang: Language::PlainText,
+            theme: None,
+            rainbow_brackets: None,
+            range: None,
+        }
+    }
+}
+
+impl Latex {
+    pub fn new(lang: Language, theme: Option<Theme>) -> Self {
+        Self {
+            lang,
+            theme,
+            rainbow_brackets: None,
+            range: None,
+        }
+    }
+}
+
+impl Formatter for Latex {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let mut buffer = String::new();
+
+        buffer.push_str("{\\ttfamily");
+        if let Some(theme) = &self.theme {
+            if theme.fg().is_some() {
+                buffer.push_str("\\color{LumisNormal}");
+            }
+        }
+        buffer.push_str("\\noindent\n");
+
+        let mut line = String::new();
+        let mut lines = Vec::new();
+
+        let brackets = self
+            .rainbow_brackets
+            .as_ref()
+            .map(|rainbow| BracketColors::new(source, self.lang, rainbow, self.theme.as_ref()));
+
+        let window = self
+            .range
+            .as_ref()
+            .map_or(0..source.len(), |range| range.byte_range(source));
+
+        highlight_iter_with_language_in_range(
+            source,
+            self.lang,
+            self.theme.clone(),
+            window.clone(),
+            rainbow(
+                source,
+                brackets.as_ref(),
+                |text, _range, scope, language, style| {
+                    let highlight = self
+                        .theme
+                        .as_ref()
+                        .and_then(|theme| resolve_highlight(theme, scope, language));
+                    let name = highlight.map(color_name);
+                    // Colors that aren't part of the theme, like the ones of rainbow brackets,
+                    // are written as HTML colors instead of the names defined by the preamble.
+                    let custom_fg =
+                        style.fg.as_deref().and_then(hex).filter(|_| {
+                            let theme_fg = self.theme.as_ref().zip(highlight).and_then(
+                                |(theme, highlight)| theme.highlights[highlight].fg.as_ref(),
+                            );
+                            theme_fg != style.fg.as_ref()
+                        });
+
+                    for (i, part) in text.split('\n').enumerate() {
+                        if i > 0 {
+                            lines.push(std::mem::take(&mut line));
+                        }
+                        let part = part.trim_end_matches('\r');
+                        if part.is_empty() {
+                            continue;
+                        }
+                        match &custom_fg {
+                            Some(fg) => {
+                                let mut token = String::new();
+                                let style = Style {
+                                    fg: None,
+                                    ..style.clone()
+                                };
+                                write_token(&mut token, part, name.as_deref(), &style);
+                                let _ = write!(line, "\\textcolor[HTML]{{{}}}{{{}}}", fg, token);
+                            }
+                            None => write_token(&mut line, part, name.as_deref(), style),
+                        }
+                    }
+                    Ok::<_, io::Error>(())
+                },
+            ),
+        )
+        .map_err(io::Error::other)?;
+
+        if !source[window].ends_with('\n') || !line.is_empty() {
+            lines.push(line);
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            if line.is_empty() {
+                buffer.push_str("\\mbox{}");
+            } else {
+                buffer.push_str(line);
+            }
+            // The empty group keeps a next line starting with `[` or `*` from being read as
+            // an argument of `\\`.
+            if i + 1 < lines.len() {
+                buffer.push_str("\\\\{}");
+            }
+            buffer.push('\n');
+        }
+
+        buffer.push('}');
+
+        output.write_all(buffer.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Generate the LaTeX preamble that loads `xcolor` and defines a color for every
+/// highlight in `theme`.
+///
+/// Foreground colors are named after the highlight scope in camel case with a `Lumis`
+/// prefix, and background colors get an additional `Bg` suffix.
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{formatter::latex, themes};
+///
+/// let theme = themes::get("dracula").unwrap();
+/// let preamble = latex::preamble(&theme);
+///
+/// assert!(preamble.starts_with("\\usepackage{xcolor}"));
+/// assert!(preamble.contains("\\definecolor{LumisNormal}{HTML}{F8F8F2}"));
+/// assert!(preamble.contains("\\definecolor{LumisNormalBg}{HTML}{282A36}"));
+/// ```
+pub fn preamble(theme: &Theme) -> String {
+    let mut preamble = String::from("\\usepackage{xcolor}\n");
+
+    for (scope, style) in &theme.highlights {
+        let name = color_name(scope);
+
+        if let Some(fg) = style.fg.as_deref().and_then(hex) {
+            let _ = writeln!(preamble, "\\definecolor{{{}}}{{HTML}}{{{}}}", name, fg);
+        }
+        if let Some(bg) = style.bg.as_deref().and_then(hex) {
+            let _ = writeln!(preamble, "\\definecolor{{{}Bg}}{{HTML}}{{{}}}", name, bg);
+        }
+    }
+
+    preamble
+}
+
+/// Find the theme highlight that styles `scope` in `language`, following the same
+/// fallback as [`Theme::get_style`].
+fn resolve_highlight<'a>(theme: &'a Theme, scope: &str, language: &str) -> Option<&'a str> {
+    if scope.is_empty() {
+        return None;
+    }
+
+    let mut candidate = format!("{}.{}", scope, language);
+    loop {
+        if let Some((key, _)) = theme.highlights.get_key_value(&candidate) {
+            return Some(key);
+        }
+        match candidate.rsplit_once('.') {
+            Some((parent, _)) => candidate = parent.to_string(),
+            None => return None,
+        }
+    }
+}
+
+/// Convert a highlight scope into a LaTeX color name, e.g. `keyword.function` into
+/// `LumisKeywordFunction`.
+fn color_name(scope: &str) -> String {
+    let mut name = String::from("Lumis");
+
+    for part in scope.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+
+    name
+}
+
+fn hex(color: &str) -> Option<String> {
+    let hex = color.trim_start_matches('#');
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hex.to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+fn write_token(output: &mut String, text: &str, name: Option<&str>, style: &Style) {
+    let mut token = escape(text);
+
+    if style.bold {
+        token = format!("\\textbf{{{}}}", token);
+    }
+    if style.italic {
+        token = format!("\\textit{{{}}}", token);
+    }
+    if style.text_decoration.underline != UnderlineStyle::None {
+        token = format!("\\underline{{{}}}", token);
+    }
+
+    if let Some(name) = name {
+        if style.fg.as_deref().and_then(hex).is_some() {
+            token = format!("\\textcolor{{{}}}{{{}}}", name, token);
+        }
+        if style.bg.as_deref().and_then(hex).is_some() {
+            token = format!(
+                "{{\\setlength{{\\fboxsep}}{{0pt}}\\colorbox{{{}Bg}}{{{}}}}}",
+                name, token
+            );
+        }
+    }
+
+    output.push_str(&token);
+}
+
+/// Escape LaTeX special characters and preserve spaces and tabs with control spaces.
+fn escape(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => buf.push_str("\\textbackslash{}"),
+            '{' => buf.push_str("\\{"),
+            '}' => buf.push_str("\\}"),
+            '#' => buf.push_str("\\#"),
+            '$' => buf.push_str("\\$"),
+            '%' => buf.push_str("\\%"),
+            '&' => buf.push_str("\\&"),
+            '_' => buf.push_str("\\_"),
+            '^' => buf.push_str("\\textasciicircum{}"),
+            '~' => buf.push_str("\\textasciitilde{}"),
+            ' ' => buf.push_str("\\ "),
+            '\t' => buf.push_str("\\ \\ \\ \\ "),
+            _ => buf.push(c),
+        }
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    fn render(formatter: &Latex, code: &str) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_colors_by_name() {
+        let formatter = LatexBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .build()
+            .unwrap();
+
+        let latex = render(&formatter, "fn main() {}");
+
+        assert!(latex.starts_with("{\\ttfamily\\color{LumisNormal}\\noindent\n"));
+        assert!(latex.contains("\\textcolor{LumisKeywordFunction}{fn}\\ "));
+        assert!(latex.contains("\\textcolor{LumisFunction}{main}"));
+        assert!(latex.ends_with('}'));
+    }
+
+    #[test]
+    fn test_escape_special_characters() {
+        assert_eq!(
+            escape("\\ { } # $ % & _ ^ ~"),
+            "\\textbackslash{}\\ \\{\\ \\}\\ \\#\\ \\$\\ \\%\\ \\&\\ \\_\\ \\textasciicircum{}\\ \\textasciitilde{}"
+        );
+    }
+
+    #[test]
+    fn test_preserve_whitespace_and_lines() {
+        let formatter = Latex::default();
+        let latex = render(&formatter, "a  b\n\n\tc\n");
+
+        assert_eq!(
+            latex,
+            "{\\ttfamily\\noindent\na\\ \\ b\\\\{}\n\\mbox{}\\\\{}\n\\ \\ \\ \\ c\n}"
+        );
+    }
+
+    #[test]
+    fn test_line_starting_with_bracket() {
+        let formatter = LatexBuilder::new().lang(Language::JSON).build().unwrap();
+        let latex = render(&formatter, "[\n[1],\n*\n]");
+
+        assert!(latex.contains("\\\\{}\n[1],\\\\{}\n*\\\\{}\n]"));
+    }
+
+    #[test]
+    fn test_font_styles() {
+        let mut theme = themes::get("dracula").unwrap();
+        theme.highlights.insert(
+            "keyword".to_string(),
+            Style {
+                fg: Some("#ff0000".to_string()),
+                bg: Some("#000000".to_string()),
+                bold: true,
+                italic: true,
+                ..Default::default()
+            },
+        );
+        theme.highlights.remove("keyword.function");
+
+        let formatter = Latex::new(Language::Rust, Some(theme));
+        let latex = render(&formatter, "fn");
+
+        assert!(latex.contains(
+            "{\\setlength{\\fboxsep}{0pt}\\colorbox{LumisKeywordBg}{\\textcolor{LumisKeyword}{\\textit{\\textbf{fn}}}}}"
+        ));
+    }
+
+    #[test]
+    fn test_preamble_names() {
+        assert_eq!(color_name("keyword.function"), "LumisKeywordFunction");
+        assert_eq!(color_name("markup.heading.1"), "LumisMarkupHeading1");
+        assert_eq!(color_name("diff.plus"), "LumisDiffPlus");
+
+        let theme = themes::get("dracula").unwrap();
+        let preamble = preamble(&theme);
+
+        assert!(preamble.contains("\\definecolor{LumisComment}{HTML}{6272A4}"));
+    }
+}
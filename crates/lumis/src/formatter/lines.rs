@@ -0,0 +1,149 @@
+//! Highlighted lines and text metrics shared by the formatters drawing text on a grid of
+//! cells, like [`Svg`](super::Svg), [`Terminal`](super::Terminal) and the diff formatters.
+
+use super::whitespace::{render_whitespace, Whitespace};
+use crate::brackets::{rainbow, BracketColors, RainbowBrackets};
+use crate::highlight::{highlight_iter_with_language_in_range, SourceRange, Style};
+use crate::languages::Language;
+use crate::semantic::{semantic, SemanticColors};
+use crate::themes::Theme;
+use std::io;
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+/// Number of cells of a tab.
+const TAB_WIDTH: usize = 4;
+
+/// Highlighted lines, each one a list of `(text, style)` segments.
+pub(crate) type Lines = Vec<Vec<(String, Style)>>;
+
+/// Highlight `source` and split the tokens into lines of `(text, style)` segments.
+pub(crate) fn collect_lines(
+    source: &str,
+    lang: Language,
+    theme: Option<Theme>,
+    rainbow_brackets: Option<&RainbowBrackets>,
+) -> io::Result<Lines> {
+    collect_lines_in_range(
+        source,
+        lang,
+        theme,
+        0..source.len(),
+        rainbow_brackets,
+        false,
+        None,
+    )
+}
+
+/// Highlight the window of `source` selected by `range`, or the whole source, and split the
+/// tokens into lines, returning them with the 1-based number of the first line.
+pub(crate) fn collect_window_lines(
+    source: &str,
+    lang: Language,
+    theme: Option<Theme>,
+    range: Option<&SourceRange>,
+    rainbow_brackets: Option<&RainbowBrackets>,
+) -> io::Result<(Lines, usize)> {
+    let Some(range) = range else {
+        return Ok((collect_lines(source, lang, theme, rainbow_brackets)?, 1));
+    };
+
+    let lines = collect_lines_in_range(
+        source,
+        lang,
+        theme,
+        range.byte_range(source),
+        rainbow_brackets,
+        false,
+        None,
+    )?;
+    Ok((lines, *range.line_range(source).start()))
+}
+
+/// Collect the styled tokens inside `window`, split by line, starting at the line
+/// that contains `window.start`.
+///
+/// Brackets are colored by nesting depth with `rainbow_brackets`, and local variables with
+/// their own color with `semantic_colors`. Whitespace is rendered with `whitespace`.
+pub(crate) fn collect_lines_in_range(
+    source: &str,
+    lang: Language,
+    theme: Option<Theme>,
+    window: Range<usize>,
+    rainbow_brackets: Option<&RainbowBrackets>,
+    semantic_colors: bool,
+    whitespace: Option<Whitespace>,
+) -> io::Result<Lines> {
+    let mut lines: Lines = vec![Vec::new()];
+    let brackets =
+        rainbow_brackets.map(|rainbow| BracketColors::new(source, lang, rainbow, theme.as_ref()));
+    let identifiers = semantic_colors.then(|| SemanticColors::new(source, lang, theme.as_ref()));
+    let whitespace_style = theme
+        .as_ref()
+        .and_then(|theme| theme.whitespace_style())
+        .unwrap_or_default();
+
+    highlight_iter_with_language_in_range(
+        source,
+        lang,
+        theme,
+        window.clone(),
+        rainbow(
+            source,
+            brackets.as_ref(),
+            semantic(
+                source,
+                identifiers.as_ref(),
+                render_whitespace(
+                    source,
+                    whitespace,
+                    whitespace_style,
+                    |text, _range, _scope, _language, style| {
+                        for (i, part) in text.split('\n').enumerate() {
+                            if i > 0 {
+                                lines.push(Vec::new());
+                            }
+                            let part = part.trim_end_matches('\r');
+                            if !part.is_empty() {
+                                if let Some(line) = lines.last_mut() {
+                                    line.push((part.to_string(), style.clone()));
+                                }
+                            }
+                        }
+                        Ok::<_, io::Error>(())
+                    },
+                ),
+            ),
+        ),
+    )
+    .map_err(io::Error::other)?;
+
+    if source[window].ends_with('\n') {
+        lines.pop();
+    }
+
+    Ok(lines)
+}
+
+/// Width of `text` in cells, with tabs expanded and wide characters like CJK and emoji
+/// taking two cells.
+pub(crate) fn text_width(text: &str) -> usize {
+    expand_tabs(text).width()
+}
+
+pub(crate) fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width("let a"), 5);
+        assert_eq!(text_width("\ta"), 5);
+        assert_eq!(text_width("日本語"), 6);
+        assert_eq!(text_width("ok 👍"), 5);
+    }
+}
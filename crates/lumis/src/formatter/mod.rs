@@ -1,10 +1,17 @@
 //! Formatter implementations for generating syntax highlighted output.
 //!
-//! This module provides four different formatters for rendering syntax highlighted code:
+//! This module provides several formatters for rendering syntax highlighted code:
 //! - [`html_inline`] - HTML output with inline CSS styles (single theme)
 //! - [`html_multi_themes`] - HTML output with inline CSS styles (multiple themes)
 //! - [`html_linked`] - HTML output with CSS classes (requires external CSS)
 //! - [`terminal`] - ANSI color codes for terminal output
+//! - [`svg`] - Standalone SVG image
+//! - [`latex`] - LaTeX markup for papers and books
+//! - [`rtf`] - Rich Text Format for pasting into office apps
+//! - [`json`] - Structured token stream
+//! - [`html_diff`] - HTML output for unified diffs, highlighting each file with its language
+//! - [`terminal_diff`] - ANSI colored output for unified diffs
+//! - `png` - PNG code screenshots (requires the `image` feature)
 //!
 //! # Builder Pattern
 //!
@@ -13,10 +20,17 @@
 //! - [`HtmlMultiThemesBuilder`] - Create HTML formatters with multiple theme support
 //! - [`HtmlLinkedBuilder`] - Create HTML formatters with CSS classes
 //! - [`TerminalBuilder`] - Create terminal formatters with ANSI colors
+//! - [`SvgBuilder`] - Create SVG image formatters
+//! - [`LatexBuilder`] - Create LaTeX formatters
+//! - [`RtfBuilder`] - Create RTF formatters
+//! - [`JsonBuilder`] - Create JSON token stream formatters
+//! - [`HtmlDiffBuilder`] - Create HTML formatters for unified diffs
+//! - [`TerminalDiffBuilder`] - Create terminal formatters for unified diffs
+//! - `PngBuilder` - Create PNG image formatters (requires the `image` feature)
 //!
 //! Builders are exported at the crate root for convenient access:
 //! ```rust
-//! use lumis::{HtmlInlineBuilder, HtmlMultiThemesBuilder, HtmlLinkedBuilder, TerminalBuilder};
+//! use lumis::{HtmlInlineBuilder, HtmlMultiThemesBuilder, HtmlLinkedBuilder, TerminalBuilder, SvgBuilder};
 //! ```
 //!
 //! # Examples
@@ -151,14 +165,46 @@ pub mod html_inline;
 pub use html_inline::{HtmlInline, HtmlInlineBuilder};
 
 pub mod html_multi_themes;
-pub use html_multi_themes::{HtmlMultiThemes, HtmlMultiThemesBuilder};
+pub use html_multi_themes::{HtmlMultiThemes, HtmlMultiThemesBuilder, ThemeEntry, ThemeList};
 
 pub mod html_linked;
 pub use html_linked::{HtmlLinked, HtmlLinkedBuilder};
 
+pub mod compact;
+pub use compact::CompactClasses;
+
 pub mod terminal;
 pub use terminal::{Terminal, TerminalBuilder};
 
+pub mod svg;
+pub use svg::{Svg, SvgBuilder};
+
+pub mod latex;
+pub use latex::{Latex, LatexBuilder};
+
+pub mod rtf;
+pub use rtf::{Rtf, RtfBuilder};
+
+pub mod json;
+pub use json::{Json, JsonBuilder};
+
+pub mod diff;
+
+pub mod html_diff;
+pub use html_diff::{HtmlDiff, HtmlDiffBuilder};
+
+pub mod terminal_diff;
+pub use terminal_diff::{TerminalDiff, TerminalDiffBuilder};
+
+pub(crate) mod lines;
+
+pub(crate) mod whitespace;
+
+#[cfg(feature = "image")]
+pub mod png;
+#[cfg(feature = "image")]
+pub use png::{Png, PngBuilder};
+
 /// Configuration for wrapping the formatted output with custom HTML elements.
 ///
 /// This struct allows you to specify opening and closing HTML tags that will wrap
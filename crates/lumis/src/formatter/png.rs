@@ -0,0 +1,611 @@
+//! PNG formatter for code screenshots.
+//!
+//! This module provides the [`Png`] formatter that rasterizes highlighted code into a PNG
+//! image on the CPU, in the style of tools like carbon and silicon: the code is drawn on a
+//! window filled with the theme background, optionally decorated with window chrome, a drop
+//! shadow and a line number gutter.
+//!
+//! Text is rendered with a bundled copy of DejaVu Sans Mono so the output is the same on
+//! every platform and no system fonts or browser are required. Italic text is drawn as a
+//! synthesized oblique of the regular face.
+//!
+//! Requires the `image` feature.
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::lines::{collect_window_lines, text_width};
+use super::svg::{CHROME_BUTTONS, CHROME_HEIGHT};
+use super::Formatter;
+use crate::ansi::hex_to_rgb;
+use crate::brackets::RainbowBrackets;
+use crate::highlight::{SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::{Theme, UnderlineStyle};
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use derive_builder::Builder;
+use std::io::{self, Write};
+use tiny_skia::{Color, FillRule, Mask, Paint, Path, PathBuilder, Pixmap, Rect, Shader, Transform};
+use unicode_width::UnicodeWidthChar;
+
+const FONT_REGULAR: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+const FONT_BOLD: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono-Bold.ttf");
+
+/// Corner radius of the window when window chrome is enabled.
+const WINDOW_RADIUS: f32 = 8.0;
+
+/// Transparent space around the window reserved for the drop shadow.
+const SHADOW_MARGIN: f32 = 40.0;
+
+/// Vertical offset of the drop shadow relative to the window.
+const SHADOW_OFFSET_Y: f32 = 10.0;
+
+/// Blur radius of the drop shadow.
+const SHADOW_BLUR: f32 = 20.0;
+
+/// Opacity of the darkest part of the drop shadow.
+const SHADOW_OPACITY: f32 = 0.45;
+
+/// Horizontal shear applied to glyphs to synthesize italics.
+const ITALIC_SKEW: f32 = 0.2;
+
+/// Colors used when no theme is set or the theme lacks a `normal` color.
+const DEFAULT_FG: (u8, u8, u8) = (0x00, 0x00, 0x00);
+const DEFAULT_BG: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+/// PNG formatter for syntax highlighting.
+///
+/// Rasterizes the highlighted code into a PNG image using a bundled monospace font.
+/// All sizes are given in logical pixels and multiplied by `scale` when drawing,
+/// so the default scale of `2.0` produces images that stay sharp on high density displays.
+/// Use [`PngBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Generating code screenshots for documentation, blog posts or social cards
+/// - Places that only accept raster images
+/// - Replacing headless browser pipelines that screenshot HTML output
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{PngBuilder, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() { println!(\"Hello\"); }";
+/// let theme = themes::get("dracula").unwrap();
+///
+/// let formatter = PngBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(Some(theme))
+///     .line_numbers(true)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+/// assert_eq!(&output[1..4], b"PNG");
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Png {
+    lang: Language,
+    theme: Option<Theme>,
+    /// Font size in logical pixels.
+    font_size: f32,
+    /// Line height as a multiple of the font size.
+    line_height: f32,
+    /// Space in logical pixels between the code and the edges of the window.
+    padding: f32,
+    /// Pixel density multiplier applied to every size.
+    scale: f32,
+    /// Draw rounded corners and a title bar with window buttons above the code.
+    window_chrome: bool,
+    /// Draw a soft drop shadow below the window.
+    shadow: bool,
+    /// Render a line number gutter to the left of the code.
+    line_numbers: bool,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Only render this window of the source.
+    ///
+    /// The whole source is still parsed so the window keeps the context of the full file,
+    /// and line numbers keep referring to the lines of the full file.
+    range: Option<SourceRange>,
+}
+
+impl PngBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Png {
+    fn default() -> Self {
+        Self {
+            lang: Language::PlainText,
+            theme: None,
+            font_size: 14.0,
+            line_height: 1.5,
+            padding: 24.0,
+            scale: 2.0,
+            window_chrome: true,
+            shadow: true,
+            line_numbers: false,
+            rainbow_brackets: None,
+            range: None,
+        }
+    }
+}
+
+impl Png {
+    /// Render `source` and return the encoded PNG bytes.
+    pub fn render(&self, source: &str) -> io::Result<Vec<u8>> {
+        let (lines, first_line) = collect_window_lines(
+            source,
+            self.lang,
+            self.theme.clone(),
+            self.range.as_ref(),
+            self.rainbow_brackets.as_ref(),
+        )?;
+        let fonts = Fonts::load()?;
+
+        let s = self.scale;
+        let font_size = self.font_size * s;
+        let line_height = font_size * self.line_height;
+        let padding = self.padding * s;
+        let char_width = fonts.char_width(font_size);
+
+        let margin = if self.shadow { SHADOW_MARGIN * s } else { 0.0 };
+        let chrome = if self.window_chrome {
+            CHROME_HEIGHT * s
+        } else {
+            0.0
+        };
+        let radius = if self.window_chrome {
+            WINDOW_RADIUS * s
+        } else {
+            0.0
+        };
+
+        let gutter_cols = if self.line_numbers {
+            (first_line + lines.len().max(1) - 1).to_string().len() + 2
+        } else {
+            0
+        };
+        let max_cols = lines
+            .iter()
+            .map(|line| line.iter().map(|(text, _)| text_width(text)).sum())
+            .max()
+            .unwrap_or(0);
+
+        let window_width = padding * 2.0 + (gutter_cols + max_cols) as f32 * char_width;
+        let window_height = chrome + padding * 2.0 + lines.len() as f32 * line_height;
+        let width = (window_width + margin * 2.0).ceil() as u32;
+        let height = (window_height + margin * 2.0).ceil() as u32;
+
+        let mut pixmap = Pixmap::new(width.max(1), height.max(1))
+            .ok_or_else(|| io::Error::other("image dimensions are too large"))?;
+
+        let fg = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.fg())
+            .and_then(hex_to_rgb)
+            .unwrap_or(DEFAULT_FG);
+        let bg = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.bg())
+            .and_then(hex_to_rgb)
+            .unwrap_or(DEFAULT_BG);
+
+        let window = rounded_rect(margin, margin, window_width, window_height, radius);
+
+        if self.shadow {
+            if let Some(window) = &window {
+                draw_shadow(&mut pixmap, window, s);
+            }
+        }
+
+        if let Some(window) = &window {
+            pixmap.fill_path(
+                window,
+                &paint(bg),
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        }
+
+        if self.window_chrome {
+            for (i, color) in CHROME_BUTTONS.iter().enumerate() {
+                let cx = margin + padding + (6.0 + i as f32 * 20.0) * s;
+                let cy = margin + chrome / 2.0;
+                let rgb = hex_to_rgb(color).unwrap_or(DEFAULT_FG);
+                if let Some(circle) = PathBuilder::from_circle(cx, cy, 6.0 * s) {
+                    pixmap.fill_path(
+                        &circle,
+                        &paint(rgb),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+            }
+        }
+
+        let mut canvas = Canvas {
+            pixmap: &mut pixmap,
+            fonts: &fonts,
+            font_size,
+            char_width,
+        };
+
+        let code_x = margin + padding + gutter_cols as f32 * char_width;
+        let top = margin + chrome + padding;
+        let (ascent, descent) = fonts.vertical_metrics(font_size);
+
+        for (i, line) in lines.iter().enumerate() {
+            let line_top = top + i as f32 * line_height;
+            let baseline = line_top + (line_height - (ascent - descent)) / 2.0 + ascent;
+
+            if self.line_numbers {
+                let number = (first_line + i).to_string();
+                let x = code_x - (number.len() + 2) as f32 * char_width;
+                canvas.draw_text(&number, x, baseline, fg, 0.5, &Style::default());
+            }
+
+            let mut x = code_x;
+            for (text, style) in line {
+                let token_width = text_width(text) as f32 * char_width;
+
+                if let Some(token_bg) = style.bg.as_deref().and_then(hex_to_rgb) {
+                    canvas.fill_rect(x, line_top, token_width, line_height, token_bg);
+                }
+
+                let color = style.fg.as_deref().and_then(hex_to_rgb).unwrap_or(fg);
+                canvas.draw_text(text, x, baseline, color, 1.0, style);
+
+                let thickness = s.max(1.0);
+                if style.text_decoration.underline != UnderlineStyle::None {
+                    canvas.fill_rect(x, baseline + 2.0 * s, token_width, thickness, color);
+                }
+                if style.text_decoration.strikethrough {
+                    canvas.fill_rect(x, baseline - font_size * 0.3, token_width, thickness, color);
+                }
+
+                x += token_width;
+            }
+        }
+
+        pixmap.encode_png().map_err(io::Error::other)
+    }
+}
+
+impl Formatter for Png {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let png = self.render(source)?;
+        output.write_all(&png)?;
+        Ok(())
+    }
+}
+
+struct Fonts {
+    regular: FontRef<'static>,
+    bold: FontRef<'static>,
+}
+
+impl Fonts {
+    fn load() -> io::Result<Self> {
+        Ok(Self {
+            regular: FontRef::try_from_slice(FONT_REGULAR).map_err(io::Error::other)?,
+            bold: FontRef::try_from_slice(FONT_BOLD).map_err(io::Error::other)?,
+        })
+    }
+
+    fn char_width(&self, font_size: f32) -> f32 {
+        let scaled = self.regular.as_scaled(PxScale::from(font_size));
+        scaled.h_advance(self.regular.glyph_id('M'))
+    }
+
+    fn vertical_metrics(&self, font_size: f32) -> (f32, f32) {
+        let scaled = self.regular.as_scaled(PxScale::from(font_size));
+        (scaled.ascent(), scaled.descent())
+    }
+}
+
+struct Canvas<'a> {
+    pixmap: &'a mut Pixmap,
+    fonts: &'a Fonts,
+    font_size: f32,
+    char_width: f32,
+}
+
+impl Canvas<'_> {
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: (u8, u8, u8)) {
+        if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+            self.pixmap
+                .fill_rect(rect, &paint(color), Transform::identity(), None);
+        }
+    }
+
+    /// Draw `text` starting at `x` on `baseline`, advancing one cell per character, or two for
+    /// wide characters like CJK and emoji.
+    fn draw_text(
+        &mut self,
+        text: &str,
+        x: f32,
+        baseline: f32,
+        color: (u8, u8, u8),
+        opacity: f32,
+        style: &Style,
+    ) {
+        let font = if style.bold {
+            &self.fonts.bold
+        } else {
+            &self.fonts.regular
+        };
+        let skew = if style.italic { ITALIC_SKEW } else { 0.0 };
+
+        let mut x = x;
+        for c in text.chars() {
+            if c == '\t' {
+                x += text_width("\t") as f32 * self.char_width;
+                continue;
+            }
+
+            let glyph = font
+                .glyph_id(c)
+                .with_scale_and_position(PxScale::from(self.font_size), point(x, baseline));
+
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let py = bounds.min.y + gy as f32;
+                    let px = bounds.min.x + gx as f32 + (baseline - py) * skew;
+                    let alpha = coverage * opacity;
+
+                    // Split coverage between neighbouring pixels so sheared glyphs stay smooth.
+                    let fract = px - px.floor();
+                    blend(self.pixmap, px.floor(), py, color, alpha * (1.0 - fract));
+                    blend(self.pixmap, px.floor() + 1.0, py, color, alpha * fract);
+                });
+            }
+
+            x += c.width().unwrap_or(0) as f32 * self.char_width;
+        }
+    }
+}
+
+/// Blend `color` with `alpha` over the premultiplied pixel at `(x, y)`.
+fn blend(pixmap: &mut Pixmap, x: f32, y: f32, color: (u8, u8, u8), alpha: f32) {
+    if alpha <= 0.0 || x < 0.0 || y < 0.0 {
+        return;
+    }
+
+    let (x, y) = (x as u32, y as u32);
+    if x >= pixmap.width() || y >= pixmap.height() {
+        return;
+    }
+
+    let alpha = alpha.min(1.0);
+    let index = ((y * pixmap.width() + x) * 4) as usize;
+    let data = pixmap.data_mut();
+    let src = [color.0, color.1, color.2, 255];
+
+    for (channel, value) in src.iter().enumerate() {
+        let dst = data[index + channel] as f32;
+        data[index + channel] = (*value as f32 * alpha + dst * (1.0 - alpha)).round() as u8;
+    }
+}
+
+/// Draw a blurred, offset silhouette of `window` below it.
+fn draw_shadow(pixmap: &mut Pixmap, window: &Path, scale: f32) {
+    let Some(mut mask) = Mask::new(pixmap.width(), pixmap.height()) else {
+        return;
+    };
+
+    mask.fill_path(
+        window,
+        FillRule::Winding,
+        true,
+        Transform::from_translate(0.0, SHADOW_OFFSET_Y * scale),
+    );
+
+    let (width, height) = (mask.width() as usize, mask.height() as usize);
+    let radius = (SHADOW_BLUR * scale / 3.0).round() as usize;
+
+    // Three box blur passes approximate a gaussian blur.
+    for _ in 0..3 {
+        box_blur(mask.data_mut(), width, height, radius);
+    }
+
+    let data = pixmap.data_mut();
+    for (i, coverage) in mask.data().iter().enumerate() {
+        let alpha = *coverage as f32 / 255.0 * SHADOW_OPACITY;
+        let pixel = &mut data[i * 4..i * 4 + 4];
+        for value in pixel.iter_mut().take(3) {
+            *value = (*value as f32 * (1.0 - alpha)).round() as u8;
+        }
+        pixel[3] = (alpha * 255.0 + pixel[3] as f32 * (1.0 - alpha)).round() as u8;
+    }
+}
+
+/// Separable box blur over a single channel buffer, treating pixels outside as empty.
+fn box_blur(data: &mut [u8], width: usize, height: usize, radius: usize) {
+    if radius == 0 {
+        return;
+    }
+
+    let mut buffer = vec![0u8; data.len()];
+    let window = (radius * 2 + 1) as u32;
+
+    for y in 0..height {
+        let row = &data[y * width..(y + 1) * width];
+        let mut sum: u32 = row.iter().take(radius + 1).map(|v| *v as u32).sum();
+        for x in 0..width {
+            buffer[y * width + x] = (sum / window) as u8;
+            if x + radius + 1 < width {
+                sum += row[x + radius + 1] as u32;
+            }
+            if x >= radius {
+                sum -= row[x - radius] as u32;
+            }
+        }
+    }
+
+    for x in 0..width {
+        let at = |y: usize| buffer[y * width + x] as u32;
+        let mut sum: u32 = (0..height.min(radius + 1)).map(at).sum();
+        for y in 0..height {
+            data[y * width + x] = (sum / window) as u8;
+            if y + radius + 1 < height {
+                sum += at(y + radius + 1);
+            }
+            if y >= radius {
+                sum -= at(y - radius);
+            }
+        }
+    }
+}
+
+fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<Path> {
+    if radius <= 0.0 {
+        return Some(PathBuilder::from_rect(Rect::from_xywh(
+            x, y, width, height,
+        )?));
+    }
+
+    let r = radius.min(width / 2.0).min(height / 2.0);
+    let (right, bottom) = (x + width, y + height);
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(x + r, y);
+    pb.line_to(right - r, y);
+    pb.quad_to(right, y, right, y + r);
+    pb.line_to(right, bottom - r);
+    pb.quad_to(right, bottom, right - r, bottom);
+    pb.line_to(x + r, bottom);
+    pb.quad_to(x, bottom, x, bottom - r);
+    pb.line_to(x, y + r);
+    pb.quad_to(x, y, x + r, y);
+    pb.close();
+    pb.finish()
+}
+
+fn paint(color: (u8, u8, u8)) -> Paint<'static> {
+    Paint {
+        shader: Shader::SolidColor(Color::from_rgba8(color.0, color.1, color.2, 255)),
+        anti_alias: true,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    fn decode(png: &[u8]) -> Pixmap {
+        Pixmap::decode_png(png).unwrap()
+    }
+
+    fn pixel(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let color = pixmap.pixel(x, y).unwrap().demultiply();
+        (color.red(), color.green(), color.blue(), color.alpha())
+    }
+
+    #[test]
+    fn test_png_signature() {
+        let formatter = Png::default();
+        let mut buffer = Vec::new();
+        formatter.format("fn main() {}", &mut buffer).unwrap();
+
+        assert_eq!(&buffer[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_background_from_theme() {
+        let formatter = PngBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .window_chrome(false)
+            .shadow(false)
+            .scale(1.0)
+            .build()
+            .unwrap();
+
+        let pixmap = decode(&formatter.render("fn main() {}").unwrap());
+
+        assert_eq!(pixel(&pixmap, 0, 0), (0x28, 0x2a, 0x36, 255));
+        assert_eq!(
+            pixel(&pixmap, pixmap.width() - 1, 0),
+            (0x28, 0x2a, 0x36, 255)
+        );
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let formatter = PngBuilder::new()
+            .window_chrome(false)
+            .shadow(false)
+            .padding(10.0)
+            .scale(1.0)
+            .build()
+            .unwrap();
+
+        let one = decode(&formatter.render("a").unwrap());
+        let three = decode(&formatter.render("a\nb\nc\n").unwrap());
+        let line_height = 14.0 * 1.5;
+
+        assert_eq!(one.height(), (10.0 * 2.0 + line_height) as u32);
+        assert_eq!(three.height(), (10.0 * 2.0 + line_height * 3.0) as u32);
+
+        let scaled = PngBuilder::new()
+            .window_chrome(false)
+            .shadow(false)
+            .padding(10.0)
+            .scale(2.0)
+            .build()
+            .unwrap();
+        let scaled = decode(&scaled.render("a").unwrap());
+
+        assert_eq!(scaled.height(), one.height() * 2);
+    }
+
+    #[test]
+    fn test_shadow_and_chrome() {
+        let formatter = PngBuilder::new()
+            .theme(themes::get("github_light").ok())
+            .scale(1.0)
+            .build()
+            .unwrap();
+
+        let pixmap = decode(&formatter.render("a").unwrap());
+
+        // Corner of the margin is fully transparent, the area below the window is shadowed.
+        assert_eq!(pixel(&pixmap, 0, 0).3, 0);
+        let below = SHADOW_MARGIN as u32 + 4;
+        assert!(pixel(&pixmap, pixmap.width() / 2, pixmap.height() - below).3 > 0);
+
+        // First window button is drawn in the title bar.
+        let button_x = (SHADOW_MARGIN + 24.0 + 6.0) as u32;
+        let button_y = (SHADOW_MARGIN + CHROME_HEIGHT / 2.0) as u32;
+        assert_eq!(pixel(&pixmap, button_x, button_y), (0xff, 0x5f, 0x56, 255));
+    }
+
+    #[test]
+    fn test_line_numbers_widen_image() {
+        let plain = PngBuilder::new().scale(1.0).build().unwrap();
+        let numbered = PngBuilder::new()
+            .scale(1.0)
+            .line_numbers(true)
+            .build()
+            .unwrap();
+
+        let plain = decode(&plain.render("let x = 1;").unwrap());
+        let numbered = decode(&numbered.render("let x = 1;").unwrap());
+
+        assert!(numbered.width() > plain.width());
+        assert_eq!(numbered.height(), plain.height());
+    }
+}
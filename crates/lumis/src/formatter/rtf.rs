@@ -0,0 +1,395 @@
+//! RTF formatter for pasting highlighted code into office apps.
+//!
+//! This module provides the [`Rtf`] formatter that renders highlighted code as a Rich Text
+//! Format document. RTF is understood by the clipboard of most word processors and
+//! presentation apps (Word, Pages, Keynote, Google Docs), so the colors survive a paste.
+//!
+//! The color table is built from every color in the [`Theme`] highlights and each token is
+//! written as a run with its foreground, background, bold, italic, underline and
+//! strikethrough taken from the [`Style`].
+//!
+//! # Example Output
+//!
+//! For the Rust code `fn main() {}` with the dracula theme, the formatter generates:
+//!
+//! ```text
+//! {\rtf1\ansi\ansicpg1252\deff0
+//! {\fonttbl{\f0\fmodern\fcharset0 Courier New;}}
+//! {\colortbl;\red248\green248\blue242;\red40\green42\blue54;...}
+//! \f0\fs24\cf1\highlight2 {\cf7 fn} {\cf9 main}...}
+//! ```
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::Formatter;
+use crate::ansi::hex_to_rgb;
+use crate::brackets::{rainbow, BracketColors, RainbowBrackets};
+use crate::highlight::{highlight_iter_with_language_in_range, SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::{Theme, UnderlineStyle};
+use derive_builder::Builder;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// RTF formatter for syntax highlighting.
+///
+/// Generates a complete RTF document that can be written to the clipboard or saved as a
+/// `.rtf` file. Use [`RtfBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Pasting highlighted code into Word, Pages, Keynote or Google Docs
+/// - Clipboard workflows like `lumis highlight file.rs -f rtf | pbcopy`
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{RtfBuilder, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() { println!(\"Hello\"); }";
+/// let theme = themes::get("dracula").unwrap();
+///
+/// let formatter = RtfBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(Some(theme))
+///     .font_family("Menlo")
+///     .font_size(14.0)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+/// let rtf = String::from_utf8(output).unwrap();
+/// assert!(rtf.starts_with("{\\rtf1"));
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Rtf {
+    lang: Language,
+    theme: Option<Theme>,
+    /// Font used for the code text.
+    #[builder(setter(into))]
+    font_family: String,
+    /// Font size in points.
+    font_size: f32,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Only render this window of the source.
+    ///
+    /// The whole source is still parsed so the window keeps the context of the full file.
+    range: Option<SourceRange>,
+}
+
+impl RtfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Rtf {
+    fn default() -> Self {
+        Self {
+            lang: Language::PlainText,
+            theme: None,
+            font_family: "Courier New".to_string(),
+            font_size: 12.0,
+            rainbow_brackets: None,
+            range: None,
+        }
+    }
+}
+
+impl Rtf {
+    pub fn new(lang: Language, theme: Option<Theme>) -> Self {
+        Self {
+            lang,
+            theme,
+            ..Default::default()
+        }
+    }
+}
+
+impl Formatter for Rtf {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let brackets = self
+            .rainbow_brackets
+            .as_ref()
+            .map(|rainbow| BracketColors::new(source, self.lang, rainbow, self.theme.as_ref()));
+        let colors = ColorTable::new(
+            self.theme.as_ref(),
+            brackets.as_ref().map_or(&[], BracketColors::palette),
+        );
+        let mut buffer = String::new();
+
+        buffer.push_str("{\\rtf1\\ansi\\ansicpg1252\\deff0\n");
+        let _ = writeln!(
+            buffer,
+            "{{\\fonttbl{{\\f0\\fmodern\\fcharset0 {};}}}}",
+            escape(&self.font_family)
+        );
+        buffer.push_str(&colors.to_rtf());
+
+        let _ = write!(buffer, "\\f0\\fs{}", (self.font_size * 2.0).round() as u32);
+        if let Some(theme) = &self.theme {
+            if let Some(fg) = theme.fg().and_then(|fg| colors.index(fg)) {
+                let _ = write!(buffer, "\\cf{}", fg);
+            }
+            if let Some(bg) = theme.bg().and_then(|bg| colors.index(bg)) {
+                let _ = write!(buffer, "\\highlight{}", bg);
+            }
+        }
+        buffer.push(' ');
+
+        // Paragraph breaks are deferred so the trailing newline of the source
+        // does not produce an empty paragraph at the end of the document.
+        let mut pending_breaks = 0;
+
+        let window = self
+            .range
+            .as_ref()
+            .map_or(0..source.len(), |range| range.byte_range(source));
+
+        highlight_iter_with_language_in_range(
+            source,
+            self.lang,
+            self.theme.clone(),
+            window.clone(),
+            rainbow(
+                source,
+                brackets.as_ref(),
+                |text, _range, _scope, _language, style| {
+                    for (i, part) in text.split('\n').enumerate() {
+                        if i > 0 {
+                            pending_breaks += 1;
+                        }
+                        let part = part.trim_end_matches('\r');
+                        if part.is_empty() {
+                            continue;
+                        }
+                        for _ in 0..pending_breaks {
+                            buffer.push_str("\\par\n");
+                        }
+                        pending_breaks = 0;
+                        write_run(&mut buffer, part, style, &colors);
+                    }
+                    Ok::<_, io::Error>(())
+                },
+            ),
+        )
+        .map_err(io::Error::other)?;
+
+        if !source[window].ends_with('\n') {
+            pending_breaks += 1;
+        }
+        for _ in 1..pending_breaks {
+            buffer.push_str("\\par\n");
+        }
+
+        buffer.push('}');
+
+        output.write_all(buffer.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// RTF color table with the distinct colors of a theme, in theme order, followed by
+/// the extra colors of rainbow brackets.
+struct ColorTable {
+    colors: Vec<(u8, u8, u8)>,
+    indexes: HashMap<(u8, u8, u8), usize>,
+}
+
+impl ColorTable {
+    fn new(theme: Option<&Theme>, extra: &[String]) -> Self {
+        let mut table = Self {
+            colors: Vec::new(),
+            indexes: HashMap::new(),
+        };
+
+        let mut colors: Vec<Option<&str>> = Vec::new();
+        if let Some(theme) = theme {
+            // Normal colors first so the defaults get the lowest indexes.
+            colors.extend([theme.fg(), theme.bg()]);
+            colors.extend(
+                theme
+                    .highlights
+                    .values()
+                    .flat_map(|style| [style.fg.as_deref(), style.bg.as_deref()]),
+            );
+        }
+        colors.extend(extra.iter().map(|color| Some(color.as_str())));
+
+        for color in colors.into_iter().flatten() {
+            if let Some(rgb) = hex_to_rgb(color) {
+                if !table.indexes.contains_key(&rgb) {
+                    table.colors.push(rgb);
+                    // Index 0 is reserved for the automatic color.
+                    table.indexes.insert(rgb, table.colors.len());
+                }
+            }
+        }
+
+        table
+    }
+
+    fn index(&self, color: &str) -> Option<usize> {
+        hex_to_rgb(color).and_then(|rgb| self.indexes.get(&rgb).copied())
+    }
+
+    fn to_rtf(&self) -> String {
+        let mut table = String::from("{\\colortbl;");
+        for (r, g, b) in &self.colors {
+            let _ = write!(table, "\\red{}\\green{}\\blue{};", r, g, b);
+        }
+        table.push_str("}\n");
+        table
+    }
+}
+
+fn write_run(output: &mut String, text: &str, style: &Style, colors: &ColorTable) {
+    let mut controls = String::new();
+
+    if let Some(fg) = style.fg.as_deref().and_then(|fg| colors.index(fg)) {
+        let _ = write!(controls, "\\cf{}", fg);
+    }
+    if let Some(bg) = style.bg.as_deref().and_then(|bg| colors.index(bg)) {
+        let _ = write!(controls, "\\highlight{}", bg);
+    }
+    if style.bold {
+        controls.push_str("\\b");
+    }
+    if style.italic {
+        controls.push_str("\\i");
+    }
+    match style.text_decoration.underline {
+        UnderlineStyle::None => {}
+        UnderlineStyle::Solid => controls.push_str("\\ul"),
+        UnderlineStyle::Wavy => controls.push_str("\\ulwave"),
+        UnderlineStyle::Double => controls.push_str("\\uldb"),
+        UnderlineStyle::Dotted => controls.push_str("\\uld"),
+        UnderlineStyle::Dashed => controls.push_str("\\uldash"),
+    }
+    if style.text_decoration.strikethrough {
+        controls.push_str("\\strike");
+    }
+
+    if controls.is_empty() {
+        output.push_str(&escape(text));
+    } else {
+        let _ = write!(output, "{{{} {}}}", controls, escape(text));
+    }
+}
+
+/// Escape RTF control characters and encode non-ASCII characters as `\u` escapes.
+fn escape(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => buf.push_str("\\\\"),
+            '{' => buf.push_str("\\{"),
+            '}' => buf.push_str("\\}"),
+            '\t' => buf.push_str("\\tab "),
+            c if c.is_ascii() => buf.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let _ = write!(buf, "\\u{}?", *unit as i16);
+                }
+            }
+        }
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    fn render(formatter: &Rtf, code: &str) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_color_table_from_theme() {
+        let theme = themes::get("dracula").unwrap();
+        let colors = ColorTable::new(Some(&theme), &[]);
+        let table = colors.to_rtf();
+
+        assert!(
+            table.starts_with("{\\colortbl;\\red248\\green248\\blue242;\\red40\\green42\\blue54;")
+        );
+        assert_eq!(colors.index("#f8f8f2"), Some(1));
+        assert_eq!(colors.index("#282a36"), Some(2));
+        assert_eq!(table.matches(';').count(), colors.colors.len() + 1);
+    }
+
+    #[test]
+    fn test_runs() {
+        let formatter = RtfBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .build()
+            .unwrap();
+
+        let rtf = render(&formatter, "fn main() {}");
+        let colors = ColorTable::new(formatter.theme.as_ref(), &[]);
+        let keyword = colors.index("#8be9fd").unwrap();
+
+        assert!(rtf.starts_with("{\\rtf1\\ansi\\ansicpg1252\\deff0\n"));
+        assert!(rtf.contains("{\\fonttbl{\\f0\\fmodern\\fcharset0 Courier New;}}"));
+        assert!(rtf.contains("\\f0\\fs24\\cf1\\highlight2 "));
+        assert!(rtf.contains(&format!("{{\\cf{} fn}}", keyword)));
+        assert!(rtf.contains("\\{"));
+        assert!(rtf.ends_with('}'));
+    }
+
+    #[test]
+    fn test_font_styles() {
+        let mut theme = themes::get("dracula").unwrap();
+        let mut style = Style {
+            bold: true,
+            italic: true,
+            ..Default::default()
+        };
+        style.text_decoration.underline = UnderlineStyle::Wavy;
+        style.text_decoration.strikethrough = true;
+        theme.highlights.insert("keyword".to_string(), style);
+        theme.highlights.remove("keyword.function");
+
+        let formatter = Rtf::new(Language::Rust, Some(theme));
+        let rtf = render(&formatter, "fn");
+
+        assert!(rtf.contains("{\\b\\i\\ulwave\\strike fn}"));
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let formatter = Rtf::default();
+
+        assert!(render(&formatter, "a\n\nb\n").ends_with(" a\\par\n\\par\nb}"));
+        assert!(render(&formatter, "a\n\n").ends_with(" a\\par\n}"));
+    }
+
+    #[test]
+    fn test_range() {
+        let formatter = RtfBuilder::new()
+            .range(Some(SourceRange::Bytes(2..7)))
+            .build()
+            .unwrap();
+
+        assert!(render(&formatter, "a\nbc\nde\nf\n").ends_with(" bc\\par\nde}"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("{a}\\b\tc"), "\\{a\\}\\\\b\\tab c");
+        assert_eq!(escape("é"), "\\u233?");
+        assert_eq!(escape("😀"), "\\u-10179?\\u-8704?");
+    }
+}
@@ -0,0 +1,443 @@
+//! SVG formatter for resolution-independent code images.
+//!
+//! This module provides the [`Svg`] formatter that renders highlighted code as a standalone
+//! SVG document. Each line becomes a `<text>` element and each styled token a `<tspan>`,
+//! so the output can be embedded wherever HTML is not accepted, like slide decks, READMEs
+//! and social cards.
+//!
+//! # Example Output
+//!
+//! For the Rust code `fn main() {}` with the dracula theme, the formatter generates:
+//!
+//! ```xml
+//! <svg xmlns="http://www.w3.org/2000/svg" width="132.8" height="53" viewBox="0 0 132.8 53">
+//! <rect width="132.8" height="53" fill="#282a36"/>
+//! <g font-family="ui-monospace, SFMono-Regular, Menlo, Consolas, monospace" font-size="14" fill="#f8f8f2">
+//! <text x="16" y="30.7" xml:space="preserve"><tspan fill="#8be9fd">fn</tspan> <tspan fill="#50fa7b">main</tspan>...</text>
+//! </g>
+//! </svg>
+//! ```
+//!
+//! Text metrics are estimated from the font size assuming a monospace font, so pick a
+//! `font_family` that is monospace on the target platform.
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::lines::{collect_window_lines, expand_tabs, text_width};
+use super::Formatter;
+use crate::brackets::RainbowBrackets;
+use crate::highlight::{SourceRange, Style};
+use crate::languages::Language;
+use crate::themes::{Theme, UnderlineStyle};
+use derive_builder::Builder;
+use std::io::{self, Write};
+
+/// Ratio between the advance width of a monospace glyph and the font size.
+const CHAR_WIDTH_RATIO: f32 = 0.6;
+
+/// Height of the title bar drawn when window chrome is enabled.
+pub(super) const CHROME_HEIGHT: f32 = 36.0;
+
+/// Colors of the close, minimize and zoom buttons drawn in the window chrome.
+pub(super) const CHROME_BUTTONS: [&str; 3] = ["#ff5f56", "#ffbd2e", "#27c93f"];
+
+/// SVG formatter for syntax highlighting.
+///
+/// Generates a self-contained SVG image with one `<text>` element per line and one
+/// `<tspan>` per styled token, colored from the theme. Use [`SvgBuilder`] to create instances.
+///
+/// # When to use
+///
+/// - Code snippets in slide decks, READMEs or social cards
+/// - Places where HTML is not accepted but images are
+/// - Output that must stay sharp at any resolution
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{SvgBuilder, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() { println!(\"Hello\"); }";
+/// let theme = themes::get("dracula").unwrap();
+///
+/// let formatter = SvgBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(Some(theme))
+///     .font_family("JetBrains Mono, monospace")
+///     .font_size(16.0)
+///     .window_chrome(true)
+///     .line_numbers(true)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+/// let svg = String::from_utf8(output).unwrap();
+/// assert!(svg.starts_with("<svg"));
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(default)]
+pub struct Svg {
+    lang: Language,
+    theme: Option<Theme>,
+    /// CSS font family list used for the code text.
+    #[builder(setter(into))]
+    font_family: String,
+    /// Font size in pixels.
+    font_size: f32,
+    /// Line height as a multiple of the font size.
+    line_height: f32,
+    /// Space in pixels between the code and the edges of the image.
+    padding: f32,
+    /// Draw a title bar with window buttons above the code.
+    window_chrome: bool,
+    /// Render a line number gutter to the left of the code.
+    line_numbers: bool,
+    /// Number of the first line in the gutter, defaults to 1.
+    line_number_start: usize,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Only render this window of the source.
+    ///
+    /// The whole source is still parsed so the window keeps the context of the full file,
+    /// and line numbers keep referring to the lines of the full file.
+    range: Option<SourceRange>,
+}
+
+impl SvgBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            lang: Language::PlainText,
+            theme: None,
+            font_family: "ui-monospace, SFMono-Regular, Menlo, Consolas, monospace".to_string(),
+            font_size: 14.0,
+            line_height: 1.5,
+            padding: 16.0,
+            window_chrome: false,
+            line_numbers: false,
+            line_number_start: 1,
+            rainbow_brackets: None,
+            range: None,
+        }
+    }
+}
+
+impl Svg {
+    fn char_width(&self) -> f32 {
+        self.font_size * CHAR_WIDTH_RATIO
+    }
+
+    fn line_height_px(&self) -> f32 {
+        self.font_size * self.line_height
+    }
+
+    fn top_offset(&self) -> f32 {
+        if self.window_chrome {
+            CHROME_HEIGHT + self.padding
+        } else {
+            self.padding
+        }
+    }
+
+    fn write_chrome(&self, output: &mut dyn Write) -> io::Result<()> {
+        let cy = CHROME_HEIGHT / 2.0;
+
+        for (i, color) in CHROME_BUTTONS.iter().enumerate() {
+            let cx = self.padding + 6.0 + i as f32 * 20.0;
+            writeln!(
+                output,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"6\" fill=\"{}\"/>",
+                num(cx),
+                num(cy),
+                color
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Formatter for Svg {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let (lines, first_line) = collect_window_lines(
+            source,
+            self.lang,
+            self.theme.clone(),
+            self.range.as_ref(),
+            self.rainbow_brackets.as_ref(),
+        )?;
+
+        let char_width = self.char_width();
+        let line_height = self.line_height_px();
+        let top = self.top_offset();
+
+        let first_number = self.line_number_start + first_line - 1;
+        let gutter_cols = if self.line_numbers {
+            (first_number + lines.len().max(1) - 1).to_string().len() + 2
+        } else {
+            0
+        };
+        let max_cols = lines.iter().map(|line| line_width(line)).max().unwrap_or(0);
+
+        let code_x = self.padding + gutter_cols as f32 * char_width;
+        let width = code_x + max_cols as f32 * char_width + self.padding;
+        let height = top + lines.len() as f32 * line_height + self.padding;
+
+        let fg = self.theme.as_ref().and_then(|theme| theme.fg());
+        let bg = self.theme.as_ref().and_then(|theme| theme.bg());
+
+        let mut buffer = Vec::new();
+
+        writeln!(
+            buffer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = num(width),
+            h = num(height)
+        )?;
+
+        if let Some(bg) = bg {
+            let radius = if self.window_chrome { " rx=\"8\"" } else { "" };
+            writeln!(
+                buffer,
+                "<rect width=\"{}\" height=\"{}\"{} fill=\"{}\"/>",
+                num(width),
+                num(height),
+                radius,
+                bg
+            )?;
+        }
+
+        if self.window_chrome {
+            self.write_chrome(&mut buffer)?;
+        }
+
+        write!(
+            buffer,
+            "<g font-family=\"{}\" font-size=\"{}\"",
+            escape(&self.font_family),
+            num(self.font_size)
+        )?;
+        if let Some(fg) = fg {
+            write!(buffer, " fill=\"{}\"", fg)?;
+        }
+        writeln!(buffer, ">")?;
+
+        for (i, line) in lines.iter().enumerate() {
+            let line_top = top + i as f32 * line_height;
+            let baseline = line_top + (line_height - self.font_size) / 2.0 + self.font_size * 0.8;
+
+            let mut col = 0;
+            for (text, style) in line {
+                let cols = text_width(text);
+                if let Some(token_bg) = &style.bg {
+                    writeln!(
+                        buffer,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                        num(code_x + col as f32 * char_width),
+                        num(line_top),
+                        num(cols as f32 * char_width),
+                        num(line_height),
+                        token_bg
+                    )?;
+                }
+                col += cols;
+            }
+
+            if self.line_numbers {
+                writeln!(
+                    buffer,
+                    "<text x=\"{}\" y=\"{}\" text-anchor=\"end\" opacity=\"0.5\">{}</text>",
+                    num(code_x - 2.0 * char_width),
+                    num(baseline),
+                    first_number + i
+                )?;
+            }
+
+            write!(
+                buffer,
+                "<text x=\"{}\" y=\"{}\" xml:space=\"preserve\">",
+                num(code_x),
+                num(baseline)
+            )?;
+            for (text, style) in line {
+                write_tspan(&mut buffer, text, style)?;
+            }
+            writeln!(buffer, "</text>")?;
+        }
+
+        writeln!(buffer, "</g>")?;
+        write!(buffer, "</svg>")?;
+
+        output.write_all(&buffer)?;
+        Ok(())
+    }
+}
+
+fn write_tspan(output: &mut dyn Write, text: &str, style: &Style) -> io::Result<()> {
+    let text = escape(&expand_tabs(text));
+    let mut attrs = String::new();
+
+    if let Some(fg) = &style.fg {
+        attrs.push_str(&format!(" fill=\"{}\"", fg));
+    }
+    if style.bold {
+        attrs.push_str(" font-weight=\"bold\"");
+    }
+    if style.italic {
+        attrs.push_str(" font-style=\"italic\"");
+    }
+
+    let underline = style.text_decoration.underline != UnderlineStyle::None;
+    match (underline, style.text_decoration.strikethrough) {
+        (true, true) => attrs.push_str(" text-decoration=\"underline line-through\""),
+        (true, false) => attrs.push_str(" text-decoration=\"underline\""),
+        (false, true) => attrs.push_str(" text-decoration=\"line-through\""),
+        (false, false) => {}
+    }
+
+    if attrs.is_empty() {
+        write!(output, "{}", text)
+    } else {
+        write!(output, "<tspan{}>{}</tspan>", attrs, text)
+    }
+}
+
+fn line_width(line: &[(String, Style)]) -> usize {
+    line.iter().map(|(text, _)| text_width(text)).sum()
+}
+
+/// Escape text for XML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            '\'' => buf.push_str("&apos;"),
+            _ => buf.push(c),
+        }
+    }
+
+    buf
+}
+
+/// Format a coordinate with at most two decimal places.
+fn num(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    format!("{}", rounded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    fn render(formatter: &Svg, code: &str) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_tokens_as_tspans() {
+        let formatter = SvgBuilder::new()
+            .lang(Language::Rust)
+            .theme(themes::get("dracula").ok())
+            .build()
+            .unwrap();
+
+        let svg = render(&formatter, "fn main() {}");
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("<rect width=\"132.8\" height=\"53\" fill=\"#282a36\"/>"));
+        assert!(svg.contains("<tspan fill=\"#8be9fd\">fn</tspan>"));
+        assert!(svg.contains("<tspan fill=\"#50fa7b\">main</tspan>"));
+    }
+
+    #[test]
+    fn test_one_text_element_per_line() {
+        let formatter = Svg::default();
+        let svg = render(&formatter, "line 1\nline 2\nline 3\n");
+
+        assert_eq!(svg.matches("<text ").count(), 3);
+        assert!(svg.contains(">line 3</text>"));
+    }
+
+    #[test]
+    fn test_range() {
+        let formatter = SvgBuilder::new()
+            .line_numbers(true)
+            .range(Some(SourceRange::Lines(9..=10)))
+            .build()
+            .unwrap();
+        let code: String = (1..=12).map(|i| format!("line {i}\n")).collect();
+        let svg = render(&formatter, &code);
+
+        assert_eq!(svg.matches(" xml:space=\"preserve\">").count(), 2);
+        assert!(svg.contains(">9</text>"));
+        assert!(svg.contains(">line 10</text>"));
+        assert!(!svg.contains(">line 11</text>"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        let formatter = Svg::default();
+        let svg = render(&formatter, "a < b && c > 'd'");
+
+        assert!(svg.contains("a &lt; b &amp;&amp; c &gt; &apos;d&apos;"));
+    }
+
+    #[test]
+    fn test_window_chrome_and_line_numbers() {
+        let formatter = SvgBuilder::new()
+            .theme(themes::get("github_light").ok())
+            .window_chrome(true)
+            .line_numbers(true)
+            .build()
+            .unwrap();
+
+        let svg = render(&formatter, "a\nb");
+
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert!(svg.contains("rx=\"8\""));
+        assert!(svg.contains("text-anchor=\"end\" opacity=\"0.5\">1</text>"));
+        assert!(svg.contains("text-anchor=\"end\" opacity=\"0.5\">2</text>"));
+    }
+
+    #[test]
+    fn test_line_number_start() {
+        let formatter = SvgBuilder::new()
+            .line_numbers(true)
+            .line_number_start(99)
+            .build()
+            .unwrap();
+
+        let svg = render(&formatter, "a\nb");
+
+        assert!(svg.contains("text-anchor=\"end\" opacity=\"0.5\">99</text>"));
+        assert!(svg.contains("text-anchor=\"end\" opacity=\"0.5\">100</text>"));
+    }
+
+    #[test]
+    fn test_font_options() {
+        let formatter = SvgBuilder::new()
+            .font_family("Fira Code")
+            .font_size(20.0)
+            .build()
+            .unwrap();
+
+        let svg = render(&formatter, "x");
+
+        assert!(svg.contains("<g font-family=\"Fira Code\" font-size=\"20\">"));
+    }
+}
@@ -13,12 +13,57 @@
 //! [0m[38;2;139;233;253mfn[0m [0m[38;2;80;250;123mmain[0m[0m[38;2;248;248;242m([0m[0m[38;2;248;248;242m)[0m [0m[38;2;248;248;242m{[0m [0m[38;2;189;147;249mprintln[0m[0m[38;2;80;250;123m![0m[0m[38;2;248;248;242m([0m[0m[38;2;241;250;140m"Hello"[0m[0m[38;2;248;248;242m)[0m[0m[38;2;248;248;242m;[0m [0m[38;2;248;248;242m}[0m
 //! ```
 //!
+//! # Color Depth
+//!
+//! Theme colors are emitted as 24-bit sequences by default. Terminals with fewer colors
+//! (tmux without truecolor, macOS Terminal.app, CI logs) can use the
+//! [`ColorDepth`](ansi::ColorDepth) option to map colors to the nearest entry of the
+//! 256 or 16 color palettes, or [`ColorDepth::Auto`](ansi::ColorDepth::Auto) to detect
+//! it from the environment.
+//!
+//! # Decorations
+//!
+//! Like `bat`, the output can be decorated with a line number gutter, highlighted lines
+//! and a file header, which turns the terminal into a simple code viewer:
+//!
+//! ```text
+//! ───────────────────────────
+//!     │ File: src/main.rs
+//! ───────────────────────────
+//!   1 │ fn main() {
+//!   2 │     println!("Hello");
+//!   3 │ }
+//! ───────────────────────────
+//! ```
+//!
+//! Line numbers are painted with the theme's line number style (see
+//! [`Theme::line_number_style`]) and
+//! highlighted lines with the background of the theme's `highlighted` style.
+//!
+//! # Background
+//!
+//! By default only the tokens are colored and the terminal background shows through,
+//! so a dark theme looks off in a light terminal. Enable `background` to paint the
+//! theme background behind every cell, padding each line to a fixed or detected width.
+//!
 //! See the [formatter](crate::formatter) module for more information and examples.
 
+use super::ansi::ColorDepth;
+use super::lines::{collect_lines_in_range, expand_tabs};
+use super::whitespace::{render_whitespace, Whitespace};
 use super::{ansi, Formatter};
+use crate::annotations::Annotation;
+use crate::brackets::{rainbow, BracketColors, RainbowBrackets};
+use crate::highlight::{SourceRange, Style};
+use crate::semantic::{semantic, SemanticColors};
 use crate::{languages::Language, themes::Theme};
 use derive_builder::Builder;
 use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Marker at the start of the continuation lines of `soft_wrap`.
+const WRAP_MARKER: &str = "↪ ";
 
 /// Terminal formatter for syntax highlighting with ANSI color codes.
 ///
@@ -43,11 +88,123 @@ use std::io::{self, Write};
 /// formatter.format(code, &mut output).unwrap();
 /// println!("{}", String::from_utf8(output).unwrap());
 /// ```
+///
+/// Limiting output to the 256 color palette:
+///
+/// ```rust
+/// use lumis::{TerminalBuilder, ansi::ColorDepth, languages::Language, themes, formatter::Formatter};
+///
+/// let formatter = TerminalBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(themes::get("dracula").ok())
+///     .color_depth(ColorDepth::Ansi256)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format("fn main() {}", &mut output).unwrap();
+/// assert!(String::from_utf8(output).unwrap().contains("\u{1b}[38;5;117mfn"));
+/// ```
+///
+/// Line numbers, highlighted lines and a file header:
+///
+/// ```rust
+/// use lumis::{TerminalBuilder, ansi::ColorDepth, languages::Language, themes, formatter::Formatter};
+///
+/// let code = "fn main() {\n    println!(\"Hello\");\n}\n";
+///
+/// let formatter = TerminalBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(themes::get("dracula").ok())
+///     .line_numbers(true)
+///     .highlight_lines(vec![2..=2])
+///     .header(Some("src/main.rs".to_string()))
+///     .color_depth(ColorDepth::NoColor)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(code, &mut output).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains("  │ File: src/main.rs\n"));
+/// assert!(output.contains(" 1 │ fn main() {\n"));
+/// ```
+///
+/// Painting the theme background across 80 columns:
+///
+/// ```rust
+/// use lumis::{TerminalBuilder, languages::Language, themes, formatter::Formatter};
+///
+/// let formatter = TerminalBuilder::new()
+///     .lang(Language::Rust)
+///     .theme(themes::get("dracula").ok())
+///     .background(true)
+///     .width(Some(80))
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format("fn main() {}", &mut output).unwrap();
+/// assert!(String::from_utf8(output).unwrap().contains("\u{1b}[48;2;40;42;54m"));
+/// ```
 #[derive(Builder, Clone, Debug)]
 #[builder(default)]
 pub struct Terminal {
     lang: Language,
     theme: Option<Theme>,
+    /// Colors supported by the terminal, defaults to [`ColorDepth::TrueColor`].
+    color_depth: ColorDepth,
+    /// Show a gutter with line numbers.
+    line_numbers: bool,
+    /// Number of the first line in the gutter, defaults to 1.
+    line_number_start: usize,
+    /// Separator between the gutter and the code, defaults to `"│"`.
+    #[builder(setter(into))]
+    gutter_separator: String,
+    /// Lines to highlight with the theme's `highlighted` style.
+    ///
+    /// Each range is inclusive on both ends. Line numbers are 1-based and relative to
+    /// the source, regardless of `line_number_start`.
+    highlight_lines: Vec<RangeInclusive<usize>>,
+    /// Header printed above the code, usually the file name.
+    header: Option<String>,
+    /// Paint the theme background behind every cell, padding each line to `width`.
+    background: bool,
+    /// Width in columns that lines are padded to when `background` is enabled, and wrapped
+    /// at when `soft_wrap` is enabled.
+    ///
+    /// Detected from the terminal when `None`, falling back to the longest line
+    /// when the output is not a terminal.
+    width: Option<usize>,
+    /// Only render this window of the source.
+    ///
+    /// The whole source is still parsed so the window keeps the context of the full file.
+    /// Line numbers and `highlight_lines` keep referring to the lines of the full file.
+    range: Option<SourceRange>,
+    /// Labels and notes attached to ranges of the source, see [`annotations`](crate::annotations).
+    ///
+    /// Annotated text is underlined with carets, followed by the label, below each line
+    /// it spans, painted with the theme's [`annotation_style()`](Theme::annotation_style).
+    annotations: Vec<Annotation>,
+    /// Color matching bracket pairs by nesting depth, see [`brackets`](crate::brackets).
+    rainbow_brackets: Option<RainbowBrackets>,
+    /// Wrap lines longer than `width` onto continuation lines, which start with a `↪`
+    /// marker under an empty gutter.
+    ///
+    /// Lines are broken on display width, so wide characters like CJK and emoji take two
+    /// columns. Nothing is wrapped when the width is neither set nor detected, and lines
+    /// with annotations are kept whole so their carets stay under the annotated text.
+    soft_wrap: bool,
+    /// Give each local variable its own stable color, see [`semantic`](crate::semantic).
+    semantic_colors: bool,
+    /// Expand tabs to the next multiple of this width, also used by `show_whitespace` and
+    /// `indent_guides`. Defaults to 4 when one of them is set.
+    tab_width: Option<usize>,
+    /// Render tabs as `→` and trailing spaces as `·`, styled with the theme's
+    /// [`whitespace_style()`](Theme::whitespace_style).
+    show_whitespace: bool,
+    /// Draw a `│` guide at each indentation level, every `tab_width` columns.
+    indent_guides: bool,
 }
 
 impl TerminalBuilder {
@@ -58,7 +215,258 @@ impl TerminalBuilder {
 
 impl Terminal {
     pub fn new(lang: Language, theme: Option<Theme>) -> Self {
-        Self { lang, theme }
+        Self {
+            lang,
+            theme,
+            ..Default::default()
+        }
+    }
+
+    fn is_decorated(&self) -> bool {
+        self.line_numbers
+            || !self.highlight_lines.is_empty()
+            || self.header.is_some()
+            || self.background
+            || !self.annotations.is_empty()
+            || self.soft_wrap
+    }
+
+    fn whitespace(&self) -> Option<Whitespace> {
+        Whitespace::new(self.tab_width, self.show_whitespace, self.indent_guides)
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlight_lines
+            .iter()
+            .any(|range| range.contains(&line))
+    }
+
+    fn format_decorated(
+        &self,
+        source: &str,
+        output: &mut dyn Write,
+        color_depth: ColorDepth,
+    ) -> io::Result<()> {
+        let window = self
+            .range
+            .as_ref()
+            .map_or(0..source.len(), |range| range.byte_range(source));
+        let first_line = self
+            .range
+            .as_ref()
+            .map_or(1, |range| *range.line_range(source).start());
+        let visible = &source[window.clone()];
+
+        let lines = collect_lines_in_range(
+            source,
+            self.lang,
+            self.theme.clone(),
+            window,
+            self.rainbow_brackets.as_ref(),
+            self.semantic_colors,
+            self.whitespace(),
+        )?;
+        let lines: Vec<Vec<(String, Style)>> = lines
+            .into_iter()
+            .map(|line| {
+                line.into_iter()
+                    .map(|(text, style)| (expand_tabs(&text), style))
+                    .collect()
+            })
+            .collect();
+
+        let theme_style =
+            |scope: &str| self.theme.as_ref().and_then(|theme| theme.get_style(scope));
+        let gutter_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .unwrap_or_default();
+        let header_style = Style {
+            bold: true,
+            ..Default::default()
+        };
+        let highlighted_bg = theme_style("highlighted").and_then(|style| style.bg.clone());
+        let normal = theme_style("normal").filter(|_| self.background);
+
+        // Paint a segment on top of the line background, which is the highlighted
+        // style for highlighted lines or the theme background when enabled.
+        let paint = |text: &str, style: &Style, line_bg: Option<&String>| {
+            let mut style = style.clone();
+            if let Some(bg) = line_bg.or(normal.and_then(|normal| normal.bg.as_ref())) {
+                style.bg.get_or_insert_with(|| bg.clone());
+            }
+            if let Some(fg) = normal.and_then(|normal| normal.fg.as_ref()) {
+                style.fg.get_or_insert_with(|| fg.clone());
+            }
+            ansi::wrap_with_ansi_with_depth(text, &style, color_depth)
+        };
+        let pad = |used: usize, width: usize, line_bg: Option<&String>| {
+            if used < width && (line_bg.is_some() || normal.is_some()) {
+                paint(&" ".repeat(width - used), &Style::default(), line_bg)
+            } else {
+                String::new()
+            }
+        };
+
+        let last_number = self.line_number_start + first_line - 1 + lines.len().saturating_sub(1);
+        let number_width = last_number.to_string().len();
+        let gutter_width = if self.line_numbers {
+            number_width + self.gutter_separator.width() + 3
+        } else {
+            0
+        };
+        let content_width = lines.iter().map(|line| line_width(line)).max().unwrap_or(0);
+        let wrap_width = self
+            .soft_wrap
+            .then(|| self.width.or_else(detect_width))
+            .flatten();
+        let width = if self.background {
+            self.width
+                .or_else(detect_width)
+                .unwrap_or(gutter_width + content_width)
+        } else {
+            gutter_width + content_width
+        };
+        let width = wrap_width.map_or(width, |wrap_width| width.min(wrap_width));
+        let continuation_gutter = if self.line_numbers {
+            format!(" {} {} ", " ".repeat(number_width), self.gutter_separator)
+        } else {
+            String::new()
+        };
+        let rule = paint(&"─".repeat(width.max(1)), &gutter_style, None);
+        let annotation_style = Style {
+            fg: self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.annotation_style())
+                .and_then(|style| style.fg),
+            bold: true,
+            ..Default::default()
+        };
+        let line_starts: Vec<usize> = if self.annotations.is_empty() {
+            Vec::new()
+        } else {
+            std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+                .collect()
+        };
+
+        if let Some(header) = &self.header {
+            writeln!(output, "{}", rule)?;
+            let mut used = 0;
+            if self.line_numbers {
+                let gutter = format!(
+                    "{} {} ",
+                    " ".repeat(number_width + 1),
+                    self.gutter_separator
+                );
+                write!(output, "{}", paint(&gutter, &gutter_style, None))?;
+                used += gutter_width;
+            }
+            let header = format!("File: {}", header);
+            used += header.width();
+            write!(output, "{}", paint(&header, &header_style, None))?;
+            writeln!(output, "{}", pad(used, width, None))?;
+            writeln!(output, "{}", rule)?;
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(output)?;
+            }
+
+            if self.line_numbers {
+                let gutter = format!(
+                    " {:>width$} {} ",
+                    self.line_number_start + first_line - 1 + i,
+                    self.gutter_separator,
+                    width = number_width
+                );
+                write!(output, "{}", paint(&gutter, &gutter_style, None))?;
+            }
+
+            let line_bg = highlighted_bg
+                .as_ref()
+                .filter(|_| self.is_highlighted(first_line + i));
+            let underlines = match line_starts.get(first_line + i - 1) {
+                Some(&line_start) => {
+                    let line_end = source[line_start..]
+                        .find('\n')
+                        .map_or(source.len(), |offset| line_start + offset);
+                    annotation_underlines(source, line_start..line_end, &self.annotations)
+                }
+                None => Vec::new(),
+            };
+            // Carets are aligned on the unwrapped line, so annotated lines are never wrapped.
+            let rows = match wrap_width.filter(|_| underlines.is_empty()) {
+                Some(wrap_width) => wrap_line(
+                    line,
+                    wrap_width.saturating_sub(gutter_width),
+                    WRAP_MARKER.width(),
+                ),
+                None => vec![line.clone()],
+            };
+
+            // Segments are painted on their own, so styles are closed and reopened around
+            // each break.
+            for (row_index, row) in rows.iter().enumerate() {
+                let mut used = gutter_width;
+                if row_index > 0 {
+                    writeln!(output)?;
+                    if self.line_numbers {
+                        write!(
+                            output,
+                            "{}",
+                            paint(&continuation_gutter, &gutter_style, None)
+                        )?;
+                    }
+                    write!(output, "{}", paint(WRAP_MARKER, &gutter_style, line_bg))?;
+                    used += WRAP_MARKER.width();
+                }
+
+                for (text, style) in row {
+                    write!(output, "{}", paint(text, style, line_bg))?;
+                }
+
+                write!(output, "{}", pad(used + line_width(row), width, line_bg))?;
+            }
+
+            for (column, text) in underlines {
+                writeln!(output)?;
+                if self.line_numbers {
+                    write!(
+                        output,
+                        "{}",
+                        paint(&continuation_gutter, &gutter_style, None)
+                    )?;
+                }
+                write!(
+                    output,
+                    "{}{}",
+                    paint(&" ".repeat(column), &Style::default(), None),
+                    paint(&text, &annotation_style, None)
+                )?;
+                write!(
+                    output,
+                    "{}",
+                    pad(gutter_width + column + text.width(), width, None)
+                )?;
+            }
+        }
+
+        if visible.ends_with('\n') {
+            writeln!(output)?;
+        }
+
+        if self.header.is_some() {
+            if !visible.ends_with('\n') {
+                writeln!(output)?;
+            }
+            write!(output, "{}", rule)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -67,20 +475,155 @@ impl Default for Terminal {
         Self {
             lang: Language::PlainText,
             theme: None,
+            color_depth: ColorDepth::default(),
+            line_numbers: false,
+            line_number_start: 1,
+            gutter_separator: "│".to_string(),
+            highlight_lines: Vec::new(),
+            header: None,
+            background: false,
+            width: None,
+            range: None,
+            annotations: Vec::new(),
+            rainbow_brackets: None,
+            soft_wrap: false,
+            semantic_colors: false,
+            tab_width: None,
+            show_whitespace: false,
+            indent_guides: false,
+        }
+    }
+}
+
+fn detect_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+/// Caret underlines drawn below the line at `line` for the annotations that touch it,
+/// as the column they start at and their text.
+///
+/// The label follows the carets on the last line of the annotation, with the note below.
+fn annotation_underlines(
+    source: &str,
+    line: Range<usize>,
+    annotations: &[Annotation],
+) -> Vec<(usize, String)> {
+    let width = |range: Range<usize>| expand_tabs(&source[range]).width();
+    let mut underlines = Vec::new();
+
+    for annotation in annotations {
+        let range = annotation.byte_range(source);
+        let touches_line = if range.is_empty() {
+            line.contains(&range.start) || range.start == line.end
+        } else {
+            range.start <= line.end && range.end > line.start
+        };
+        let is_last_line = range.is_empty() || range.end - 1 <= line.end;
+        let start = range.start.max(line.start);
+        let end = range.end.min(line.end);
+        if !touches_line || (start >= end && !is_last_line) {
+            continue;
+        }
+
+        let column = width(line.start..start);
+        let mut text = "^".repeat(width(start..end).max(1));
+        if is_last_line {
+            text.push(' ');
+            text.push_str(&annotation.label);
+        }
+        underlines.push((column, text));
+
+        if let Some(note) = annotation.note.as_ref().filter(|_| is_last_line) {
+            underlines.push((column, format!("note: {}", note)));
         }
     }
+
+    underlines.sort_by_key(|(column, _)| *column);
+    underlines
+}
+
+fn line_width(line: &[(String, Style)]) -> usize {
+    line.iter().map(|(text, _)| text.width()).sum()
+}
+
+/// Break a line into rows of at most `width` columns, the continuation rows leaving `indent`
+/// columns for the wrap marker.
+///
+/// Characters are measured by display width, so wide characters like CJK and emoji take two
+/// columns and are never split across rows.
+fn wrap_line(line: &[(String, Style)], width: usize, indent: usize) -> Vec<Vec<(String, Style)>> {
+    let mut rows: Vec<Vec<(String, Style)>> = vec![Vec::new()];
+    let mut capacity = width.max(1);
+    let mut used = 0;
+
+    for (text, style) in line {
+        let mut segment = String::new();
+        for c in text.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if used + char_width > capacity && used > 0 {
+                if !segment.is_empty() {
+                    rows.last_mut()
+                        .unwrap()
+                        .push((std::mem::take(&mut segment), style.clone()));
+                }
+                rows.push(Vec::new());
+                capacity = width.saturating_sub(indent).max(1);
+                used = 0;
+            }
+            segment.push(c);
+            used += char_width;
+        }
+        if !segment.is_empty() {
+            rows.last_mut().unwrap().push((segment, style.clone()));
+        }
+    }
+
+    rows
 }
 
 impl Formatter for Terminal {
     fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
-        crate::highlight::highlight_iter(
+        let color_depth = self.color_depth.resolve();
+
+        if self.is_decorated() {
+            return self.format_decorated(source, output, color_depth);
+        }
+
+        let brackets = self
+            .rainbow_brackets
+            .as_ref()
+            .map(|rainbow| BracketColors::new(source, self.lang, rainbow, self.theme.as_ref()));
+        let identifiers = self
+            .semantic_colors
+            .then(|| SemanticColors::new(source, self.lang, self.theme.as_ref()));
+        let whitespace_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.whitespace_style())
+            .unwrap_or_default();
+        let write_segment = |text: &str, _range, _scope: &str, _language: &str, style: &Style| {
+            let ansi_text = ansi::wrap_with_ansi_with_depth(text, style, color_depth);
+            write!(output, "{}", ansi_text)
+        };
+        let window = self
+            .range
+            .as_ref()
+            .map_or(0..source.len(), |range| range.byte_range(source));
+
+        crate::highlight::highlight_iter_with_language_in_range(
             source,
             self.lang,
             self.theme.clone(),
-            |text, _range, _scope, style| {
-                let ansi_text = ansi::wrap_with_ansi(text, style);
-                write!(output, "{}", ansi_text)
-            },
+            window,
+            rainbow(
+                source,
+                brackets.as_ref(),
+                semantic(
+                    source,
+                    identifiers.as_ref(),
+                    render_whitespace(source, self.whitespace(), whitespace_style, write_segment),
+                ),
+            ),
         )
         .map_err(io::Error::other)
     }
@@ -103,4 +646,316 @@ mod tests {
         assert!(result.contains(":rust"));
         // Without a theme, some tokens may not have styling, so just check the text is there
     }
+
+    #[test]
+    fn test_color_depth() {
+        let code = "fn main() {}";
+        let render = |color_depth| {
+            let formatter = TerminalBuilder::new()
+                .lang(Language::Rust)
+                .theme(crate::themes::get("dracula").ok())
+                .color_depth(color_depth)
+                .build()
+                .unwrap();
+            let mut buffer = Vec::new();
+            formatter.format(code, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        assert!(render(ColorDepth::TrueColor).contains("\u{1b}[38;2;139;233;253mfn"));
+        assert!(render(ColorDepth::Ansi256).contains("\u{1b}[38;5;117mfn"));
+        assert!(render(ColorDepth::Ansi16).contains("\u{1b}[37mfn"));
+        assert_eq!(render(ColorDepth::NoColor), code);
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        let code = "a\nb\n\tc\n";
+        let formatter = TerminalBuilder::new()
+            .line_numbers(true)
+            .line_number_start(9)
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "  9 │ a\n 10 │ b\n 11 │     c\n"
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        let code = "a\nb\nc\nd\n";
+        let render = |line_numbers| {
+            let formatter = TerminalBuilder::new()
+                .line_numbers(line_numbers)
+                .range(Some(SourceRange::Lines(2..=3)))
+                .color_depth(ColorDepth::NoColor)
+                .build()
+                .unwrap();
+            let mut buffer = Vec::new();
+            formatter.format(code, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        assert_eq!(render(false), "b\nc\n");
+        assert_eq!(render(true), " 2 │ b\n 3 │ c\n");
+    }
+
+    #[test]
+    fn test_gutter_separator_and_header() {
+        let formatter = TerminalBuilder::new()
+            .line_numbers(true)
+            .gutter_separator("|")
+            .header(Some("notes.txt".to_string()))
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("hello", &mut buffer).unwrap();
+        let rule = "─".repeat(10);
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format!("{rule}\n   | File: notes.txt\n{rule}\n 1 | hello\n{rule}")
+        );
+    }
+
+    #[test]
+    fn test_highlight_lines() {
+        let theme = crate::themes::get("dracula").unwrap();
+        let highlighted = theme.get_style("highlighted").unwrap().bg.clone().unwrap();
+        let (r, g, b) = ansi::hex_to_rgb(&highlighted).unwrap();
+        let bg = format!("\u{1b}[48;2;{};{};{}m", r, g, b);
+
+        let formatter = TerminalBuilder::new()
+            .theme(Some(theme))
+            .highlight_lines(vec![2..=2])
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("a\nlonger\nc", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = result.split('\n').collect();
+
+        assert!(!lines[0].contains(&bg));
+        assert!(lines[1].contains(&format!("{}longer", bg)));
+        assert!(!lines[2].contains(&bg));
+
+        let formatter = TerminalBuilder::new()
+            .theme(crate::themes::get("dracula").ok())
+            .highlight_lines(vec![1..=1])
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("a\nlonger", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        // Short highlighted lines are padded to the width of the longest line
+        assert!(result.contains(&format!("{}     \u{1b}[0m", bg)));
+    }
+
+    #[test]
+    fn test_background() {
+        let bg = "\u{1b}[48;2;40;42;54m";
+        let fg = "\u{1b}[38;2;248;248;242m";
+
+        let formatter = TerminalBuilder::new()
+            .theme(crate::themes::get("dracula").ok())
+            .background(true)
+            .width(Some(10))
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("ab\n\ncd\n", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            format!("\u{1b}[0m{fg}{bg}ab\u{1b}[0m\u{1b}[0m{fg}{bg}        \u{1b}[0m")
+        );
+        assert_eq!(lines[1], format!("\u{1b}[0m{fg}{bg}          \u{1b}[0m"));
+        assert!(lines.iter().all(|line| line.ends_with("\u{1b}[0m")));
+
+        // Lines longer than the width are not truncated
+        let mut buffer = Vec::new();
+        formatter.format("abcdefghijkl", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+        assert_eq!(result, format!("\u{1b}[0m{fg}{bg}abcdefghijkl\u{1b}[0m"));
+    }
+
+    #[test]
+    fn test_annotations() {
+        let code = "let x = 1;\nlet r = &x;\n";
+        let formatter = TerminalBuilder::new()
+            .line_numbers(true)
+            .annotations(vec![
+                Annotation::new(19..21, "borrow here").with_note("lives until the end"),
+                Annotation::new(4..14, "spans lines"),
+                Annotation::new(21..21, "end"),
+            ])
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format(code, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                " 1 │ let x = 1;\n",
+                "   │     ^^^^^^\n",
+                " 2 │ let r = &x;\n",
+                "   │ ^^^ spans lines\n",
+                "   │         ^^ borrow here\n",
+                "   │         note: lives until the end\n",
+                "   │           ^ end\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_rainbow_brackets() {
+        let formatter = TerminalBuilder::new()
+            .lang(Language::Rust)
+            .rainbow_brackets(Some(RainbowBrackets::with_palette(["#ff0000", "#00ff00"])))
+            .color_depth(ColorDepth::TrueColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("f(g(\"(\"))", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let red = "\x1b[38;2;255;0;0m";
+        let green = "\x1b[38;2;0;255;0m";
+        assert_eq!(result.matches(red).count(), 2);
+        assert_eq!(result.matches(green).count(), 2);
+        // The bracket inside the string isn't colored
+        assert!(result.contains("\"(\""));
+    }
+
+    #[test]
+    fn test_soft_wrap() {
+        let formatter = TerminalBuilder::new()
+            .lang(Language::PlainText)
+            .line_numbers(true)
+            .soft_wrap(true)
+            .width(Some(12))
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format("abcdefghijklmn\n漢字漢字漢字\n", &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                " 1 │ abcdefg\n",
+                "   │ ↪ hijkl\n",
+                "   │ ↪ mn\n",
+                " 2 │ 漢字漢\n",
+                "   │ ↪ 字漢\n",
+                "   │ ↪ 字\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_soft_wrap_keeps_annotated_lines_whole() {
+        let formatter = TerminalBuilder::new()
+            .lang(Language::PlainText)
+            .line_numbers(true)
+            .soft_wrap(true)
+            .width(Some(12))
+            .annotations(vec![Annotation::new(10..12, "here")])
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format("abcdefghijklmn\nopqrstuvwxyz\n", &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                " 1 │ abcdefghijklmn\n",
+                "   │           ^^ here\n",
+                " 2 │ opqrstu\n",
+                "   │ ↪ vwxyz\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_soft_wrap_reopens_styles() {
+        let theme = crate::themes::get("dracula").unwrap();
+        let formatter = TerminalBuilder::new()
+            .lang(Language::Rust)
+            .theme(Some(theme))
+            .soft_wrap(true)
+            .width(Some(8))
+            .color_depth(ColorDepth::TrueColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter.format("\"abcdefghij\"", &mut buffer).unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let string = "\x1b[38;2;241;250;140m";
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(&format!("{string}\"abcdefg\x1b[0m")));
+        assert!(lines[1].contains(&format!("{string}hij\"")));
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let formatter = TerminalBuilder::new()
+            .lang(Language::YAML)
+            .tab_width(Some(2))
+            .show_whitespace(true)
+            .indent_guides(true)
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format("a:\n  b:  \n    c:\t1\n", &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "a:\n│ b:··\n│ │ c:→ 1\n"
+        );
+    }
+
+    #[test]
+    fn test_semantic_colors() {
+        let theme = crate::themes::get("dracula").unwrap();
+        let formatter = TerminalBuilder::new()
+            .lang(Language::Rust)
+            .theme(Some(theme.clone()))
+            .semantic_colors(true)
+            .color_depth(ColorDepth::TrueColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format("fn f(count: i32) {\n    count;\n}\n", &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        let color = &theme.semantic_palette()[crate::semantic::color_index("count")];
+        let (r, g, b) = ansi::hex_to_rgb(color).unwrap();
+        let count = format!("\x1b[38;2;{r};{g};{b}mcount");
+        assert_eq!(result.matches(&count).count(), 2);
+    }
 }
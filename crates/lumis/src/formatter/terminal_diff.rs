@@ -0,0 +1,402 @@
+//! Terminal formatter for syntax-aware unified diffs.
+//!
+//! This module provides the [`TerminalDiff`] formatter that renders a unified diff with
+//! ANSI color codes, highlighting the code of each file with its own language (see the
+//! [diff](super::diff) module) on top of the `diff.plus` and `diff.minus` backgrounds
+//! of the theme. Added and removed lines are padded so the backgrounds form solid blocks.
+//!
+//! [`TerminalDiff::format_side_by_side()`] renders two versions of a source in two
+//! columns instead, with the words that changed inside modified lines emphasized.
+//!
+//! See the [formatter](crate::formatter) module for more information and examples.
+
+use super::ansi::{self, ColorDepth};
+use super::diff::{
+    change_style, highlight_side_by_side, highlight_unified, line_style, DiffLine, SideLine,
+};
+use super::lines::{expand_tabs, text_width};
+use super::Formatter;
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::themes::Theme;
+use derive_builder::Builder;
+use std::io::{self, Write};
+
+/// Terminal formatter for unified diffs with ANSI color codes.
+///
+/// Use [`TerminalDiffBuilder`] to create instances.
+///
+/// # Example
+///
+/// ```rust
+/// use lumis::{TerminalDiffBuilder, ansi::ColorDepth, themes, formatter::Formatter};
+///
+/// let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -1 +1 @@\n-fn a() {}\n+fn b() {}\n";
+///
+/// let formatter = TerminalDiffBuilder::new()
+///     .theme(themes::get("dracula").ok())
+///     .line_numbers(true)
+///     .color_depth(ColorDepth::NoColor)
+///     .build()
+///     .unwrap();
+///
+/// let mut output = Vec::new();
+/// formatter.format(diff, &mut output).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+///
+/// assert!(output.contains("1   │ -fn a() {}\n"));
+/// assert!(output.contains("  1 │ +fn b() {}\n"));
+/// ```
+#[derive(Builder, Clone, Debug, Default)]
+#[builder(default)]
+pub struct TerminalDiff {
+    theme: Option<Theme>,
+    /// Colors supported by the terminal, defaults to [`ColorDepth::TrueColor`].
+    color_depth: ColorDepth,
+    /// Show a gutter with the old and new line numbers.
+    line_numbers: bool,
+}
+
+impl TerminalDiffBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TerminalDiff {
+    pub fn new(theme: Option<Theme>) -> Self {
+        Self {
+            theme,
+            ..Default::default()
+        }
+    }
+
+    /// Render a side-by-side comparison of two versions of a source.
+    ///
+    /// Both sources are highlighted with `language`. The old side is on the left and the
+    /// new side on the right, each with its own line numbers and a `-` or `+` marker on
+    /// changed lines, see [`highlight_side_by_side()`](super::diff::highlight_side_by_side).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use lumis::{TerminalDiffBuilder, ansi::ColorDepth, languages::Language};
+    ///
+    /// let formatter = TerminalDiffBuilder::new()
+    ///     .color_depth(ColorDepth::NoColor)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut output = Vec::new();
+    /// formatter
+    ///     .format_side_by_side("a = 1\nb = 2\n", "a = 1\nb = 3\n", Language::Python, &mut output)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(output).unwrap(),
+    ///     "1   a = 1 │ 1   a = 1\n2 - b = 2 │ 2 + b = 3\n"
+    /// );
+    /// ```
+    pub fn format_side_by_side(
+        &self,
+        old: &str,
+        new: &str,
+        language: Language,
+        output: &mut dyn Write,
+    ) -> io::Result<()> {
+        let color_depth = self.color_depth.resolve();
+        let rows = highlight_side_by_side(old, new, language, self.theme.clone())?;
+
+        let sides = || {
+            rows.iter()
+                .flat_map(|row| [row.old.as_ref(), row.new.as_ref()])
+        };
+        let number_width = sides()
+            .flatten()
+            .map(|line| line.line)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let old_width = rows
+            .iter()
+            .filter_map(|row| row.old.as_ref())
+            .map(side_width)
+            .max()
+            .unwrap_or(0);
+        let new_width = rows
+            .iter()
+            .filter_map(|row| row.new.as_ref())
+            .map(side_width)
+            .max()
+            .unwrap_or(0);
+        let gutter_style = self.gutter_style();
+
+        for row in &rows {
+            self.write_side(
+                output,
+                row.old.as_ref(),
+                number_width,
+                old_width,
+                color_depth,
+            )?;
+            write!(
+                output,
+                "{}",
+                ansi::wrap_with_ansi_with_depth(" │ ", &gutter_style, color_depth)
+            )?;
+            // Only pad the right column when there's a background to fill.
+            let new_width = if color_depth == ColorDepth::NoColor {
+                0
+            } else {
+                new_width
+            };
+            self.write_side(
+                output,
+                row.new.as_ref(),
+                number_width,
+                new_width,
+                color_depth,
+            )?;
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+
+    fn gutter_style(&self) -> Style {
+        self.theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .unwrap_or_default()
+    }
+
+    fn write_side(
+        &self,
+        output: &mut dyn Write,
+        line: Option<&SideLine>,
+        number_width: usize,
+        width: usize,
+        color_depth: ColorDepth,
+    ) -> io::Result<()> {
+        let Some(line) = line else {
+            return write!(output, "{}", " ".repeat(number_width + 3 + width));
+        };
+
+        let gutter = format!("{:>width$} ", line.line, width = number_width);
+        write!(
+            output,
+            "{}",
+            ansi::wrap_with_ansi_with_depth(&gutter, &self.gutter_style(), color_depth)
+        )?;
+
+        let line_style = line_style(self.theme.as_ref(), line.kind);
+        let line_bg = line_style.as_ref().and_then(|style| style.bg.clone());
+        let change_bg = change_style(self.theme.as_ref(), line.kind).and_then(|style| style.bg);
+        let paint = |text: &str, style: &Style, bg: Option<&String>| {
+            let mut style = style.clone();
+            if let Some(bg) = bg {
+                style.bg = Some(bg.clone());
+            }
+            ansi::wrap_with_ansi_with_depth(text, &style, color_depth)
+        };
+
+        let marker_style = Style {
+            fg: line_style.as_ref().and_then(|style| style.fg.clone()),
+            ..Default::default()
+        };
+        write!(
+            output,
+            "{}",
+            paint(
+                &format!("{} ", line.kind.marker()),
+                &marker_style,
+                line_bg.as_ref()
+            )
+        )?;
+
+        for (text, style, changed) in line.split_changes() {
+            let bg = if changed {
+                change_bg.as_ref().or(line_bg.as_ref())
+            } else {
+                line_bg.as_ref()
+            };
+            write!(output, "{}", paint(&expand_tabs(text), style, bg))?;
+        }
+
+        let used = side_width(line);
+        if used < width {
+            write!(
+                output,
+                "{}",
+                paint(
+                    &" ".repeat(width - used),
+                    &Style::default(),
+                    line_bg.as_ref()
+                )
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn side_width(line: &SideLine) -> usize {
+    line.segments.iter().map(|(text, _)| text_width(text)).sum()
+}
+
+fn line_width(line: &DiffLine) -> usize {
+    line.kind.marker().len()
+        + line
+            .segments
+            .iter()
+            .map(|(text, _)| text_width(text))
+            .sum::<usize>()
+}
+
+impl Formatter for TerminalDiff {
+    fn format(&self, source: &str, output: &mut dyn Write) -> io::Result<()> {
+        let color_depth = self.color_depth.resolve();
+        let lines = highlight_unified(source, self.theme.clone())?;
+
+        let number_width = lines
+            .iter()
+            .flat_map(|line| [line.old_line, line.new_line])
+            .flatten()
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let content_width = lines.iter().map(line_width).max().unwrap_or(0);
+        let gutter_style = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.line_number_style())
+            .unwrap_or_default();
+
+        for line in &lines {
+            if self.line_numbers {
+                let number = |number: Option<usize>| {
+                    number.map_or(" ".repeat(number_width), |number| {
+                        format!("{:>width$}", number, width = number_width)
+                    })
+                };
+                let gutter = format!("{} {} │ ", number(line.old_line), number(line.new_line));
+                write!(
+                    output,
+                    "{}",
+                    ansi::wrap_with_ansi_with_depth(&gutter, &gutter_style, color_depth)
+                )?;
+            }
+
+            let line_style = line_style(self.theme.as_ref(), line.kind);
+            let line_bg = line_style.as_ref().and_then(|style| style.bg.as_ref());
+            let paint = |text: &str, style: &Style| {
+                let mut style = style.clone();
+                if let Some(bg) = line_bg {
+                    style.bg = Some(bg.clone());
+                }
+                ansi::wrap_with_ansi_with_depth(text, &style, color_depth)
+            };
+
+            let marker_style = Style {
+                fg: line_style.as_ref().and_then(|style| style.fg.clone()),
+                ..Default::default()
+            };
+            write!(output, "{}", paint(line.kind.marker(), &marker_style))?;
+
+            for (text, style) in &line.segments {
+                write!(output, "{}", paint(&expand_tabs(text), style))?;
+            }
+
+            let width = line_width(line);
+            if line_bg.is_some() && color_depth != ColorDepth::NoColor && width < content_width {
+                write!(
+                    output,
+                    "{}",
+                    paint(&" ".repeat(content_width - width), &Style::default())
+                )?;
+            }
+
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes;
+
+    const DIFF: &str = "--- a/app.rb\n+++ b/app.rb\n@@ -1,2 +1,2 @@\n x = 1\n-puts x\n+puts y\n";
+
+    fn render(formatter: &TerminalDiff) -> String {
+        let mut buffer = Vec::new();
+        formatter.format(DIFF, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_no_color() {
+        let formatter = TerminalDiffBuilder::new()
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+
+        assert_eq!(render(&formatter), DIFF);
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let formatter = TerminalDiffBuilder::new()
+            .color_depth(ColorDepth::NoColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format_side_by_side("a\nbb\n", "a\nb\nc\n", Language::PlainText, &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "1   a  │ 1   a\n2 - bb │ 2 + b\n       │ 3 + c\n"
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_emphasis() {
+        let formatter = TerminalDiffBuilder::new()
+            .theme(themes::get("dracula").ok())
+            .color_depth(ColorDepth::TrueColor)
+            .build()
+            .unwrap();
+        let mut buffer = Vec::new();
+        formatter
+            .format_side_by_side("x = 1\n", "x = 2\n", Language::Python, &mut buffer)
+            .unwrap();
+        let result = String::from_utf8(buffer).unwrap();
+
+        // diff.plus emphasis background #607c6e behind the changed number
+        assert!(result.contains("\u{1b}[48;2;96;124;110m2"));
+    }
+
+    #[test]
+    fn test_backgrounds() {
+        let formatter = TerminalDiffBuilder::new()
+            .theme(themes::get("github_light").ok())
+            .color_depth(ColorDepth::TrueColor)
+            .build()
+            .unwrap();
+        let result = render(&formatter);
+
+        // diff.minus background #e4b7be behind the marker and the code
+        assert!(result.contains("\u{1b}[38;2;209;36;47m\u{1b}[48;2;228;183;190m-"));
+        // diff.plus background #b8d0bf
+        assert!(result.contains("\u{1b}[48;2;184;208;191m"));
+        // padding to the widest line
+        let added = result.lines().nth(5).unwrap();
+        assert!(added.ends_with("\u{1b}[48;2;184;208;191m        \u{1b}[0m"));
+    }
+}
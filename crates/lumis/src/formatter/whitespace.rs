@@ -0,0 +1,254 @@
+//! Whitespace visualization, tab expansion and indent guides.
+//!
+//! Shared by the [`Terminal`](super::Terminal) and HTML formatters through their
+//! `show_whitespace`, `tab_width` and `indent_guides` options. When any of them is set, tabs
+//! are expanded to the next tab stop, so the glyphs and guides line up with the code.
+
+use crate::highlight::Style;
+use std::ops::Range;
+
+/// Tab width used when only `show_whitespace` or `indent_guides` is set.
+pub(crate) const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Glyph drawn in the first column of a tab.
+pub(crate) const TAB_GLYPH: char = '→';
+
+/// Glyph drawn in place of a trailing space.
+pub(crate) const SPACE_GLYPH: char = '·';
+
+/// Glyph of the vertical indent guides.
+pub(crate) const GUIDE_GLYPH: char = '│';
+
+/// Kind of a piece of rendered text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    /// Text of the source, with tabs expanded.
+    Text,
+    /// Visible tabs and trailing spaces.
+    Whitespace,
+    /// Vertical indent guides.
+    Guide,
+}
+
+/// How whitespace is rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Whitespace {
+    tab_width: usize,
+    visible: bool,
+    indent_guides: bool,
+}
+
+impl Whitespace {
+    /// Returns `None` when none of the options is set, leaving the text untouched.
+    pub(crate) fn new(
+        tab_width: Option<usize>,
+        visible: bool,
+        indent_guides: bool,
+    ) -> Option<Self> {
+        if tab_width.is_none() && !visible && !indent_guides {
+            return None;
+        }
+
+        Some(Self {
+            tab_width: tab_width.unwrap_or(DEFAULT_TAB_WIDTH).max(1),
+            visible,
+            indent_guides,
+        })
+    }
+
+    /// Render the text at `range` of `source`, split into pieces of the same kind with the
+    /// range of the source each one covers.
+    ///
+    /// Columns are counted from the start of the line, so `range` doesn't have to start
+    /// at a line boundary.
+    pub(crate) fn render(
+        &self,
+        source: &str,
+        range: Range<usize>,
+    ) -> Vec<(String, Kind, Range<usize>)> {
+        let mut pieces: Vec<(String, Kind, Range<usize>)> = Vec::new();
+        let mut push = |text: &str, kind: Kind, range: Range<usize>| match pieces.last_mut() {
+            Some((last, last_kind, last_range))
+                if *last_kind == kind && last_range.end == range.start =>
+            {
+                last.push_str(text);
+                last_range.end = range.end;
+            }
+            _ => pieces.push((text.to_string(), kind, range)),
+        };
+
+        let mut line = Line::at(source, range.start);
+        let mut column = self.width(&source[line.start..range.start], 0);
+
+        for (offset, c) in source[range.clone()].char_indices() {
+            let offset = range.start + offset;
+            let char_range = offset..offset + c.len_utf8();
+            let in_indent = offset < line.indent_end;
+            let is_guide = |column: usize| {
+                self.indent_guides && in_indent && column.is_multiple_of(self.tab_width)
+            };
+
+            match c {
+                '\n' => {
+                    push("\n", Kind::Text, char_range);
+                    line = Line::at(source, offset + 1);
+                    column = 0;
+                }
+                '\t' => {
+                    let width = self.tab_width - column % self.tab_width;
+                    let padding = " ".repeat(width - 1);
+                    if self.visible {
+                        push(
+                            &format!("{TAB_GLYPH}{padding}"),
+                            Kind::Whitespace,
+                            char_range,
+                        );
+                    } else if is_guide(column) {
+                        push(&format!("{GUIDE_GLYPH}{padding}"), Kind::Guide, char_range);
+                    } else {
+                        push(&format!(" {padding}"), Kind::Text, char_range);
+                    }
+                    column += width;
+                }
+                ' ' if self.visible && offset >= line.trailing_start => {
+                    push(&SPACE_GLYPH.to_string(), Kind::Whitespace, char_range);
+                    column += 1;
+                }
+                ' ' if is_guide(column) => {
+                    push(&GUIDE_GLYPH.to_string(), Kind::Guide, char_range);
+                    column += 1;
+                }
+                c => {
+                    push(&source[char_range.clone()], Kind::Text, char_range);
+                    if c != '\r' {
+                        column += 1;
+                    }
+                }
+            }
+        }
+
+        pieces
+    }
+
+    /// Width in columns of `text` starting at `column`, with tabs expanded.
+    fn width(&self, text: &str, column: usize) -> usize {
+        text.chars().fold(column, |column, c| match c {
+            '\t' => column + self.tab_width - column % self.tab_width,
+            _ => column + 1,
+        }) - column
+    }
+}
+
+/// Offsets of the line containing an offset of the source.
+struct Line {
+    start: usize,
+    /// End of the leading whitespace. Lines with only whitespace have no indentation.
+    indent_end: usize,
+    /// Start of the trailing whitespace.
+    trailing_start: usize,
+}
+
+impl Line {
+    fn at(source: &str, offset: usize) -> Self {
+        let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[start..end].trim_end_matches('\r');
+        let content = text.trim_start_matches([' ', '\t']);
+        let trailing_start = start + text.trim_end_matches([' ', '\t']).len();
+        let indent_end = if content.trim_end_matches([' ', '\t']).is_empty() {
+            start
+        } else {
+            start + text.len() - content.len()
+        };
+
+        Self {
+            start,
+            indent_end,
+            trailing_start,
+        }
+    }
+}
+
+/// Wrap a callback of [`highlight_iter()`](crate::highlight::highlight_iter) so the text
+/// passed to it is rendered with `whitespace`, with visible whitespace and indent guides
+/// painted with `whitespace_style`.
+///
+/// The range passed with each piece is the range of the source it covers.
+pub(crate) fn render_whitespace<'a, F, E>(
+    source: &'a str,
+    whitespace: Option<Whitespace>,
+    whitespace_style: Style,
+    mut callback: F,
+) -> impl FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a
+where
+    F: FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a,
+{
+    move |text, range, scope, language, style| {
+        let Some(whitespace) = whitespace else {
+            return callback(text, range, scope, language, style);
+        };
+
+        for (text, kind, piece) in whitespace.render(source, range) {
+            match kind {
+                Kind::Text => callback(&text, piece, scope, language, style)?,
+                Kind::Whitespace | Kind::Guide => {
+                    let style = Style {
+                        fg: whitespace_style.fg.clone().or(style.fg.clone()),
+                        bg: style.bg.clone(),
+                        ..Default::default()
+                    };
+                    callback(&text, piece, scope, language, &style)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(whitespace: Whitespace, source: &str) -> String {
+        whitespace
+            .render(source, 0..source.len())
+            .into_iter()
+            .map(|(text, _, _)| text)
+            .collect()
+    }
+
+    #[test]
+    fn test_tabs_and_trailing_spaces() {
+        let whitespace = Whitespace::new(Some(4), true, false).unwrap();
+
+        assert_eq!(render(whitespace, "a:\tb  \n\tc"), "a:→ b··\n→   c");
+    }
+
+    #[test]
+    fn test_indent_guides() {
+        let whitespace = Whitespace::new(Some(2), false, true).unwrap();
+
+        assert_eq!(
+            render(whitespace, "a:\n  b:\n    c: 1\n\n"),
+            "a:\n│ b:\n│ │ c: 1\n\n"
+        );
+    }
+
+    #[test]
+    fn test_range_inside_line() {
+        let whitespace = Whitespace::new(Some(4), true, false).unwrap();
+        let source = "ab\tc ";
+
+        assert_eq!(
+            whitespace.render(source, 2..5),
+            vec![
+                ("→ ".to_string(), Kind::Whitespace, 2..3),
+                ("c".to_string(), Kind::Text, 3..4),
+                ("·".to_string(), Kind::Whitespace, 4..5),
+            ]
+        );
+    }
+}
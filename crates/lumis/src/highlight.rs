@@ -60,6 +60,27 @@
 //! }
 //! ```
 //!
+//! ## Highlighting a range of lines
+//!
+//! ```rust
+//! use lumis::highlight::{highlight_iter_in_range, SourceRange};
+//! use lumis::languages::Language;
+//!
+//! // The second line starts inside a block comment that only parses as such
+//! // when the whole source is highlighted.
+//! let code = "/* a\nb */\nlet x = 1;\n";
+//!
+//! let mut segments = Vec::new();
+//!
+//! highlight_iter_in_range(code, Language::Rust, None, &SourceRange::Lines(2..=2), |text, range, scope, _style| {
+//!     assert_eq!(&code[range], text);
+//!     segments.push((text.to_string(), scope));
+//!     Ok::<_, std::io::Error>(())
+//! }).unwrap();
+//!
+//! assert_eq!(segments[0], ("b */".to_string(), "comment"));
+//! ```
+//!
 //! ## Using the streaming API with a callback
 //!
 //! ```rust
@@ -79,10 +100,11 @@
 
 use crate::constants::HIGHLIGHT_NAMES;
 use crate::languages::Language;
+use crate::semantic::{semantic, SemanticColors};
 use crate::themes::Theme;
 use crate::vendor::tree_sitter_highlight::{HighlightEvent, Highlighter as TSHighlighter};
 use smol_str::format_smolstr;
-use std::ops::Range;
+use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -123,6 +145,89 @@ pub enum HighlightError {
     EventProcessing(String),
 }
 
+/// A window of the source to render.
+///
+/// The whole source is always parsed, so tokens keep the context of the full file
+/// (e.g. a window starting inside a block comment or string), but only the tokens
+/// inside the window are emitted. Byte ranges and line numbers reported by the
+/// highlighter remain relative to the full source.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::highlight::SourceRange;
+///
+/// let source = "one\ntwo\nthree\n";
+///
+/// assert_eq!(SourceRange::Lines(2..=3).byte_range(source), 4..14);
+/// assert_eq!(SourceRange::Bytes(5..9).line_range(source), 2..=3);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SourceRange {
+    /// Line range, 1-based and inclusive on both ends.
+    Lines(RangeInclusive<usize>),
+    /// Byte range into the source.
+    Bytes(Range<usize>),
+}
+
+impl SourceRange {
+    /// Resolve the window into a byte range of `source`.
+    ///
+    /// Line ranges include the trailing newline of the last line. Ranges are clamped
+    /// to the source length and byte offsets are moved back to the nearest char boundary.
+    pub fn byte_range(&self, source: &str) -> Range<usize> {
+        match self {
+            SourceRange::Lines(lines) => {
+                let first = (*lines.start()).max(1);
+                let last = *lines.end();
+                let mut start = source.len();
+                let mut end = source.len();
+
+                let line_starts = std::iter::once(0)
+                    .chain(source.match_indices('\n').map(|(offset, _)| offset + 1));
+
+                for (i, offset) in line_starts.enumerate() {
+                    let line = i + 1;
+                    if line == first {
+                        start = offset;
+                    }
+                    if line == last.saturating_add(1) {
+                        end = offset;
+                        break;
+                    }
+                }
+
+                start.min(end)..end
+            }
+            SourceRange::Bytes(bytes) => {
+                let end = floor_char_boundary(source, bytes.end);
+                let start = floor_char_boundary(source, bytes.start).min(end);
+                start..end
+            }
+        }
+    }
+
+    /// Resolve the window into the 1-based, inclusive range of lines it touches.
+    pub fn line_range(&self, source: &str) -> RangeInclusive<usize> {
+        let bytes = self.byte_range(source);
+        let first = source[..bytes.start].matches('\n').count() + 1;
+        let last = if bytes.end > bytes.start {
+            source[..bytes.end - 1].matches('\n').count() + 1
+        } else {
+            first
+        };
+        first..=last
+    }
+}
+
+pub(crate) fn floor_char_boundary(source: &str, index: usize) -> usize {
+    let mut index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
 /// High-level stateful highlighter for syntax highlighting.
 ///
 /// This is the primary API for most users. It manages tree-sitter state internally
@@ -144,6 +249,7 @@ pub enum HighlightError {
 pub struct Highlighter {
     language: Language,
     theme: Option<Theme>,
+    semantic_colors: bool,
 }
 
 impl Highlighter {
@@ -169,7 +275,42 @@ impl Highlighter {
     /// let highlighter = Highlighter::new(Language::JavaScript, None);
     /// ```
     pub fn new(language: Language, theme: Option<Theme>) -> Self {
-        Self { language, theme }
+        Self {
+            language,
+            theme,
+            semantic_colors: false,
+        }
+    }
+
+    /// Give each local variable its own stable color, see [`semantic`](crate::semantic).
+    ///
+    /// The definition and the references of a local binding are styled with a color of the
+    /// theme's [`semantic_palette()`](Theme::semantic_palette) picked by its name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::highlight::Highlighter;
+    /// use lumis::languages::Language;
+    /// use lumis::themes;
+    ///
+    /// let code = "fn main() {\n    let x = 1;\n    x;\n}\n";
+    /// let theme = themes::get("dracula").unwrap();
+    ///
+    /// let highlighter = Highlighter::new(Language::Rust, Some(theme)).semantic_colors(true);
+    /// let segments = highlighter.highlight(code).unwrap();
+    ///
+    /// let colors: Vec<_> = segments
+    ///     .iter()
+    ///     .filter(|(_, text)| *text == "x")
+    ///     .map(|(style, _)| style.fg.clone())
+    ///     .collect();
+    /// assert_eq!(colors.len(), 2);
+    /// assert_eq!(colors[0], colors[1]);
+    /// ```
+    pub fn semantic_colors(mut self, enabled: bool) -> Self {
+        self.semantic_colors = enabled;
+        self
     }
 
     /// Highlight the entire source code and return styled segments.
@@ -219,6 +360,9 @@ impl Highlighter {
             )
             .map_err(|e| HighlightError::HighlighterInit(format!("{:?}", e)))?;
 
+        let semantic = self
+            .semantic_colors
+            .then(|| SemanticColors::new(source, self.language, self.theme.as_ref()));
         let mut result = Vec::new();
         let mut style_stack: Vec<Arc<Style>> = vec![Arc::new(Style::default())];
 
@@ -246,10 +390,20 @@ impl Highlighter {
                     style_stack.push(new_style);
                 }
                 HighlightEvent::Source { start, end } => {
-                    let text = &source[start..end];
-                    if !text.is_empty() {
-                        let current_style = style_stack.last().map(Arc::clone).unwrap_or_default();
-                        result.push((current_style, text));
+                    let current_style = style_stack.last().map(Arc::clone).unwrap_or_default();
+                    let Some(ref semantic) = semantic else {
+                        if start < end {
+                            result.push((current_style, &source[start..end]));
+                        }
+                        continue;
+                    };
+
+                    for (piece, color) in semantic.split(start..end) {
+                        let style = match color {
+                            Some(color) => Arc::new(semantic.style(&current_style, color)),
+                            None => Arc::clone(&current_style),
+                        };
+                        result.push((style, &source[piece]));
                     }
                 }
                 HighlightEvent::HighlightEnd => {
@@ -314,15 +468,147 @@ where
     F: FnMut(&str, Range<usize>, &'static str, &Style) -> Result<(), E>,
     E: std::error::Error + Send + Sync + 'static,
 {
+    highlight_iter_with_language(
+        source,
+        language,
+        theme,
+        |text, range, scope, _language, style| on_event_source(text, range, scope, style),
+    )
+}
+
+/// Streaming syntax highlighting of a window of the source.
+///
+/// Same as [`highlight_iter()`] but only segments inside `range` are passed to the callback,
+/// clipped to the window. The whole source is still parsed so the window keeps the context
+/// of the full file, and the byte ranges received by the callback are relative to `source`.
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::highlight::{highlight_iter_in_range, SourceRange};
+/// use lumis::languages::Language;
+///
+/// let code = "let a = \"one\ntwo\";\nlet b = 2;\n";
+/// let mut segments = Vec::new();
+///
+/// highlight_iter_in_range(code, Language::Rust, None, &SourceRange::Lines(2..=2), |text, range, scope, _style| {
+///     segments.push((text.to_string(), range, scope));
+///     Ok::<_, std::io::Error>(())
+/// }).unwrap();
+///
+/// // The second line is still highlighted as the end of a string
+/// assert_eq!(segments[0], ("two\"".to_string(), 13..17, "string"));
+/// ```
+pub fn highlight_iter_in_range<F, E>(
+    source: &str,
+    language: Language,
+    theme: Option<Theme>,
+    range: &SourceRange,
+    mut on_event_source: F,
+) -> Result<(), HighlightError>
+where
+    F: FnMut(&str, Range<usize>, &'static str, &Style) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    highlight_iter_with_language_in_range(
+        source,
+        language,
+        theme,
+        range.byte_range(source),
+        |text, range, scope, _language, style| on_event_source(text, range, scope, style),
+    )
+}
+
+/// Streaming syntax highlighting with a stable color for each local variable.
+///
+/// Same as [`highlight_iter()`] but the definition and the references of each local binding
+/// are passed as their own segments, styled with a color of the theme's
+/// [`semantic_palette()`](Theme::semantic_palette). See [`semantic`](crate::semantic).
+///
+/// # Examples
+///
+/// ```rust
+/// use lumis::highlight::highlight_iter_with_semantic_colors;
+/// use lumis::languages::Language;
+/// use lumis::themes;
+///
+/// let code = "fn main() {\n    let count = 1;\n    count;\n}\n";
+/// let theme = themes::get("github_light").unwrap();
+/// let mut colors = Vec::new();
+///
+/// highlight_iter_with_semantic_colors(code, Language::Rust, Some(theme.clone()), |text, _range, _scope, style| {
+///     if text == "count" {
+///         colors.push(style.fg.clone().unwrap());
+///     }
+///     Ok::<_, std::io::Error>(())
+/// }).unwrap();
+///
+/// assert_eq!(colors.len(), 2);
+/// assert!(theme.semantic_palette().contains(&colors[0]));
+/// assert_eq!(colors[0], colors[1]);
+/// ```
+pub fn highlight_iter_with_semantic_colors<F, E>(
+    source: &str,
+    language: Language,
+    theme: Option<Theme>,
+    mut on_event_source: F,
+) -> Result<(), HighlightError>
+where
+    F: FnMut(&str, Range<usize>, &'static str, &Style) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let colors = SemanticColors::new(source, language, theme.as_ref());
+
+    highlight_iter_with_language(
+        source,
+        language,
+        theme,
+        semantic(
+            source,
+            Some(&colors),
+            |text, range, scope, _language, style| on_event_source(text, range, scope, style),
+        ),
+    )
+}
+
+/// Same as [`highlight_iter()`] but the callback also receives the name of the language
+/// each segment belongs to, which differs from `language` inside injections.
+pub(crate) fn highlight_iter_with_language<F, E>(
+    source: &str,
+    language: Language,
+    theme: Option<Theme>,
+    on_event_source: F,
+) -> Result<(), HighlightError>
+where
+    F: FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    highlight_iter_with_language_in_range(source, language, theme, 0..source.len(), on_event_source)
+}
+
+/// Same as [`highlight_iter_with_language()`] but only emits the segments inside `window`.
+pub(crate) fn highlight_iter_with_language_in_range<F, E>(
+    source: &str,
+    language: Language,
+    theme: Option<Theme>,
+    window: Range<usize>,
+    mut on_event_source: F,
+) -> Result<(), HighlightError>
+where
+    F: FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let config = language.config();
     let mut ts_highlighter = TSHighlighter::new();
     let events = ts_highlighter
-        .highlight(language.config(), source.as_bytes(), None, |injected| {
+        .highlight(config, source.as_bytes(), None, |injected| {
             Some(Language::guess(Some(injected), "").config())
         })
         .map_err(|e| HighlightError::HighlighterInit(format!("{:?}", e)))?;
 
     let mut style_stack: Vec<Style> = vec![Style::default()];
     let mut scope_stack: Vec<&'static str> = vec![""];
+    let mut language_stack: Vec<String> = vec![config.language_name.clone()];
 
     for event in events {
         let event = event.map_err(|e| HighlightError::EventProcessing(format!("{:?}", e)))?;
@@ -345,15 +631,34 @@ where
                 };
                 style_stack.push(new_style);
                 scope_stack.push(scope);
+                language_stack.push(lang);
             }
             HighlightEvent::Source { start, end } => {
+                if start >= window.end {
+                    break;
+                }
+                let start = start.max(window.start);
+                let end = end.min(window.end);
+                if start >= end {
+                    continue;
+                }
                 let text = &source[start..end];
                 if !text.is_empty() {
                     let default_style = Style::default();
                     let current_style = style_stack.last().unwrap_or(&default_style);
                     let current_scope = scope_stack.last().copied().unwrap_or("");
-                    on_event_source(text, start..end, current_scope, current_style)
-                        .map_err(|e| HighlightError::EventProcessing(e.to_string()))?;
+                    let current_language = language_stack
+                        .last()
+                        .map(String::as_str)
+                        .unwrap_or_default();
+                    on_event_source(
+                        text,
+                        start..end,
+                        current_scope,
+                        current_language,
+                        current_style,
+                    )
+                    .map_err(|e| HighlightError::EventProcessing(e.to_string()))?;
                 }
             }
             HighlightEvent::HighlightEnd => {
@@ -363,6 +668,9 @@ where
                 if scope_stack.len() > 1 {
                     scope_stack.pop();
                 }
+                if language_stack.len() > 1 {
+                    language_stack.pop();
+                }
             }
         }
     }
@@ -477,4 +785,43 @@ mod tests {
         let reconstructed: String = segments.iter().map(|(_, text)| *text).collect();
         assert_eq!(reconstructed, code);
     }
+
+    #[test]
+    fn test_source_range() {
+        let code = "one\ntwo\nthree";
+
+        assert_eq!(SourceRange::Lines(1..=1).byte_range(code), 0..4);
+        assert_eq!(SourceRange::Lines(2..=3).byte_range(code), 4..13);
+        assert_eq!(SourceRange::Lines(3..=100).byte_range(code), 8..13);
+        assert_eq!(SourceRange::Lines(5..=6).byte_range(code), 13..13);
+        assert_eq!(SourceRange::Bytes(2..100).byte_range(code), 2..13);
+        assert_eq!(SourceRange::Bytes(1..2).byte_range("é"), 0..2);
+
+        assert_eq!(SourceRange::Lines(2..=3).line_range(code), 2..=3);
+        assert_eq!(SourceRange::Bytes(0..4).line_range(code), 1..=1);
+        assert_eq!(SourceRange::Bytes(2..5).line_range(code), 1..=2);
+    }
+
+    #[test]
+    fn test_streaming_in_range_keeps_context() {
+        let code = "/*\nstill a comment\n*/\nfn main() {}\n";
+        let mut segments = Vec::new();
+
+        highlight_iter_in_range(
+            code,
+            Language::Rust,
+            None,
+            &SourceRange::Lines(2..=2),
+            |text, range, scope, _style| {
+                segments.push((text.to_string(), range, scope));
+                Ok::<_, std::io::Error>(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            segments,
+            vec![("still a comment\n".to_string(), 3..19, "comment")]
+        );
+    }
 }
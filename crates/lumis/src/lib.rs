@@ -81,6 +81,13 @@
 //! | [`HtmlMultiThemesBuilder`] | HTML (inline) with multiple themes | Support light/dark mode, theme switching |
 //! | [`HtmlLinkedBuilder`] | HTML with CSS classes | Multiple code blocks, custom styling |
 //! | [`TerminalBuilder`] | ANSI escape codes | CLI tools, terminal output |
+//! | [`SvgBuilder`] | SVG image | Slide decks, READMEs, social cards |
+//! | [`LatexBuilder`] | LaTeX markup | Papers and books |
+//! | [`RtfBuilder`] | Rich Text Format | Pasting into Word, Pages, Keynote, Google Docs |
+//! | [`JsonBuilder`] | JSON token stream | Rendering tokens in other languages or frameworks |
+//! | [`HtmlDiffBuilder`] | HTML for unified diffs | Colorized diffs in code reviews and web pages |
+//! | [`TerminalDiffBuilder`] | ANSI escape codes for unified diffs | Reviewing patches in the terminal |
+//! | `PngBuilder` | PNG image (`image` feature) | Code screenshots without a browser |
 //!
 //! See the [`formatter`] module for advanced features like line highlighting and custom formatters.
 //!
@@ -304,10 +311,17 @@
 //! | zenburn |
 //! | zephyr_dark |
 
+pub mod annotations;
+pub mod brackets;
 pub mod constants;
+pub mod css;
+pub mod diagnostics;
+pub mod folding;
 pub mod formatter;
 pub mod highlight;
 pub mod languages;
+pub mod navigation;
+pub mod semantic;
 pub mod themes;
 pub mod vendor;
 
@@ -320,9 +334,13 @@ use std::io::{self, Write};
 
 // Re-export builders for easier access
 pub use crate::formatter::{
-    HtmlInlineBuilder, HtmlLinkedBuilder, HtmlMultiThemesBuilder, TerminalBuilder,
+    HtmlDiffBuilder, HtmlInlineBuilder, HtmlLinkedBuilder, HtmlMultiThemesBuilder, JsonBuilder,
+    LatexBuilder, RtfBuilder, SvgBuilder, TerminalBuilder, TerminalDiffBuilder,
 };
 
+#[cfg(feature = "image")]
+pub use crate::formatter::PngBuilder;
+
 /// Highlights source code and returns it as a string.
 ///
 /// This is a convenience wrapper that calls the formatter and returns the result as a String.
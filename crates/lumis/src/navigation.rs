@@ -0,0 +1,442 @@
+//! Definitions and references of the identifiers of the source.
+//!
+//! The `locals.scm` queries bundled with each language capture the scopes, definitions and
+//! references of a document. [`Symbols::resolve()`] links each reference to the definition it
+//! refers to, walking up the enclosing scopes like an editor does for "go to definition".
+//!
+//! The HTML formatters use it with the `code_navigation` option to give each definition an
+//! anchor `id` and wrap each reference in an `<a href="#def-...">` pointing at it, while
+//! [`code_navigation_script()`](crate::formatter::html::code_navigation_script) highlights all
+//! the uses of a symbol on hover.
+//!
+//! The resolution is purely syntactic: it doesn't know about imports, modules or types, so
+//! only symbols defined in the same source are linked.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::languages::Language;
+//! use lumis::navigation::Symbols;
+//!
+//! let code = "fn main() {\n    let x = 1;\n    println!(\"{}\", x + x);\n}\n";
+//! let symbols = Symbols::resolve(code, Language::Rust);
+//!
+//! let x = symbols.definitions.iter().position(|def| def.name == "x").unwrap();
+//! assert_eq!(symbols.definitions[x].id, "def-2-9");
+//! assert_eq!(symbols.references.iter().filter(|r| r.definition == x).count(), 2);
+//! ```
+
+use crate::languages::Language;
+use std::collections::HashMap;
+use std::ops::Range;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Parser, QueryCursor};
+
+/// An identifier introducing a symbol, like a variable or a function name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Definition {
+    /// Name of the symbol.
+    pub name: String,
+    /// Kind of definition from the capture name, eg: `function` for `@local.definition.function`.
+    pub kind: Option<String>,
+    /// Byte range of the identifier in the source.
+    pub range: Range<usize>,
+    /// Anchor id of the definition, `def-LINE-COLUMN` with 1-based line and byte column.
+    pub id: String,
+}
+
+/// An identifier referring to a [`Definition`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// Byte range of the identifier in the source.
+    pub range: Range<usize>,
+    /// Index of the definition in [`Symbols::definitions`].
+    pub definition: usize,
+}
+
+/// Definitions of a source and the references resolved to them.
+///
+/// References that don't resolve to a definition of the same source, like calls to functions
+/// of the standard library, are left out.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Symbols {
+    /// Definitions sorted by their position in the source.
+    pub definitions: Vec<Definition>,
+    /// References sorted by their position in the source.
+    pub references: Vec<Reference>,
+}
+
+/// A definition or a reference found in the source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Occurrence<'a> {
+    Definition(&'a Definition),
+    Reference(&'a Definition),
+}
+
+struct Scope {
+    range: Range<usize>,
+    node_id: usize,
+}
+
+struct Candidate<'tree> {
+    node: Node<'tree>,
+    kind: Option<String>,
+    scope: Option<String>,
+}
+
+impl Symbols {
+    /// Resolve the symbols of `source` with the locals query of `language`.
+    ///
+    /// Languages without a locals query, or sources that fail to parse, have no symbols.
+    pub fn resolve(source: &str, language: Language) -> Self {
+        let config = language.config();
+        let mut parser = Parser::new();
+        if parser.set_language(&config.language).is_err() {
+            return Self::default();
+        }
+        let Some(tree) = parser.parse(source, None) else {
+            return Self::default();
+        };
+
+        let query = &config.query;
+        let capture_names = query.capture_names();
+        let mut scopes = vec![Scope {
+            range: 0..source.len(),
+            node_id: usize::MAX,
+        }];
+        let mut definitions: Vec<Candidate> = Vec::new();
+        let mut references: Vec<Candidate> = Vec::new();
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+        while let Some(query_match) = matches.next() {
+            let properties = query.property_settings(query_match.pattern_index);
+            let property = |suffix: &str| {
+                properties
+                    .iter()
+                    .find(|property| property.key.ends_with(suffix))
+                    .and_then(|property| property.value.as_deref().map(str::to_string))
+            };
+
+            for capture in query_match.captures {
+                let name = capture_names[capture.index as usize];
+                let node = capture.node;
+
+                if name == "local.scope" {
+                    scopes.push(Scope {
+                        range: node.byte_range(),
+                        node_id: node.id(),
+                    });
+                } else if name == "local.reference" {
+                    references.push(Candidate {
+                        node,
+                        kind: property("reference.kind"),
+                        scope: None,
+                    });
+                } else if let Some(kind) = name.strip_prefix("local.definition") {
+                    if kind.starts_with('-') {
+                        continue;
+                    }
+                    definitions.push(Candidate {
+                        node,
+                        kind: kind.strip_prefix('.').map(str::to_string),
+                        scope: property(".scope"),
+                    });
+                }
+            }
+        }
+
+        definitions.sort_by_key(|def| def.node.start_byte());
+        definitions.dedup_by_key(|def| def.node.byte_range());
+        references.sort_by_key(|reference| reference.node.start_byte());
+        references.dedup_by_key(|reference| reference.node.byte_range());
+        references.retain(|reference| {
+            definitions
+                .binary_search_by_key(&reference.node.start_byte(), |def| def.node.start_byte())
+                .is_err()
+        });
+
+        let scopes = ScopeTree::new(scopes);
+        let definition_scopes: Vec<usize> = definitions
+            .iter()
+            .map(|def| definition_scope(&scopes, def))
+            .collect();
+
+        let text = |node: &Node| &source[node.byte_range()];
+        let symbols_definitions: Vec<Definition> = definitions
+            .iter()
+            .map(|def| {
+                let position = def.node.start_position();
+                Definition {
+                    name: text(&def.node).to_string(),
+                    kind: def.kind.clone(),
+                    range: def.node.byte_range(),
+                    id: format!("def-{}-{}", position.row + 1, position.column + 1),
+                }
+            })
+            .collect();
+
+        // Definitions by scope and name, in the order of the source.
+        let mut by_scope: HashMap<(usize, &str), Vec<usize>> = HashMap::new();
+        for (i, def) in symbols_definitions.iter().enumerate() {
+            by_scope
+                .entry((definition_scopes[i], def.name.as_str()))
+                .or_default()
+                .push(i);
+        }
+
+        let references = references
+            .iter()
+            .filter_map(|reference| {
+                let name = text(&reference.node);
+                let offset = reference.node.start_byte();
+
+                scopes
+                    .enclosing(&reference.node.byte_range())
+                    .find_map(|scope| {
+                        let candidates: Vec<usize> = by_scope
+                            .get(&(scope, name))?
+                            .iter()
+                            .copied()
+                            .filter(|&i| {
+                                reference.kind.is_none()
+                                    || symbols_definitions[i].kind == reference.kind
+                            })
+                            .collect();
+
+                        // The closest preceding definition shadows the others, while items like
+                        // functions and types can also be used before they are defined.
+                        candidates
+                            .iter()
+                            .rev()
+                            .find(|&&i| symbols_definitions[i].range.start <= offset)
+                            .or(candidates
+                                .first()
+                                .filter(|&&i| is_item(symbols_definitions[i].kind.as_deref())))
+                            .map(|&definition| Reference {
+                                range: reference.node.byte_range(),
+                                definition,
+                            })
+                    })
+            })
+            .collect();
+
+        Self {
+            definitions: symbols_definitions,
+            references,
+        }
+    }
+
+    /// The definition or reference covering `offset`.
+    pub(crate) fn at(&self, offset: usize) -> Option<Occurrence<'_>> {
+        let i = self
+            .definitions
+            .partition_point(|def| def.range.end <= offset);
+        if let Some(def) = self
+            .definitions
+            .get(i)
+            .filter(|def| def.range.start <= offset)
+        {
+            return Some(Occurrence::Definition(def));
+        }
+
+        let i = self
+            .references
+            .partition_point(|reference| reference.range.end <= offset);
+        self.references
+            .get(i)
+            .filter(|reference| reference.range.start <= offset)
+            .map(|reference| Occurrence::Reference(&self.definitions[reference.definition]))
+    }
+
+    /// First start or end offset of a definition or reference after `offset`.
+    pub(crate) fn next_boundary(&self, offset: usize) -> Option<usize> {
+        let definitions = next_boundary(&self.definitions, |def| &def.range, offset);
+        let references = next_boundary(&self.references, |reference| &reference.range, offset);
+        definitions.into_iter().chain(references).min()
+    }
+}
+
+/// First start or end offset after `offset` of `items`, sorted and not overlapping.
+pub(crate) fn next_boundary<T>(
+    items: &[T],
+    range: impl Fn(&T) -> &Range<usize>,
+    offset: usize,
+) -> Option<usize> {
+    let i = items.partition_point(|item| range(item).end <= offset);
+    items.get(i).map(|item| {
+        let range = range(item);
+        if range.start > offset {
+            range.start
+        } else {
+            range.end
+        }
+    })
+}
+
+/// Definitions of items like functions and types, as opposed to variables and parameters.
+pub(crate) fn is_item(kind: Option<&str>) -> bool {
+    !matches!(
+        kind,
+        None | Some("var" | "variable" | "parameter" | "var.parameter")
+    )
+}
+
+/// Scopes of a source nested in each other like the nodes that open them.
+struct ScopeTree {
+    scopes: Vec<Scope>,
+    /// Indices of the scopes sorted by start, enclosing scopes before the ones they contain.
+    order: Vec<usize>,
+    /// Index of the enclosing scope of each scope, the first one being the whole source.
+    parents: Vec<Option<usize>>,
+}
+
+impl ScopeTree {
+    fn new(scopes: Vec<Scope>) -> Self {
+        let mut order: Vec<usize> = (0..scopes.len()).collect();
+        order.sort_by_key(|&i| {
+            (
+                scopes[i].range.start,
+                std::cmp::Reverse(scopes[i].range.end),
+                i,
+            )
+        });
+
+        let mut parents = vec![None; scopes.len()];
+        let mut stack: Vec<usize> = Vec::new();
+        for &i in &order {
+            while let Some(&top) = stack.last() {
+                if contains(&scopes[top].range, &scopes[i].range) {
+                    break;
+                }
+                stack.pop();
+            }
+            parents[i] = stack.last().copied();
+            stack.push(i);
+        }
+
+        Self {
+            scopes,
+            order,
+            parents,
+        }
+    }
+
+    /// Index of the innermost scope containing `range`, followed by the enclosing ones.
+    fn enclosing(&self, range: &Range<usize>) -> impl Iterator<Item = usize> + '_ {
+        // The innermost scope is the last one starting before `range` or one of its parents.
+        let last = self
+            .order
+            .partition_point(|&i| self.scopes[i].range.start <= range.start);
+        let mut scope = last.checked_sub(1).map(|i| self.order[i]);
+        while let Some(i) = scope.filter(|&i| !contains(&self.scopes[i].range, range)) {
+            scope = self.parents[i];
+        }
+
+        std::iter::successors(scope, |&i| self.parents[i])
+    }
+}
+
+fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// Index of the scope a definition belongs to.
+///
+/// Items naming the scope they open, like functions or structs, belong to the enclosing
+/// scope so they can be referenced from outside. The `definition.KIND.scope` property of
+/// the query can also move a definition to the `parent` scope or to the `global` one.
+fn definition_scope(scopes: &ScopeTree, def: &Candidate) -> usize {
+    let mut enclosing = scopes
+        .enclosing(&def.node.byte_range())
+        .filter(|&i| scopes.scopes[i].node_id != def.node.id());
+    let Some(innermost) = enclosing.next() else {
+        return 0;
+    };
+
+    let names_scope = def
+        .node
+        .parent()
+        .is_some_and(|parent| parent.id() == scopes.scopes[innermost].node_id);
+
+    match def.scope.as_deref() {
+        Some("global") => 0,
+        Some("parent") => enclosing.next().unwrap_or(0),
+        _ if names_scope && is_item(def.kind.as_deref()) => enclosing.next().unwrap_or(0),
+        _ => innermost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(source: &str, language: Language) -> Vec<(String, String)> {
+        let symbols = Symbols::resolve(source, language);
+        symbols
+            .references
+            .iter()
+            .map(|reference| {
+                (
+                    source[reference.range.clone()].to_string(),
+                    symbols.definitions[reference.definition].id.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let code = "fn main() {\n    let x = 1;\n    {\n        let x = 2;\n        x;\n    }\n    x;\n}\n";
+
+        assert_eq!(
+            links(code, Language::Rust),
+            vec![
+                ("x".to_string(), "def-4-13".to_string()),
+                ("x".to_string(), "def-2-9".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_items_used_before_definition() {
+        let code = "fn main() {\n    helper();\n}\n\nfn helper() {}\n";
+
+        assert_eq!(
+            links(code, Language::Rust),
+            vec![("helper".to_string(), "def-5-4".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_variables_used_before_definition() {
+        let code = "fn main() {\n    x;\n    let x = 1;\n}\n";
+
+        assert!(links(code, Language::Rust).is_empty());
+    }
+
+    #[test]
+    fn test_language_without_locals() {
+        assert_eq!(
+            Symbols::resolve("x", Language::PlainText),
+            Symbols::default()
+        );
+    }
+
+    #[test]
+    fn test_occurrence_lookup() {
+        let code = "fn main() {\n    let x = 1;\n    x;\n}\n";
+        let symbols = Symbols::resolve(code, Language::Rust);
+        let x = code.find('x').unwrap();
+        let reference = code.rfind('x').unwrap();
+
+        assert!(matches!(symbols.at(x), Some(Occurrence::Definition(def)) if def.name == "x"));
+        assert!(
+            matches!(symbols.at(reference), Some(Occurrence::Reference(def)) if def.name == "x")
+        );
+        assert_eq!(symbols.at(x + 1), None);
+        assert_eq!(symbols.next_boundary(x), Some(x + 1));
+        assert_eq!(symbols.next_boundary(x + 1), Some(reference));
+        assert_eq!(symbols.next_boundary(reference + 1), None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Semantic colors: a stable color for each local variable.
+//!
+//! Long functions are easier to follow when every local binding has its own hue, shared by
+//! its definition and all of its references. The bindings are found with the `locals.scm`
+//! queries, like in [`navigation`](crate::navigation), and each one gets a color of a
+//! palette picked by hashing its name, so a variable keeps its color across renders.
+//!
+//! The palette is tuned for the background of the theme with [`Theme::semantic_palette()`]:
+//! light colors on dark themes and darker ones on light themes.
+//!
+//! Semantic colors are opt-in, with [`Highlighter::semantic_colors()`] and
+//! [`highlight_iter_with_semantic_colors()`], or the `semantic_colors` option of the HTML and
+//! terminal formatters. The theme CSS files include `.semantic-N` rules for
+//! [`HtmlLinked`](crate::formatter::HtmlLinked).
+//!
+//! [`Highlighter::semantic_colors()`]: crate::highlight::Highlighter::semantic_colors
+//! [`highlight_iter_with_semantic_colors()`]: crate::highlight::highlight_iter_with_semantic_colors
+//!
+//! # Examples
+//!
+//! ```rust
+//! use lumis::languages::Language;
+//! use lumis::semantic::local_identifiers;
+//!
+//! let code = "fn main() {\n    let total = 1;\n    let count = total + 1;\n}\n";
+//! let identifiers = local_identifiers(code, Language::Rust);
+//!
+//! let color = |name: &str| {
+//!     identifiers
+//!         .iter()
+//!         .filter(|identifier| &code[identifier.range.clone()] == name)
+//!         .map(|identifier| identifier.color)
+//!         .collect::<Vec<_>>()
+//! };
+//!
+//! // The definition and the reference share the same color
+//! assert_eq!(color("total").len(), 2);
+//! assert_eq!(color("total")[0], color("total")[1]);
+//! ```
+
+use crate::highlight::Style;
+use crate::languages::Language;
+use crate::navigation::Symbols;
+use crate::themes::Theme;
+use std::ops::Range;
+
+/// Number of colors of the semantic palettes, and of the `.semantic-N` rules in the theme
+/// CSS files.
+pub const PALETTE_SIZE: usize = 12;
+
+/// An identifier of a local binding, either its definition or a reference to it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Identifier {
+    /// Byte range of the identifier in the source.
+    pub range: Range<usize>,
+    /// Index of the color in the palette, derived from the name of the binding.
+    pub color: usize,
+}
+
+/// Find the identifiers of the local bindings of `source`, sorted by position.
+///
+/// Local bindings are the variables and parameters defined in the source. Items like
+/// functions and types keep their regular highlight.
+pub fn local_identifiers(source: &str, language: Language) -> Vec<Identifier> {
+    let symbols = Symbols::resolve(source, language);
+    let color = |definition: usize| color_index(&symbols.definitions[definition].name);
+    let is_local = |definition: usize| {
+        !crate::navigation::is_item(symbols.definitions[definition].kind.as_deref())
+    };
+
+    let mut identifiers: Vec<Identifier> = (0..symbols.definitions.len())
+        .filter(|&definition| is_local(definition))
+        .map(|definition| Identifier {
+            range: symbols.definitions[definition].range.clone(),
+            color: color(definition),
+        })
+        .chain(
+            symbols
+                .references
+                .iter()
+                .filter(|reference| is_local(reference.definition))
+                .map(|reference| Identifier {
+                    range: reference.range.clone(),
+                    color: color(reference.definition),
+                }),
+        )
+        .collect();
+
+    identifiers.sort_by_key(|identifier| identifier.range.start);
+    identifiers
+}
+
+/// Index in the palette of the color of the binding `name`.
+///
+/// Uses the FNV-1a hash, which is stable across platforms and releases.
+pub fn color_index(name: &str) -> usize {
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    (hash % PALETTE_SIZE as u64) as usize
+}
+
+/// Palette of [`PALETTE_SIZE`] hues evenly spread around the color wheel, with the
+/// lightness tuned for a background color.
+///
+/// Without a background, the colors have a medium lightness readable on both light and
+/// dark backgrounds.
+pub(crate) fn palette_for_background(background: Option<&str>) -> Vec<String> {
+    let dark = background
+        .and_then(crate::formatter::ansi::hex_to_rgb)
+        .map(|(r, g, b)| (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0 < 0.5);
+    let (saturation, lightness) = match dark {
+        Some(true) => (0.6, 0.72),
+        Some(false) => (0.65, 0.4),
+        None => (0.6, 0.55),
+    };
+
+    (0..PALETTE_SIZE)
+        .map(|i| {
+            hsl_to_hex(
+                15.0 + i as f32 * 360.0 / PALETTE_SIZE as f32,
+                saturation,
+                lightness,
+            )
+        })
+        .collect()
+}
+
+fn hsl_to_hex(hue: f32, saturation: f32, lightness: f32) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + m) * 255.0).round() as u8;
+
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// Identifiers of the local bindings of a source with the palette used to color them.
+pub(crate) struct SemanticColors {
+    identifiers: Vec<Identifier>,
+    palette: Vec<String>,
+}
+
+impl SemanticColors {
+    pub(crate) fn new(source: &str, language: Language, theme: Option<&Theme>) -> Self {
+        Self {
+            identifiers: local_identifiers(source, language),
+            palette: match theme {
+                Some(theme) => theme.semantic_palette(),
+                None => palette_for_background(None),
+            },
+        }
+    }
+
+    /// Split `range` at the identifiers it contains, returning each piece with the color
+    /// index of the identifier it covers, if any.
+    pub(crate) fn split(&self, range: Range<usize>) -> Vec<(Range<usize>, Option<usize>)> {
+        let first = self
+            .identifiers
+            .partition_point(|identifier| identifier.range.end <= range.start);
+        let mut pieces = Vec::new();
+        let mut start = range.start;
+
+        for identifier in &self.identifiers[first..] {
+            if identifier.range.start >= range.end {
+                break;
+            }
+            if identifier.range.start > start {
+                pieces.push((start..identifier.range.start, None));
+            }
+            let end = identifier.range.end.min(range.end);
+            pieces.push((
+                identifier.range.start.max(start)..end,
+                Some(identifier.color),
+            ));
+            start = end;
+        }
+        if start < range.end {
+            pieces.push((start..range.end, None));
+        }
+
+        pieces
+    }
+
+    /// Color index of the identifier covering `offset`.
+    pub(crate) fn color_at(&self, offset: usize) -> Option<usize> {
+        let i = self
+            .identifiers
+            .partition_point(|identifier| identifier.range.end <= offset);
+        self.identifiers
+            .get(i)
+            .filter(|identifier| identifier.range.start <= offset)
+            .map(|identifier| identifier.color)
+    }
+
+    /// First start or end offset of an identifier after `offset`.
+    pub(crate) fn next_boundary(&self, offset: usize) -> Option<usize> {
+        crate::navigation::next_boundary(&self.identifiers, |identifier| &identifier.range, offset)
+    }
+
+    /// Color at `index` of the palette.
+    pub(crate) fn color(&self, index: usize) -> &str {
+        &self.palette[index % self.palette.len()]
+    }
+
+    /// `style` with the foreground of the color at `index`.
+    pub(crate) fn style(&self, style: &Style, index: usize) -> Style {
+        Style {
+            fg: Some(self.color(index).to_string()),
+            ..style.clone()
+        }
+    }
+}
+
+/// Wrap a callback of [`highlight_iter()`](crate::highlight::highlight_iter) so identifiers
+/// of local bindings are passed as their own segments, styled with their color.
+pub(crate) fn semantic<'a, F, E>(
+    source: &'a str,
+    colors: Option<&'a SemanticColors>,
+    mut callback: F,
+) -> impl FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a
+where
+    F: FnMut(&str, Range<usize>, &'static str, &str, &Style) -> Result<(), E> + 'a,
+{
+    move |text, range, scope, language, style| {
+        let Some(colors) = colors else {
+            return callback(text, range, scope, language, style);
+        };
+
+        for (piece, color) in colors.split(range) {
+            let text = &source[piece.clone()];
+            match color {
+                Some(color) => callback(text, piece, scope, language, &colors.style(style, color))?,
+                None => callback(text, piece, scope, language, style)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_local_bindings() {
+        let code = "fn add(a: i32) -> i32 {\n    let b = a;\n    add(b)\n}\n";
+        let names: Vec<&str> = local_identifiers(code, Language::Rust)
+            .into_iter()
+            .map(|identifier| &code[identifier.range])
+            .collect();
+
+        assert_eq!(names, vec!["a", "b", "a", "b"]);
+    }
+
+    #[test]
+    fn test_next_boundary() {
+        let code = "fn f(a: i32) {\n    a;\n}\n";
+        let colors = SemanticColors::new(code, Language::Rust, None);
+        let a = code.find('a').unwrap();
+        let reference = code.rfind('a').unwrap();
+
+        assert_eq!(colors.next_boundary(0), Some(a));
+        assert_eq!(colors.next_boundary(a), Some(a + 1));
+        assert_eq!(colors.next_boundary(a + 1), Some(reference));
+        assert_eq!(colors.next_boundary(reference + 1), None);
+    }
+
+    #[test]
+    fn test_palette_for_background() {
+        let dark = palette_for_background(Some("#000000"));
+        let light = palette_for_background(Some("#ffffff"));
+
+        assert_eq!(dark.len(), PALETTE_SIZE);
+        assert_ne!(dark, light);
+        assert_eq!(hsl_to_hex(0.0, 1.0, 0.5), "#ff0000");
+        assert_eq!(hsl_to_hex(240.0, 1.0, 0.5), "#0000ff");
+    }
+}
@@ -650,6 +650,10 @@ impl Theme {
         }
     }
 
+    /// Generate the stylesheet of the theme bundled in `css/`, for [`HtmlLinked`](crate::formatter::HtmlLinked).
+    ///
+    /// Use [`css::Stylesheet`](crate::css::Stylesheet) for a custom root selector, class
+    /// prefix, or to combine a light and a dark theme.
     pub fn css(&self, enable_italic: bool) -> String {
         let mut rules = Vec::new();
 
@@ -664,19 +668,244 @@ impl Theme {
             rules.push(" {}\n".to_string());
         }
 
-        for (scope, style) in &self.highlights {
-            let style_css = style.css(enable_italic, "\n  ");
+        for rule in crate::css::theme_rules(self, None, enable_italic, "") {
+            rules.push(rule.render(&rule.selectors));
+        }
+
+        rules.join("")
+    }
+
+    /// Get the style for line number gutters.
+    ///
+    /// Uses the foreground of `comment`, which is what the bundled themes get. Custom themes
+    /// can style the gutter on their own with a `line_nr` highlight.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let style = theme.line_number_style().unwrap();
+    /// assert_eq!(style.fg.as_deref(), Some("#6272a4"));
+    /// ```
+    pub fn line_number_style(&self) -> Option<Style> {
+        if let Some(style) = self.highlights.get("line_nr") {
+            return Some(style.clone());
+        }
+
+        let fg = self.get_style("comment")?.fg.clone()?;
+        Some(Style {
+            fg: Some(fg),
+            ..Default::default()
+        })
+    }
+
+    /// Get the style of visible whitespace and indent guides.
+    ///
+    /// Uses the foreground of `comment`, which is what the bundled themes get. Custom themes
+    /// can style whitespace on their own with a `whitespace` or `nontext` highlight.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let style = theme.whitespace_style().unwrap();
+    /// assert_eq!(style.fg.as_deref(), Some("#6272a4"));
+    /// ```
+    pub fn whitespace_style(&self) -> Option<Style> {
+        if let Some(style) = ["whitespace", "nontext"]
+            .iter()
+            .find_map(|scope| self.highlights.get(*scope))
+        {
+            return Some(style.clone());
+        }
+
+        let fg = self.get_style("comment")?.fg.clone()?;
+        Some(Style {
+            fg: Some(fg),
+            ..Default::default()
+        })
+    }
+
+    /// Get the style for added or removed lines in a diff.
+    ///
+    /// Takes the foreground of the `diff.plus` or `diff.minus` scope. Most themes only
+    /// define a foreground for these scopes, so when the background is missing it's
+    /// derived by tinting the theme background with 20% of the foreground.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let style = theme.diff_style("diff.plus").unwrap();
+    /// assert_eq!(style.fg.as_deref(), Some("#b3f6c0"));
+    /// assert_eq!(style.bg.as_deref(), Some("#445352"));
+    ///
+    /// let theme = themes::get("github_light").unwrap();
+    /// let style = theme.diff_style("diff.minus").unwrap();
+    /// assert_eq!(style.bg.as_deref(), Some("#e4b7be"));
+    /// ```
+    pub fn diff_style(&self, scope: &str) -> Option<Style> {
+        let style = self.get_style(scope)?;
+        let bg = style
+            .bg
+            .clone()
+            .or_else(|| tint(style.fg.as_deref()?, self.bg_or_default(), 0.2));
+
+        Some(Style {
+            fg: style.fg.clone(),
+            bg,
+            ..Default::default()
+        })
+    }
+
+    /// Get the style for the words that changed inside added or removed lines.
+    ///
+    /// A stronger variant of [`diff_style()`](Theme::diff_style), tinting the line
+    /// background with 25% of the `diff.plus` or `diff.minus` foreground.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let style = theme.diff_emphasis_style("diff.plus").unwrap();
+    /// assert_eq!(style.bg.as_deref(), Some("#607c6e"));
+    /// ```
+    pub fn diff_emphasis_style(&self, scope: &str) -> Option<Style> {
+        let style = self.diff_style(scope)?;
+        let bg = tint(style.fg.as_deref()?, style.bg.as_deref()?, 0.25);
+
+        Some(Style { bg, ..style })
+    }
+
+    /// Get the style for annotations attached to ranges of the source.
+    ///
+    /// Takes the color of the `comment.note` highlight, using its background for themes
+    /// that draw notes as badges, and tints the theme background with 20% of it for the
+    /// background of the annotated text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let style = theme.annotation_style().unwrap();
+    /// assert_eq!(style.fg.as_deref(), Some("#8be9fd"));
+    /// assert_eq!(style.bg.as_deref(), Some("#3c505e"));
+    /// ```
+    pub fn annotation_style(&self) -> Option<Style> {
+        let fg = self.badge_color("comment.note")?.to_string();
+        let bg = tint(&fg, self.bg_or_default(), 0.2);
+
+        Some(Style {
+            fg: Some(fg),
+            bg,
+            bold: true,
+            ..Default::default()
+        })
+    }
 
-            if !style_css.is_empty() {
-                rules.push(format!(
-                    ".{} {{\n  {}\n}}\n",
-                    scope.replace('.', "-"),
-                    style_css
-                ))
+    /// Get the palette of rainbow brackets, by nesting depth.
+    ///
+    /// Takes the distinct foreground colors of prominent highlights like `keyword`,
+    /// `function` and `string`, skipping the normal foreground, and repeats them to fill
+    /// the [`PALETTE_SIZE`](crate::brackets::PALETTE_SIZE) depths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// let palette = theme.bracket_palette();
+    /// assert_eq!(palette.len(), 6);
+    /// assert_eq!(palette[0], "#ff79c6");
+    /// ```
+    pub fn bracket_palette(&self) -> Vec<String> {
+        let scopes = [
+            "keyword",
+            "function",
+            "string",
+            "type",
+            "constant",
+            "variable.parameter",
+            "number",
+            "property",
+        ];
+        let mut colors: Vec<String> = Vec::new();
+
+        for scope in scopes {
+            let Some(fg) = self.get_style(scope).and_then(|style| style.fg.as_deref()) else {
+                continue;
             };
+            let fg = fg.to_ascii_lowercase();
+            if Some(fg.as_str()) != self.fg().map(str::to_ascii_lowercase).as_deref()
+                && !colors.contains(&fg)
+            {
+                colors.push(fg);
+            }
         }
 
-        rules.join("")
+        if colors.is_empty() {
+            return crate::brackets::DEFAULT_PALETTE
+                .iter()
+                .map(|color| color.to_string())
+                .collect();
+        }
+
+        colors
+            .iter()
+            .cycle()
+            .take(crate::brackets::PALETTE_SIZE)
+            .cloned()
+            .collect()
+    }
+
+    /// Get the palette of semantic colors, tuned for the background of the theme.
+    ///
+    /// Hues are evenly spread around the color wheel, lighter on dark themes and darker on
+    /// light themes. See [`semantic`](crate::semantic).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use lumis::themes;
+    ///
+    /// let theme = themes::get("dracula").unwrap();
+    /// assert_eq!(theme.semantic_palette().len(), lumis::semantic::PALETTE_SIZE);
+    /// ```
+    pub fn semantic_palette(&self) -> Vec<String> {
+        let background = self
+            .bg()
+            .filter(|bg| crate::formatter::ansi::hex_to_rgb(bg).is_some());
+        let background = background.unwrap_or(match self.appearance {
+            Appearance::Light => "#ffffff",
+            Appearance::Dark => "#000000",
+        });
+
+        crate::semantic::palette_for_background(Some(background))
+    }
+
+    /// Color of highlights that some themes draw as badges, like `comment.note`:
+    /// the background when set, otherwise the foreground.
+    pub(crate) fn badge_color(&self, scope: &str) -> Option<&str> {
+        let style = self.get_style(scope)?;
+        style.bg.as_deref().or(style.fg.as_deref())
+    }
+
+    fn bg_or_default(&self) -> &str {
+        self.bg().unwrap_or(match self.appearance {
+            Appearance::Light => "#ffffff",
+            Appearance::Dark => "#000000",
+        })
     }
 
     /// Get style for a scope.
@@ -746,6 +975,21 @@ impl Theme {
     }
 }
 
+/// Mix `amount` of `color` into `base`, both hex colors.
+fn tint(color: &str, base: &str, amount: f32) -> Option<String> {
+    let color = crate::formatter::ansi::hex_to_rgb(color)?;
+    let base = crate::formatter::ansi::hex_to_rgb(base)?;
+    let mix =
+        |color: u8, base: u8| (color as f32 * amount + base as f32 * (1.0 - amount)).round() as u8;
+
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        mix(color.0, base.0),
+        mix(color.1, base.1),
+        mix(color.2, base.2)
+    ))
+}
+
 impl Style {
     pub fn css(&self, enable_italic: bool, separator: &str) -> String {
         let mut rules = Vec::new();
@@ -984,11 +1228,105 @@ pre.lumis {
   background-color: gray;
   font-weight: bold;
 }
+mark.annotation {
+  color: inherit;
+  text-decoration: underline;
+}
+.annotation-marker {
+  user-select: none;
+  -webkit-user-select: none;
+}
+a.symbol-reference {
+  color: inherit;
+  text-decoration: none;
+}
+.rainbow-bracket-1 {
+  color: blue;
+}
+.rainbow-bracket-2 {
+  color: blue;
+}
+.rainbow-bracket-3 {
+  color: blue;
+}
+.rainbow-bracket-4 {
+  color: blue;
+}
+.rainbow-bracket-5 {
+  color: blue;
+}
+.rainbow-bracket-6 {
+  color: blue;
+}
+.semantic-1 {
+  color: #e2a28d;
+}
+.semantic-2 {
+  color: #e2cd8d;
+}
+.semantic-3 {
+  color: #cde28d;
+}
+.semantic-4 {
+  color: #a2e28d;
+}
+.semantic-5 {
+  color: #8de2a2;
+}
+.semantic-6 {
+  color: #8de2cd;
+}
+.semantic-7 {
+  color: #8dcde2;
+}
+.semantic-8 {
+  color: #8da2e2;
+}
+.semantic-9 {
+  color: #a28de2;
+}
+.semantic-10 {
+  color: #cd8de2;
+}
+.semantic-11 {
+  color: #e28dcd;
+}
+.semantic-12 {
+  color: #e28da2;
+}
+.line-number {
+  user-select: none;
+  -webkit-user-select: none;
+}
 "#;
 
         assert_eq!(theme.css(true), expected);
     }
 
+    #[test]
+    fn test_line_number_style() {
+        let json = r##"{"name": "test", "appearance": "dark", "revision": "test", "highlights": {"comment": {"fg": "#666666", "italic": true}}}"##;
+        let theme = from_json(json).unwrap();
+
+        let style = theme.line_number_style().unwrap();
+        assert_eq!(style.fg.as_deref(), Some("#666666"));
+        assert!(!style.italic);
+        assert!(theme.css(true).ends_with(
+            ".line-number {\n  user-select: none;\n  -webkit-user-select: none;\n  color: #666666;\n}\n"
+        ));
+
+        let json = r##"{"name": "test", "appearance": "dark", "revision": "test", "highlights": {"comment": {"fg": "#666666"}, "line_nr": {"fg": "#444444", "bg": "#000000"}}}"##;
+        let theme = from_json(json).unwrap();
+
+        let style = theme.line_number_style().unwrap();
+        assert_eq!(style.fg.as_deref(), Some("#444444"));
+        assert_eq!(style.bg.as_deref(), Some("#000000"));
+
+        let json =
+            r#"{"name": "test", "appearance": "dark", "revision": "test", "highlights": {}}"#;
+        assert!(from_json(json).unwrap().line_number_style().is_none());
+    }
+
     // Tests for specialized capture groups (issue #287)
     // https://github.com/leandrocp/lumis/issues/287
 
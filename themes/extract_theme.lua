@@ -2,6 +2,9 @@ local regular_groups = {
 	"Normal",
 	"Comment",
 	"CursorLine",
+	"LineNr",
+	"Whitespace",
+	"NonText",
 }
 
 local treesitter_groups = {
@@ -312,6 +315,8 @@ local function extract_colorscheme_colors(theme)
 			local key = string.lower(string.gsub(group, "@", ""))
 			if key == "cursorline" then
 				key = "highlighted"
+			elseif key == "linenr" then
+				key = "line_nr"
 			end
 			highlights[key] = style
 		end